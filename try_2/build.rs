@@ -0,0 +1,28 @@
+// Bettet Build-Infos ein, die das Developer-Fenster anzeigt
+
+use std::process::Command;
+
+fn main() {
+    let git_hash = output_of("git", &["rev-parse", "--short", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = output_of(&rustc, &["--version"]).unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=GIT_HASH={}", git_hash);
+    println!("cargo:rustc-env=RUSTC_VERSION={}", rustc_version);
+
+    // neu bauen, wenn sich der Commit ändert
+    if let Some(git_dir) = output_of("git", &["rev-parse", "--git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        println!("cargo:rerun-if-changed={}/refs", git_dir);
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+fn output_of(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    Some(text.trim().to_string())
+}
@@ -0,0 +1,376 @@
+//! `config.toml`: defaults the organizers set once per event.
+
+use std::path::{Path, PathBuf};
+
+/// Languages the UI can be shown in.
+pub const LANGUAGES: [&str; 2] = ["en", "de"];
+
+/// Every field has a default, so a missing file or key is never an error.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    /// Zielzahl the dev window starts with
+    pub target_number: i32,
+    pub winner_count: usize,
+    /// tried before the built-in search paths
+    pub background_image: Option<PathBuf>,
+    /// first start only, later the last window size wins
+    pub window_width: f32,
+    pub window_height: f32,
+    /// where Excel exports go, the working directory if unset
+    pub export_folder: Option<PathBuf>,
+    /// UI language until one is picked in the dev window, see `i18n::Lang`
+    pub language: String,
+    /// e.g. "Ctrl+E" or "Ctrl+Shift+F5", exports without the dev window
+    pub export_shortcut: String,
+    /// set with `set-password`; the dev and table windows ask for it
+    pub admin_password_hash: Option<String>,
+    /// `set-password --operator`: table window only, no draws or exports
+    pub operator_password_hash: Option<String>,
+    /// wrong passwords in a row before the prompt locks
+    pub unlock_attempts: u32,
+    pub lockout_secs: u64,
+    /// the password is asked again after this long without input in the
+    /// dev or table window, 0 never
+    pub admin_idle_secs: u64,
+    /// fullscreen without window frame; closing needs Ctrl+Shift+Alt+Q and
+    /// the admin password
+    pub kiosk: bool,
+    /// "ANNA SCHMIDT" is stored as "Anna Schmidt", see `title_case`; off by
+    /// default, which keeps names as typed
+    pub title_case_names: bool,
+    /// event colors, see `ThemeConfig`
+    pub theme: ThemeConfig,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            target_number: 300,
+            winner_count: 5,
+            background_image: None,
+            window_width: 800.0,
+            window_height: 600.0,
+            export_folder: None,
+            language: "en".to_string(),
+            export_shortcut: "Ctrl+E".to_string(),
+            admin_password_hash: None,
+            operator_password_hash: None,
+            unlock_attempts: 5,
+            lockout_secs: 60,
+            admin_idle_secs: 300,
+            kiosk: false,
+            title_case_names: false,
+            theme: ThemeConfig::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// `config.toml` next to the executable.
+    pub fn default_path() -> PathBuf {
+        std::env::current_exe()
+            .map(|exe| exe.with_file_name("config.toml"))
+            .unwrap_or_else(|_| PathBuf::from("config.toml"))
+    }
+
+    /// Reads and checks `path`; a missing file gives the defaults.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(AppConfig::default()),
+            Err(e) => return Err(format!("Could not read {}: {}", path.display(), e)),
+        };
+        let config: AppConfig = toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        config.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+    }
+
+    /// Names the first bad value and what would be accepted.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.target_number < 1 {
+            return Err(format!("target_number must be at least 1, got {}", self.target_number));
+        }
+        if !(1..=100).contains(&self.winner_count) {
+            return Err(format!("winner_count must be between 1 and 100, got {}", self.winner_count));
+        }
+        if self.window_width < 640.0 || self.window_height < 480.0 {
+            return Err(format!(
+                "window must be at least 640x480, got {}x{}",
+                self.window_width, self.window_height
+            ));
+        }
+        if !LANGUAGES.contains(&self.language.as_str()) {
+            return Err(format!("language must be one of {:?}, got {:?}", LANGUAGES, self.language));
+        }
+        if parse_shortcut(&self.export_shortcut).is_none() {
+            return Err(format!(
+                "export_shortcut must look like \"Ctrl+E\" (letter, digit or F1-F12), got {:?}",
+                self.export_shortcut
+            ));
+        }
+        if let Some(hash) = &self.admin_password_hash
+            && !crate::password::is_password_hash(hash)
+        {
+            return Err("admin_password_hash is not a hash, set the password with `set-password`".to_string());
+        }
+        if let Some(hash) = &self.operator_password_hash {
+            if !crate::password::is_password_hash(hash) {
+                return Err("operator_password_hash is not a hash, set it with `set-password --operator`".to_string());
+            }
+            // without an admin password everyone is admin anyway
+            if self.admin_password_hash.is_none() {
+                return Err("operator_password_hash needs an admin_password_hash as well".to_string());
+            }
+        }
+        if self.unlock_attempts < 1 {
+            return Err("unlock_attempts must be at least 1, got 0".to_string());
+        }
+        self.theme.colors()?;
+        Ok(())
+    }
+}
+
+/// `[theme]`: this year's colors as "#rrggbb" or "#rrggbbaa". Whatever is
+/// left out keeps the dev window's preset, or the built-in look that
+/// follows the light/dark setting.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// selected widgets, links and info toasts
+    pub primary: Option<String>,
+    /// registration form frame; keep it see-through for the background picture
+    pub form_background: Option<String>,
+    /// winner rows in the table with the standard palette
+    pub winner_highlight: Option<String>,
+    pub success: Option<String>,
+    pub error: Option<String>,
+    /// replaces the standard palette's red-green gradient in the table
+    pub distance_steps: Vec<DistanceStep>,
+}
+
+/// Distances up to `up_to` times the largest one get `color`; anything
+/// beyond the last step gets the last color.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DistanceStep {
+    pub up_to: f32,
+    pub color: String,
+}
+
+/// `ThemeConfig` parsed, `None` where the built-in color stays.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThemeColors {
+    pub primary: Option<egui::Color32>,
+    pub form_background: Option<egui::Color32>,
+    pub winner_highlight: Option<egui::Color32>,
+    pub success: Option<egui::Color32>,
+    pub error: Option<egui::Color32>,
+    /// (up_to, color), ascending
+    pub distance_steps: Vec<(f32, egui::Color32)>,
+}
+
+impl ThemeConfig {
+    /// Names the first key that is not a color or a step out of order.
+    pub fn colors(&self) -> Result<ThemeColors, String> {
+        let color = |key: &str, text: &str| {
+            parse_color(text)
+                .ok_or_else(|| format!("theme.{} must look like \"#1e1e23\" or \"#1e1e2380\", got {:?}", key, text))
+        };
+        let optional = |key: &str, value: &Option<String>| value.as_deref().map(|text| color(key, text)).transpose();
+        let mut steps = Vec::with_capacity(self.distance_steps.len());
+        for step in &self.distance_steps {
+            let previous = steps.last().map_or(0.0, |&(up_to, _)| up_to);
+            if !(step.up_to > previous && step.up_to <= 1.0) {
+                return Err(format!(
+                    "theme.distance_steps must rise from above 0 to at most 1, got up_to = {} after {}",
+                    step.up_to, previous
+                ));
+            }
+            steps.push((step.up_to, color("distance_steps.color", &step.color)?));
+        }
+        Ok(ThemeColors {
+            primary: optional("primary", &self.primary)?,
+            form_background: optional("form_background", &self.form_background)?,
+            winner_highlight: optional("winner_highlight", &self.winner_highlight)?,
+            success: optional("success", &self.success)?,
+            error: optional("error", &self.error)?,
+            distance_steps: steps,
+        })
+    }
+}
+
+impl ThemeColors {
+    /// These colors, with `base`'s wherever one is unset.
+    pub fn or(self, base: ThemeColors) -> ThemeColors {
+        ThemeColors {
+            primary: self.primary.or(base.primary),
+            form_background: self.form_background.or(base.form_background),
+            winner_highlight: self.winner_highlight.or(base.winner_highlight),
+            success: self.success.or(base.success),
+            error: self.error.or(base.error),
+            distance_steps: if self.distance_steps.is_empty() { base.distance_steps } else { self.distance_steps },
+        }
+    }
+
+    /// Color for a distance that is `fraction` of the largest, if there are steps.
+    pub fn distance_color(&self, fraction: f32) -> Option<egui::Color32> {
+        self.distance_steps
+            .iter()
+            .find(|&&(up_to, _)| fraction <= up_to)
+            .or(self.distance_steps.last())
+            .map(|&(_, color)| color)
+    }
+}
+
+/// "#rrggbb", or "#rrggbbaa" with alpha; the "#" may be left out.
+pub fn parse_color(text: &str) -> Option<egui::Color32> {
+    let hex = text.trim().trim_start_matches('#');
+    if !matches!(hex.len(), 6 | 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some(egui::Color32::from_rgba_unmultiplied(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
+
+/// "Ctrl+Shift+E" and the like; modifiers in any order and case.
+pub fn parse_shortcut(text: &str) -> Option<egui::KeyboardShortcut> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key = parse_key(parts.pop()?)?;
+    let mut modifiers = egui::Modifiers::NONE;
+    for part in parts {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" => modifiers.ctrl = true,
+            "shift" => modifiers.shift = true,
+            "alt" => modifiers.alt = true,
+            _ => return None,
+        }
+    }
+    // ctrl doubles as command, the way egui reports it on Windows and Linux
+    modifiers.command = modifiers.ctrl;
+    Some(egui::KeyboardShortcut::new(modifiers, key))
+}
+
+fn parse_key(name: &str) -> Option<egui::Key> {
+    use egui::Key::*;
+    const LETTERS: [egui::Key; 26] = [A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z];
+    const DIGITS: [egui::Key; 10] = [Num0, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9];
+    const F_KEYS: [egui::Key; 12] = [F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12];
+    let name = name.to_ascii_uppercase();
+    match name.as_bytes() {
+        [c @ b'A'..=b'Z'] => Some(LETTERS[(c - b'A') as usize]),
+        [c @ b'0'..=b'9'] => Some(DIGITS[(c - b'0') as usize]),
+        [b'F', ..] => name[1..].parse::<usize>().ok().and_then(|n| F_KEYS.get(n.checked_sub(1)?).copied()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_keys_and_files_fall_back_to_defaults() {
+        let config: AppConfig = toml::from_str("winner_count = 3").unwrap();
+        assert_eq!(config, AppConfig { winner_count: 3, ..AppConfig::default() });
+
+        let missing = std::env::temp_dir().join("winter_raten_no_such_config.toml");
+        assert_eq!(AppConfig::load(&missing).unwrap(), AppConfig::default());
+    }
+
+    #[test]
+    fn bad_values_are_named() {
+        let bad = AppConfig { language: "fr".to_string(), ..AppConfig::default() };
+        assert!(bad.validate().unwrap_err().contains("language"));
+        let bad = AppConfig { winner_count: 0, ..AppConfig::default() };
+        assert!(bad.validate().unwrap_err().contains("winner_count"));
+        assert!(toml::from_str::<AppConfig>("winners = 3").is_err());
+        let bad = AppConfig { export_shortcut: "Ctrl+Enter".to_string(), ..AppConfig::default() };
+        assert!(bad.validate().unwrap_err().contains("export_shortcut"));
+        let bad = AppConfig { admin_password_hash: Some("geheim".to_string()), ..AppConfig::default() };
+        assert!(bad.validate().unwrap_err().contains("admin_password_hash"));
+        let operator_only = AppConfig {
+            operator_password_hash: Some(format!("pbkdf2-sha256$1000${}${}", "00".repeat(16), "00".repeat(32))),
+            ..AppConfig::default()
+        };
+        assert!(operator_only.validate().unwrap_err().contains("admin_password_hash"));
+    }
+
+    #[test]
+    fn shortcuts_parse() {
+        let ctrl_e = parse_shortcut("Ctrl+E").unwrap();
+        assert_eq!(ctrl_e.key, egui::Key::E);
+        assert!(ctrl_e.modifiers.ctrl && !ctrl_e.modifiers.shift);
+        let f5 = parse_shortcut("shift + ctrl + f5").unwrap();
+        assert_eq!(f5.key, egui::Key::F5);
+        assert!(f5.modifiers.shift);
+        assert!(parse_shortcut("Hyper+E").is_none());
+        assert!(parse_shortcut("Ctrl+F13").is_none());
+        assert!(parse_shortcut("").is_none());
+    }
+
+    #[test]
+    fn theme_colors_parse_and_are_checked() {
+        assert_eq!(parse_color("#ff8000"), Some(egui::Color32::from_rgb(255, 128, 0)));
+        assert_eq!(parse_color("1E1E2380"), Some(egui::Color32::from_rgba_unmultiplied(30, 30, 35, 128)));
+        assert_eq!(parse_color("#fff"), None);
+        assert_eq!(parse_color("#gg0000"), None);
+
+        let config: AppConfig = toml::from_str(
+            "[theme]\nwinner_highlight = \"#b01030\"\n\
+             [[theme.distance_steps]]\nup_to = 0.1\ncolor = \"#00ff00\"\n\
+             [[theme.distance_steps]]\nup_to = 0.5\ncolor = \"#ffff00\"\n",
+        )
+        .unwrap();
+        let colors = config.theme.colors().unwrap();
+        assert_eq!(colors.winner_highlight, Some(egui::Color32::from_rgb(176, 16, 48)));
+        assert_eq!(colors.primary, None);
+        assert_eq!(colors.distance_color(0.0), Some(egui::Color32::GREEN));
+        assert_eq!(colors.distance_color(0.3), Some(egui::Color32::YELLOW));
+        assert_eq!(colors.distance_color(1.0), Some(egui::Color32::YELLOW));
+        assert_eq!(ThemeColors::default().distance_color(0.0), None);
+
+        let base = ThemeColors {
+            primary: Some(egui::Color32::RED),
+            winner_highlight: Some(egui::Color32::BLUE),
+            ..ThemeColors::default()
+        };
+        let layered = colors.or(base);
+        assert_eq!(layered.primary, Some(egui::Color32::RED));
+        assert_eq!(layered.winner_highlight, Some(egui::Color32::from_rgb(176, 16, 48)));
+        assert_eq!(layered.distance_steps.len(), 2);
+
+        let bad = AppConfig {
+            theme: ThemeConfig { error: Some("red".to_string()), ..ThemeConfig::default() },
+            ..AppConfig::default()
+        };
+        assert!(bad.validate().unwrap_err().contains("theme.error"));
+        let step = |up_to| DistanceStep { up_to, color: "#ffffff".to_string() };
+        let unordered = ThemeConfig { distance_steps: vec![step(0.5), step(0.2)], ..ThemeConfig::default() };
+        assert!(unordered.colors().unwrap_err().contains("distance_steps"));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("winter_raten_config_{}.toml", std::process::id()));
+        let config = AppConfig {
+            target_number: 250,
+            export_folder: Some(PathBuf::from("exports")),
+            theme: ThemeConfig {
+                primary: Some("#3c5a8c".to_string()),
+                distance_steps: vec![DistanceStep { up_to: 1.0, color: "#ffffff".to_string() }],
+                ..ThemeConfig::default()
+            },
+            ..AppConfig::default()
+        };
+        config.save(&path).unwrap();
+        assert_eq!(AppConfig::load(&path).unwrap(), config);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,117 @@
+//! Crash reports: a panic writes `crash_<timestamp>.txt` to the log folder,
+//! and the next start offers to open it.
+
+use std::backtrace::Backtrace;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::config::AppConfig;
+use crate::db::unix_now;
+
+/// Replaces the default panic output with a report file. If the file can't
+/// be written the report goes to stderr instead.
+pub fn install_hook(dir: PathBuf, config: &AppConfig, db_path: &Path) {
+    let settings = settings_snapshot(config, db_path);
+    std::panic::set_hook(Box::new(move |info| {
+        let report = format!(
+            "Snow Drift Registration v{} ({})\nat {}\n\n{}\n\nBacktrace:\n{}\n\nSettings:\n{}",
+            env!("CARGO_PKG_VERSION"),
+            env!("GIT_HASH"),
+            unix_now(),
+            info,
+            Backtrace::force_capture(),
+            settings,
+        );
+        match write_report(&dir, &report) {
+            Ok(path) => {
+                log::error!("Crashed, report in {}: {}", path.display(), info);
+                eprintln!("{}\nCrash report written to {}", info, path.display());
+            }
+            Err(e) => eprintln!("{}\n(could not write the crash report: {})", report, e),
+        }
+    }));
+}
+
+/// The config without paths, which can carry user names, or password
+/// hashes, and the database file name only.
+fn settings_snapshot(config: &AppConfig, db_path: &Path) -> String {
+    let redact = |path: &Option<PathBuf>| path.as_ref().map(|_| PathBuf::from("<set>"));
+    let redact_hash = |hash: &Option<String>| hash.as_ref().map(|_| "<redacted>".to_string());
+    let config = AppConfig {
+        background_image: redact(&config.background_image),
+        export_folder: redact(&config.export_folder),
+        admin_password_hash: redact_hash(&config.admin_password_hash),
+        operator_password_hash: redact_hash(&config.operator_password_hash),
+        ..config.clone()
+    };
+    let db_name = db_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    format!("db = {:?}\n{}", db_name, toml::to_string(&config).unwrap_or_default())
+}
+
+fn write_report(dir: &Path, report: &str) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("crash_{}.txt", unix_now()));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+/// Newest report nobody has dismissed yet.
+pub fn unseen_report(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with("crash_") && name.ends_with(".txt")
+        })
+        // same length names, so the timestamps sort as text
+        .max()
+}
+
+/// Keeps the report but stops offering it, by renaming it to `.seen`.
+pub fn mark_seen(report: &Path) -> io::Result<()> {
+    std::fs::rename(report, report.with_extension("seen"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newest_report_is_offered_until_dismissed() {
+        let dir = std::env::temp_dir().join(format!("winter_raten_crashes_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(unseen_report(&dir), None);
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("crash_1700000000.txt"), "old").unwrap();
+        std::fs::write(dir.join("winter-2024-12-24.log"), "").unwrap();
+        let newest = write_report(&dir, "boom").unwrap();
+        assert_eq!(unseen_report(&dir), Some(newest.clone()));
+
+        mark_seen(&newest).unwrap();
+        assert_eq!(unseen_report(&dir), Some(dir.join("crash_1700000000.txt")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn snapshot_leaves_out_paths() {
+        let config = AppConfig { export_folder: Some(PathBuf::from("C:/Users/anna/Desktop")), ..AppConfig::default() };
+        let snapshot = settings_snapshot(&config, Path::new("C:/Users/anna/registrations.db"));
+        assert!(!snapshot.contains("anna"), "{}", snapshot);
+        assert!(snapshot.contains("registrations.db") && snapshot.contains("winner_count = 5"), "{}", snapshot);
+    }
+
+    #[test]
+    fn snapshot_leaves_out_password_hashes() {
+        let config = AppConfig {
+            admin_password_hash: Some(crate::password::hash_password("geheim")),
+            operator_password_hash: Some(crate::password::hash_password("kasse")),
+            ..AppConfig::default()
+        };
+        let snapshot = settings_snapshot(&config, Path::new("registrations.db"));
+        for hash in [&config.admin_password_hash, &config.operator_password_hash] {
+            assert!(!snapshot.contains(hash.as_deref().unwrap()), "{}", snapshot);
+        }
+        assert!(snapshot.contains("admin_password_hash = \"<redacted>\""), "{}", snapshot);
+    }
+}
@@ -0,0 +1,2805 @@
+//! Registrations in SQLite: schema, migrations and everything the app reads or writes.
+//!
+//! Two booths may share one file. Writes wait for each other (`write_transaction`),
+//! other writers show up through `data_revision`, and a previewed draw is
+//! only committed if nothing changed since. Limits: WAL needs shared memory,
+//! so every process using the file must run on the machine that holds it;
+//! on SMB/NFS shares the locks SQLite relies on may be broken and the file
+//! can be corrupted.
+
+use rusqlite::{Connection, Result as SqlResult, Transaction, TransactionBehavior};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use serde_json::json;
+use crate::draw::{
+    RankedWinner, WinnerMode, closest_candidate, distance, pick_winners, sort_for_table, weighted_candidate,
+};
+use crate::export::ExportColumn;
+use crate::form::validate_form;
+use crate::i18n::tr;
+use crate::tr;
+
+/// How far we got contacting a winner.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyStatus {
+    #[default]
+    None,
+    Emailed,
+    Confirmed,
+    Unreachable,
+}
+
+impl NotifyStatus {
+    /// In the order the table offers them.
+    pub const ALL: [NotifyStatus; 4] = [
+        NotifyStatus::None,
+        NotifyStatus::Emailed,
+        NotifyStatus::Confirmed,
+        NotifyStatus::Unreachable,
+    ];
+
+    /// Value stored in the `notified` column.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotifyStatus::None => "none",
+            NotifyStatus::Emailed => "emailed",
+            NotifyStatus::Confirmed => "confirmed",
+            NotifyStatus::Unreachable => "unreachable",
+        }
+    }
+
+    /// Inverse of `as_str`, unknown values read as `None`.
+    pub fn parse(value: &str) -> Self {
+        Self::ALL.into_iter().find(|s| s.as_str() == value).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+/// A stored registration.
+pub struct User {
+    pub id: i32,
+    pub first_name: String,
+    pub surname: String,
+    pub email: String,
+    pub number: i32,
+    pub winner: bool,
+    pub prize_index: Option<i32>,
+    pub notified: NotifyStatus,
+    /// "entry #" shown to the participant, stable across deletions
+    pub ticket_number: i32,
+}
+
+#[derive(Clone, Default)]
+/// One place of the prize list.
+pub struct Prize {
+    /// 1 = first place
+    pub rank: i32,
+    pub name: String,
+    pub description: String,
+}
+
+/// Summary of all guesses, `None` fields when nobody registered yet.
+#[derive(Clone, Default)]
+pub struct NumberStats {
+    pub count: i64,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+}
+
+/// A registration that has not been stored yet.
+pub struct NewUser {
+    pub first_name: String,
+    pub surname: String,
+    pub email: String,
+    pub number: i32,
+    /// generated by the dev window, removed again by "Delete test data"
+    pub is_test: bool,
+}
+
+/// A row read back from an export, still unchecked. `number` stays text so
+/// a typo is reported like one on the form.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedUser {
+    /// row in the sheet, for the report
+    pub row: usize,
+    /// None for rows added in the sheet
+    pub id: Option<i32>,
+    pub first_name: String,
+    pub surname: String,
+    pub email: String,
+    pub number: String,
+}
+
+/// Why an imported row of a deleted registration was left alone.
+fn in_recycle_bin(id: i32) -> String {
+    tr!("registration {} is in the recycle bin, restore it first", id)
+}
+
+/// One row of `upsert_users`: checked, left alone if it is in the recycle
+/// bin or unchanged, else written through `update` or `insert`. Only a
+/// failed lookup is an error, a refused write is reported as `Failed`.
+fn upsert_outcome<S: UserStore + ?Sized>(
+    store: &S,
+    row: &ImportedUser,
+    guesses: &RangeInclusive<i32>,
+    deleted: &HashSet<i32>,
+    update: impl FnOnce(i32, &str, &str, &str, i32) -> SqlResult<()>,
+    insert: impl FnOnce(&str, &str, &str, i32) -> SqlResult<(i32, i32)>,
+) -> SqlResult<RowOutcome> {
+    let fields = [row.first_name.as_str(), &row.surname, &row.email, &row.number];
+    if let Some(error) = validate_form(fields, guesses).first() {
+        return Ok(RowOutcome::Skipped(error.message.clone()));
+    }
+    let [first_name, surname, email, number] = fields.map(str::trim);
+    let number: i32 = number.parse().unwrap_or_default();
+    let existing = match row.id {
+        Some(id) => store.get_user(id)?,
+        None => None,
+    };
+    let stored = match existing {
+        Some(u) if deleted.contains(&u.id) => Ok(RowOutcome::Skipped(in_recycle_bin(u.id))),
+        Some(u) if (u.first_name.as_str(), u.surname.as_str(), u.email.as_str(), u.number)
+            == (first_name, surname, email, number) => Ok(RowOutcome::Unchanged),
+        Some(u) => update(u.id, first_name, surname, email, number).map(|_| RowOutcome::Updated(u.id)),
+        None => insert(first_name, surname, email, number).map(|(_, ticket)| RowOutcome::Inserted(ticket)),
+    };
+    Ok(stored.unwrap_or_else(|e| RowOutcome::Failed(e.to_string())))
+}
+
+/// What became of one row handed to `insert_users_batch` or `upsert_users`.
+#[derive(Debug, PartialEq)]
+pub enum RowOutcome {
+    /// stored under this entry number
+    Inserted(i32),
+    /// existing registration with this id was corrected
+    Updated(i32),
+    /// matched a registration that already had these values
+    Unchanged,
+    /// refused by the form rules, or left alone because it is in the
+    /// recycle bin, before touching the database
+    Skipped(String),
+    /// refused by the database, e.g. an email that is already registered
+    Failed(String),
+}
+
+/// Per-row results of a batch insert, in input order.
+pub struct BatchReport {
+    pub rows: Vec<RowOutcome>,
+}
+
+impl BatchReport {
+    /// Rows that were stored.
+    pub fn inserted(&self) -> usize {
+        self.rows.iter().filter(|r| matches!(r, RowOutcome::Inserted(_))).count()
+    }
+
+    pub fn updated(&self) -> usize {
+        self.rows.iter().filter(|r| matches!(r, RowOutcome::Updated(_))).count()
+    }
+
+    /// Rows that were skipped or failed.
+    pub fn rejected(&self) -> usize {
+        self.rows.iter().filter(|r| matches!(r, RowOutcome::Skipped(_) | RowOutcome::Failed(_))).count()
+    }
+}
+
+/// One past calculation, as listed in the draw history.
+pub struct Draw {
+    pub id: i64,
+    /// 1-based count of the draws in this event; ids skip after deletions
+    pub round: i64,
+    pub created_at: i64,
+    pub mode: String,
+    pub targets: String,
+    pub winner_count: i32,
+    pub seed: Option<i64>,
+    pub participant_count: i32,
+}
+
+/// A winner of a past draw, including ones that were re-rolled away.
+pub struct DrawWinner {
+    pub user_id: i32,
+    pub name: String,
+    pub number: i32,
+    pub rank: i32,
+    pub distance: i64,
+    pub replaced_reason: Option<String>,
+}
+
+/// The SQLite file holding registrations, draws and the audit log.
+pub struct Database {
+    conn: Connection,
+    path: PathBuf,
+    /// written into every audit entry, see `set_actor`
+    actor: Option<String>,
+}
+
+/// One row of the append-only audit log.
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: i64,
+    pub action: String,
+    pub user_id: Option<i32>,
+    /// JSON, usually with "old" and/or "new" values
+    pub details: String,
+    /// role that was unlocked, None for the public form and the CLI
+    pub actor: Option<String>,
+}
+
+/// Where the booth window was when it was last closed, in points.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct WindowGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Dev window inputs from the last run. Fields that were never saved stay
+/// None, so `config.toml` still decides them.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DevSettings {
+    pub targets: Option<String>,
+    pub winner_count: Option<String>,
+    pub mode: Option<String>,
+    pub exclude_previous: Option<bool>,
+    pub number_hint: Option<bool>,
+    pub auto_refresh: Option<bool>,
+    pub simple_snow: Option<bool>,
+    pub show_target: Option<bool>,
+    pub touch_mode: Option<bool>,
+    pub touch_keyboard: Option<bool>,
+    pub anonymize_export: Option<bool>,
+    pub export_initials: Option<bool>,
+    /// made up on the first anonymized export, kept so hashes stay comparable
+    pub export_salt: Option<String>,
+    /// "en", "de", see `i18n::Lang::code`
+    pub language: Option<String>,
+    /// (column, exported) in file order, see `export::column_spec`
+    pub export_columns: Option<Vec<(ExportColumn, bool)>>,
+    /// "system", "dark", "light"
+    pub theme: Option<String>,
+    /// "christmas", "new_year", "neutral" or "none", see `ui::ThemePreset`
+    pub theme_preset: Option<String>,
+    pub snow_count: Option<usize>,
+    /// (far, near) flakes, see `ui::Backdrop`
+    pub snow_speed: Option<(f32, f32)>,
+    pub snow_size: Option<(f32, f32)>,
+    /// premultiplied RGBA, shown without a background picture
+    pub background_color: Option<[u8; 4]>,
+    pub flat_background: Option<bool>,
+    pub dev_window: Option<ToolWindow>,
+    pub table_window: Option<ToolWindow>,
+}
+
+/// Whether a tool window was open and where, relative to the booth window.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ToolWindow {
+    pub open: bool,
+    pub rect: Option<WindowGeometry>,
+}
+
+/// Everything the "Database" panel shows, fetched in one go.
+pub struct DbStats {
+    pub registrations: i64,
+    pub winners: i64,
+    pub distinct_emails: i64,
+    pub first_created: Option<i64>,
+    pub last_created: Option<i64>,
+    pub path: PathBuf,
+    /// `None` for in-memory databases
+    pub file_size: Option<u64>,
+    pub journal_mode: String,
+    pub synchronous: String,
+    pub foreign_keys: bool,
+    pub busy_timeout_ms: i64,
+}
+
+/// Wait between two submissions until the organizers change it.
+pub const DEFAULT_SUBMIT_INTERVAL_SECS: u64 = 2;
+
+/// Everything the UI needs from storage. `Database` is the real thing;
+/// the defaults cover what only SQLite offers (backups, history, audit),
+/// so a store for tests only has to keep a list of users.
+pub trait UserStore {
+    /// Stores a registration, returns its (id, entry number).
+    fn insert_user(&self, firstname: &str, surname: &str, email: &str, number: i32) -> SqlResult<(i32, i32)>;
+    /// Stores many registrations at once, bad rows are reported instead of stored.
+    fn insert_users_batch(&self, users: &[NewUser]) -> SqlResult<BatchReport>;
+    /// `insert_users_batch` for long imports: `progress` gets the number of
+    /// rows done before each row, and returning false cancels the whole
+    /// batch, which gives None.
+    fn insert_users_batch_with(&self, users: &[NewUser], progress: &mut dyn FnMut(usize) -> bool) -> SqlResult<Option<BatchReport>> {
+        if !progress(0) {
+            return Ok(None);
+        }
+        self.insert_users_batch(users).map(Some)
+    }
+    /// Applies rows read back from an export: a known id is corrected in
+    /// place, anything else is added as a new registration. Rows of
+    /// registrations in the recycle bin are skipped; restoring is up to
+    /// the recycle bin.
+    fn upsert_users(&self, rows: &[ImportedUser]) -> SqlResult<BatchReport> {
+        let guesses = self.guess_range()?;
+        let deleted: HashSet<i32> = self.get_deleted_users()?.into_iter().map(|u| u.id).collect();
+        let mut outcomes = Vec::with_capacity(rows.len());
+        for row in rows {
+            outcomes.push(upsert_outcome(
+                self, row, &guesses, &deleted,
+                |id, first_name, surname, email, number| self.update_user(id, first_name, surname, email, number),
+                |first_name, surname, email, number| self.insert_user(first_name, surname, email, number),
+            )?);
+        }
+        Ok(BatchReport { rows: outcomes })
+    }
+    /// Corrects a registration's details; winner state is left alone.
+    fn update_user(&self, id: i32, firstname: &str, surname: &str, email: &str, number: i32) -> SqlResult<()>;
+    /// Untouched since it was inserted: not edited, won, notified or deleted.
+    fn undoable(&self, id: i32) -> SqlResult<bool>;
+    /// Takes back a fresh registration, None if it is no longer `undoable`.
+    fn undo_registration(&self, id: i32) -> SqlResult<Option<User>>;
+    /// Moves a registration to the recycle bin.
+    fn delete_user(&self, id: i32) -> SqlResult<()>;
+    /// Takes a registration out of the recycle bin, false if its email is taken again.
+    fn restore_user(&self, id: i32) -> SqlResult<bool>;
+    /// Brings back a deleted registration with the values it had, winner flag included.
+    fn undelete_user(&self, user: &User) -> SqlResult<bool>;
+    /// Removes a registration from the recycle bin for good.
+    fn purge_user(&self, id: i32) -> SqlResult<()>;
+    /// Live registrations by id, without the recycle bin.
+    fn get_all_users(&self) -> SqlResult<Vec<User>>;
+    /// The recycle bin.
+    fn get_deleted_users(&self) -> SqlResult<Vec<User>>;
+    /// Any registration, including ones in the recycle bin.
+    fn get_user(&self, id: i32) -> SqlResult<Option<User>>;
+    /// Changes whenever registrations are added or removed.
+    fn registration_revision(&self) -> SqlResult<(i64, i64)>;
+    /// Bumped by every write, whichever process made it.
+    fn data_revision(&self) -> SqlResult<i64> {
+        Err(unsupported())
+    }
+
+    /// Runs a draw and marks its winners, one per target.
+    fn calculate_winners(&self, targets: &[i32], exclude_previous: bool, mode: WinnerMode, seed: u64) -> SqlResult<()>;
+    /// Commits a previewed draw, or returns false and changes nothing if any
+    /// data changed since the preview read `revision`.
+    fn calculate_winners_if_unchanged(
+        &self,
+        revision: i64,
+        targets: &[i32],
+        exclude_previous: bool,
+        mode: WinnerMode,
+        seed: u64,
+    ) -> SqlResult<bool> {
+        if self.data_revision()? != revision {
+            return Ok(false);
+        }
+        self.calculate_winners(targets, exclude_previous, mode, seed)?;
+        Ok(true)
+    }
+    /// Jury override: sets the winner flag by hand, outside of any draw.
+    fn set_winner(&self, id: i32, winner: bool) -> SqlResult<()>;
+    /// Records how far contacting a winner got.
+    fn set_notified(&self, id: i32, status: NotifyStatus) -> SqlResult<()>;
+    /// True if a winner flag was set by hand since the last draw.
+    fn has_manual_overrides(&self) -> SqlResult<bool>;
+    /// Locked winners cannot be recalculated, toggled or deleted.
+    fn winners_locked(&self) -> SqlResult<bool>;
+    /// Locks or unlocks the current winners.
+    fn set_winners_locked(&self, locked: bool) -> SqlResult<()>;
+
+    /// The prize list, first place first.
+    fn get_prizes(&self) -> SqlResult<Vec<Prize>>;
+    /// Replaces the whole prize list; ranks are taken from the slice order.
+    fn save_prizes(&self, prizes: &[Prize]) -> SqlResult<()>;
+    /// Whether the form accepts registrations.
+    fn registration_open(&self) -> SqlResult<bool>;
+    /// Opens or closes the form.
+    fn set_registration_open(&self, open: bool) -> SqlResult<()>;
+    /// When the form closes on its own, as a unix timestamp.
+    fn registration_close_at(&self) -> SqlResult<Option<i64>>;
+    /// Schedules or cancels the automatic close.
+    fn set_registration_close_at(&self, close_at: Option<i64>) -> SqlResult<()>;
+    /// Seconds the booth, or one HTTP client, has to wait between submissions.
+    fn submit_interval_secs(&self) -> SqlResult<u64> {
+        Ok(DEFAULT_SUBMIT_INTERVAL_SECS)
+    }
+    /// Changes the wait between submissions; 0 turns the limit off.
+    fn set_submit_interval_secs(&self, _secs: u64) -> SqlResult<()> {
+        Err(unsupported())
+    }
+    /// Numbers guests may enter; without settings any number from 1 up.
+    fn guess_range(&self) -> SqlResult<RangeInclusive<i32>> {
+        Ok(1..=i32::MAX)
+    }
+    /// Changes the allowed guesses; the caller checks `1 <= min <= max`.
+    fn set_guess_range(&self, _min: i32, _max: i32) -> SqlResult<()> {
+        Err(unsupported())
+    }
+    /// Where new registrations are posted, empty when nobody listens.
+    fn webhook_url(&self) -> SqlResult<String> {
+        Ok(String::new())
+    }
+    /// Sets or, with "", clears the webhook.
+    fn set_webhook_url(&self, _url: &str) -> SqlResult<()> {
+        Err(unsupported())
+    }
+    /// Window position and inner size from the last run.
+    fn window_geometry(&self) -> SqlResult<Option<WindowGeometry>> {
+        Ok(None)
+    }
+    /// Remembers the window for the next start.
+    fn set_window_geometry(&self, _geometry: WindowGeometry) -> SqlResult<()> {
+        Err(unsupported())
+    }
+    /// Dev window inputs from the last run; all None if nothing was saved.
+    fn dev_settings(&self) -> SqlResult<DevSettings> {
+        Ok(DevSettings::default())
+    }
+    fn set_dev_settings(&self, _settings: &DevSettings) -> SqlResult<()> {
+        Err(unsupported())
+    }
+    /// Form fields typed but not submitted when the app last stopped.
+    fn form_draft(&self) -> SqlResult<Option<[String; 4]>> {
+        Ok(None)
+    }
+    /// Saves the half-filled form; all fields empty removes the draft.
+    fn set_form_draft(&self, _fields: &[String; 4]) -> SqlResult<()> {
+        Err(unsupported())
+    }
+
+    /// What `calculate_winners` would pick right now, without writing anything.
+    /// `seed` only matters for the weighted draw, which it makes repeatable.
+    /// The draw itself and `draw --dry-run` both pick through here, so a
+    /// preview can't disagree with the draw.
+    fn preview_winners(
+        &self,
+        targets: &[i32],
+        exclude_previous: bool,
+        mode: WinnerMode,
+        seed: u64,
+    ) -> SqlResult<Vec<RankedWinner>> {
+        let previous = if exclude_previous {
+            self.get_previous_winners()?
+        } else {
+            HashMap::new()
+        };
+        let candidates = self.get_all_users()?
+            .into_iter()
+            .filter(|u| !previous.contains_key(&u.id))
+            .collect();
+        Ok(pick_winners(candidates, targets, mode, seed))
+    }
+
+    /// Live registrations in table order, see `sort_for_table`.
+    fn get_sorted_users(&self, max_number: i32) -> SqlResult<Vec<User>> {
+        let mut users = self.get_all_users()?;
+        sort_for_table(&mut users, max_number);
+        Ok(users)
+    }
+
+    /// (confirmed winners, all winners)
+    fn notification_summary(&self) -> SqlResult<(i64, i64)> {
+        let winners: Vec<User> = self.get_all_users()?.into_iter().filter(|u| u.winner).collect();
+        let confirmed = winners.iter().filter(|u| u.notified == NotifyStatus::Confirmed).count();
+        Ok((confirmed as i64, winners.len() as i64))
+    }
+
+    /// Rounds each user has won so far, keyed by user id. Rounds are
+    /// numbered 1, 2, ... in draw order, like `Draw::round`.
+    fn get_previous_winners(&self) -> SqlResult<HashMap<i32, Vec<i64>>> {
+        Ok(HashMap::new())
+    }
+    /// Forgets who won so far, so "exclude previous winners" starts over.
+    /// The draw history itself stays.
+    fn reset_winner_history(&self) -> SqlResult<()> {
+        Err(unsupported())
+    }
+
+    /// The draw history, newest first.
+    fn get_draws(&self) -> SqlResult<Vec<Draw>> {
+        Ok(Vec::new())
+    }
+
+    /// Everyone a past draw picked.
+    fn get_draw_winners(&self, _draw_id: i64) -> SqlResult<Vec<DrawWinner>> {
+        Ok(Vec::new())
+    }
+
+    /// Who the following changes are logged for, e.g. "operator".
+    fn set_actor(&mut self, _actor: Option<&str>) {}
+
+    /// Newest first, at most `limit` entries.
+    fn get_audit_log(&self, _action: Option<&str>, _user_id: Option<i32>, _limit: usize) -> SqlResult<Vec<AuditEntry>> {
+        Ok(Vec::new())
+    }
+
+    /// The distinct actions in the audit log, for filtering.
+    fn audit_actions(&self) -> SqlResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Replaces one winner with the next best candidate, returns the replacement.
+    fn reroll_winner(&self, _user_id: i32, _reason: &str) -> SqlResult<Option<User>> {
+        Err(unsupported())
+    }
+
+    /// Puts the winner flags back to what a past draw decided.
+    fn restore_draw(&self, _draw_id: i64) -> SqlResult<()> {
+        Err(unsupported())
+    }
+
+    /// Keeps `keep` and moves the duplicates in `remove` to the recycle bin.
+    fn merge_users(&self, _keep: i32, _remove: &[i32]) -> SqlResult<()> {
+        Err(unsupported())
+    }
+
+    /// Removes everything the test-data generator inserted.
+    fn delete_test_users(&self) -> SqlResult<usize> {
+        Err(unsupported())
+    }
+
+    /// Deletes all registrations and the draw history.
+    fn clear_all(&self) -> SqlResult<usize> {
+        Err(unsupported())
+    }
+
+    /// Name of the running event, shown in the title bar and export file names.
+    fn event_name(&self) -> SqlResult<String> {
+        Ok(String::new())
+    }
+    fn set_event_name(&self, _name: &str) -> SqlResult<()> {
+        Err(unsupported())
+    }
+
+    /// Everything between two events in one go: winners unlocked, all
+    /// registrations and draws gone (see `clear_all`), the form open without
+    /// a deadline, and the new name. Returns the registrations removed.
+    fn start_new_event(&self, name: &str) -> SqlResult<usize> {
+        // the lock would refuse deleting the old winners
+        self.set_winners_locked(false)?;
+        let removed = self.clear_all()?;
+        self.set_registration_open(true)?;
+        self.set_registration_close_at(None)?;
+        self.set_event_name(name)?;
+        Ok(removed)
+    }
+
+    /// What the "Database" panel shows.
+    fn stats(&self) -> SqlResult<DbStats> {
+        Err(unsupported())
+    }
+
+    /// Whether everything is gone once the app closes.
+    fn is_in_memory(&self) -> bool {
+        false
+    }
+
+    /// Summary of all live guesses.
+    fn number_stats(&self) -> SqlResult<NumberStats> {
+        Err(unsupported())
+    }
+
+    /// (bin start, count) for every non-empty bin.
+    fn number_histogram(&self, _bin_width: i64) -> SqlResult<Vec<(i64, i64)>> {
+        Err(unsupported())
+    }
+
+    /// Copies the whole store to `path`.
+    fn backup_to(&self, _path: &Path) -> SqlResult<()> {
+        Err(unsupported())
+    }
+
+    /// Replaces the whole store with the backup at `path`.
+    fn restore_from(&mut self, _path: &Path) -> SqlResult<()> {
+        Err(unsupported())
+    }
+}
+
+/// For the `UserStore` defaults a store doesn't implement.
+fn unsupported() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("Not supported by this store".to_string()),
+    )
+}
+
+impl Database {
+    /// `insert_user` inside the caller's transaction; gives id and entry number.
+    fn insert_user_row(&self, firstname: &str, surname: &str, email: &str, number: i32) -> SqlResult<(i32, i32)> {
+        let ticket = self.next_ticket_number()?;
+        self.conn.execute(
+            "INSERT INTO users (first_name, surname, email, number, winner, ticket_number, created_at)
+             VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6)",
+            rusqlite::params![firstname, surname, email, number, ticket, unix_now()],
+        )?;
+        let id = self.conn.last_insert_rowid() as i32;
+        self.log_action("insert", Some(id), json!({
+            "new": { "first_name": firstname, "surname": surname, "email": email, "number": number, "ticket_number": ticket }
+        }))?;
+        Ok((id, ticket))
+    }
+
+    /// `update_user` inside the caller's transaction.
+    fn update_user_row(&self, id: i32, firstname: &str, surname: &str, email: &str, number: i32) -> SqlResult<()> {
+        let Some(old) = self.get_user(id)? else {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        };
+        self.conn.execute(
+            "UPDATE users SET first_name = ?1, surname = ?2, email = ?3, number = ?4 WHERE id = ?5",
+            rusqlite::params![firstname, surname, email, number, id],
+        )?;
+        self.log_action("update", Some(id), json!({
+            "old": { "first_name": old.first_name, "surname": old.surname, "email": old.email, "number": old.number },
+            "new": { "first_name": firstname, "surname": surname, "email": email, "number": number },
+        }))
+    }
+
+    /// Opens (or creates) the database file and brings its schema up to
+    /// date; ":memory:" gives a throwaway one.
+    pub fn open(path: &Path) -> SqlResult<Self> {
+        let conn = Connection::open(path)?;
+        // WAL lets readers (exports, a second reporting process) work while we write
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        migrate(&conn)?;
+        Ok(Database { conn, path: path.to_path_buf(), actor: None })
+    }
+
+    /// Makes sure `path` is one of our databases before it replaces the live one.
+    pub fn check_backup_schema(path: &Path) -> Result<(), String> {
+        let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("Cannot open backup: {}", e))?;
+
+        let mut stmt = conn.prepare("SELECT name FROM pragma_table_info('users')")
+            .map_err(|e| format!("Not a database file: {}", e))?;
+        let columns = stmt.query_map([], |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Not a database file: {}", e))?;
+
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| format!("Not a database file: {}", e))?;
+        if version > MIGRATIONS.len() as i64 {
+            return Err(format!("Backup is from a newer version (schema {}), please update", version));
+        }
+
+        // anything older is migrated after the restore, the first schema is enough
+        let required = ["id", "first_name", "surname", "email", "number", "winner"];
+        if let Some(missing) = required.iter().find(|c| !columns.iter().any(|col| col == *c)) {
+            return Err(format!("Incompatible backup, users table has no '{}' column", missing));
+        }
+
+        // migrate a copy and compare every table with a fresh database, so a
+        // broken draws, prizes or audit_log table is caught before the restore
+        let mut copy = Connection::open_in_memory().map_err(|e| e.to_string())?;
+        rusqlite::backup::Backup::new(&conn, &mut copy)
+            .and_then(|backup| backup.run_to_completion(256, std::time::Duration::ZERO, None))
+            .map_err(|e| format!("Cannot read backup: {}", e))?;
+        migrate(&copy).map_err(|e| format!("Incompatible backup, the upgrade failed: {}", e))?;
+        let fresh = Connection::open_in_memory().map_err(|e| e.to_string())?;
+        migrate(&fresh).map_err(|e| e.to_string())?;
+
+        let schema = |conn: &Connection| -> SqlResult<Vec<(String, String)>> {
+            conn.prepare(
+                "SELECT m.name, c.name FROM sqlite_master m, pragma_table_info(m.name) c
+                 WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'",
+            )?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect()
+        };
+        let found = schema(&copy).map_err(|e| format!("Not a database file: {}", e))?;
+        let expected = schema(&fresh).map_err(|e| e.to_string())?;
+        match expected.iter().find(|column| !found.contains(column)) {
+            Some((table, column)) => Err(format!("Incompatible backup, {} table has no '{}' column", table, column)),
+            None => Ok(()),
+        }
+    }
+
+    /// Draws up to this id no longer count as previous wins.
+    fn winner_history_since(&self) -> SqlResult<i64> {
+        Ok(self.get_setting("winner_history_since")?.and_then(|v| v.parse().ok()).unwrap_or(0))
+    }
+
+    fn get_setting(&self, key: &str) -> SqlResult<Option<String>> {
+        use rusqlite::OptionalExtension;
+        self.conn
+            .prepare_cached("SELECT value FROM settings WHERE key = ?1")?
+            .query_row([key], |row| row.get(0))
+            .optional()
+    }
+
+    fn get_ui_state(&self, key: &str) -> SqlResult<Option<String>> {
+        use rusqlite::OptionalExtension;
+        self.conn
+            .prepare_cached("SELECT value FROM ui_state WHERE key = ?1")?
+            .query_row([key], |row| row.get(0))
+            .optional()
+    }
+
+    fn set_ui_state(&self, key: &str, value: &str) -> SqlResult<()> {
+        self.conn
+            .prepare_cached(
+                "INSERT INTO ui_state (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            )?
+            .execute([key, value])
+            .map(|_| ())
+    }
+
+    fn set_setting(&self, key: &str, value: &str) -> SqlResult<()> {
+        self.conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            [key, value],
+        )?;
+        Ok(())
+    }
+
+    /// BEGIN IMMEDIATE takes the write lock up front, so a second writer
+    /// (the HTTP endpoint, another booth) is waited for once, within
+    /// `BUSY_TIMEOUT`, instead of failing when a read lock has to upgrade.
+    fn write_transaction(&self) -> SqlResult<Transaction<'_>> {
+        Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)
+    }
+
+    /// Appends to the audit log. Every mutating method calls this inside its
+    /// own transaction, so a change and its log entry land together or not at all.
+    fn log_action(&self, action: &str, user_id: Option<i32>, details: serde_json::Value) -> SqlResult<()> {
+        self.conn.execute(
+            "INSERT INTO audit_log (created_at, action, user_id, details, actor) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![unix_now(), action, user_id, details.to_string(), self.actor],
+        )?;
+        Ok(())
+    }
+
+    /// Hands out the next entry number. Never reused, so deleting a
+    /// registration leaves a gap instead of renumbering the others.
+    fn next_ticket_number(&self) -> SqlResult<i32> {
+        self.conn.query_row(
+            "UPDATE meta SET value = value + 1 WHERE key = 'ticket_counter' RETURNING value",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// `calculate_winners`, optionally refusing (false) once `data_revision`
+    /// moved away from `expected_revision`.
+    fn run_draw(
+        &self,
+        expected_revision: Option<i64>,
+        targets: &[i32],
+        exclude_previous: bool,
+        mode: WinnerMode,
+        seed: u64,
+    ) -> SqlResult<bool> {
+        let tx = self.write_transaction()?;
+        // the write lock is already held, so no booth can commit in between
+        if let Some(expected) = expected_revision
+            && self.data_revision()? != expected
+        {
+            return Ok(false);
+        }
+        tx.execute(
+            "UPDATE users SET winner = 0, prize_index = NULL, manual_override = 0, notified = 'none', notified_at = NULL",
+            [],
+        )?;
+
+        let participant_count: i64 = tx.query_row("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL", [], |row| row.get(0))?;
+        let winners = self.preview_winners(targets, exclude_previous, mode, seed)?;
+
+        let targets_text: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+        // the seed is what makes a lottery draw verifiable afterwards
+        let recorded_seed = (mode == WinnerMode::WeightedRandom).then_some(seed as i64);
+        tx.execute(
+            "INSERT INTO draws (created_at, mode, targets, exclude_previous, winner_count, seed, participant_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                unix_now(),
+                mode.as_str(),
+                targets_text.join(","),
+                exclude_previous,
+                winners.len(),
+                recorded_seed,
+                participant_count,
+            ],
+        )?;
+        let draw_id = tx.last_insert_rowid();
+
+        for w in &winners {
+            tx.execute(
+                "UPDATE users SET winner = 1, prize_index = ?1 WHERE id = ?2",
+                rusqlite::params![w.prize_index as i32, w.user.id],
+            )?;
+            tx.execute(
+                "INSERT INTO draw_winners (draw_id, user_id, prize_index, rank, distance)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![draw_id, w.user.id, w.prize_index as i32, w.prize_index as i32 + 1, w.distance],
+            )?;
+        }
+
+        let winner_ids: Vec<i32> = winners.iter().map(|w| w.user.id).collect();
+        self.log_action("calculate_winners", None, json!({
+            "draw_id": draw_id,
+            "mode": mode.as_str(),
+            "targets": targets,
+            "seed": recorded_seed,
+            "winners": winner_ids,
+        }))?;
+        tx.commit()?;
+        log::info!(
+            "Draw {} ({}, targets {:?}) picked {} of {} participants",
+            draw_id, mode.as_str(), targets, winners.len(), participant_count
+        );
+        Ok(true)
+    }
+
+    /// `condition` must be a literal, values go through `params` so the
+    /// cached statement is reused.
+    fn users_where<P: rusqlite::Params>(&self, condition: &str, params: P) -> SqlResult<Vec<User>> {
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "SELECT id, first_name, surname, email, number, winner, prize_index, notified, ticket_number
+             FROM users WHERE {} ORDER BY id",
+            condition,
+        ))?;
+
+        let users = stmt.query_map(params, |row| {
+            Ok(User {
+                id: row.get(0)?,
+                first_name: row.get(1)?,
+                surname: row.get(2)?,
+                email: row.get(3)?,
+                number: row.get(4)?,
+                winner: row.get::<_, i32>(5)? == 1,
+                prize_index: row.get(6)?,
+                notified: NotifyStatus::parse(&row.get::<_, String>(7)?),
+                ticket_number: row.get::<_, Option<i32>>(8)?.unwrap_or_default(),
+            })
+        })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(users)
+    }
+}
+
+impl UserStore for Database {
+    /// Copies the whole database into `path` via the SQLite backup API.
+    fn backup_to(&self, path: &Path) -> SqlResult<()> {
+        self.conn.backup(rusqlite::DatabaseName::Main, path, None)
+    }
+
+    /// Replaces everything in this connection with the contents of `path`.
+    /// Call `check_backup_schema` first, this does no validation.
+    fn restore_from(&mut self, path: &Path) -> SqlResult<()> {
+        self.conn.restore(rusqlite::DatabaseName::Main, path, None::<fn(rusqlite::backup::Progress)>)?;
+        // cached statements were compiled against the replaced schema
+        self.conn.flush_prepared_statement_cache();
+        // older backups get the same upgrade as older database files
+        migrate(&self.conn)?;
+        self.log_action("restore_backup", None, json!({ "path": path.display().to_string() }))
+    }
+
+    fn get_prizes(&self) -> SqlResult<Vec<Prize>> {
+        let mut stmt = self.conn.prepare_cached("SELECT rank, name, description FROM prizes ORDER BY rank")?;
+        let prizes = stmt.query_map([], |row| {
+            Ok(Prize {
+                rank: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+            })
+        })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(prizes)
+    }
+
+    fn save_prizes(&self, prizes: &[Prize]) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        let old: Vec<String> = self.get_prizes()?.into_iter().map(|p| p.name).collect();
+        tx.execute("DELETE FROM prizes", [])?;
+        for (idx, prize) in prizes.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO prizes (rank, name, description) VALUES (?1, ?2, ?3)",
+                rusqlite::params![idx as i32 + 1, prize.name.trim(), prize.description.trim()],
+            )?;
+        }
+        let new: Vec<&str> = prizes.iter().map(|p| p.name.trim()).collect();
+        self.log_action("save_prizes", None, json!({ "old": old, "new": new }))?;
+        tx.commit()
+    }
+
+    /// Open unless someone explicitly closed it.
+    fn registration_open(&self) -> SqlResult<bool> {
+        Ok(self.get_setting("registration_open")?.as_deref() != Some("0"))
+    }
+
+    fn set_registration_open(&self, open: bool) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        self.set_setting("registration_open", if open { "1" } else { "0" })?;
+        self.log_action(if open { "open_registration" } else { "close_registration" }, None, json!({}))?;
+        tx.commit()
+    }
+
+    /// Scheduled automatic close as a unix timestamp.
+    fn registration_close_at(&self) -> SqlResult<Option<i64>> {
+        Ok(self.get_setting("registration_close_at")?.and_then(|v| v.parse().ok()))
+    }
+
+    fn set_registration_close_at(&self, close_at: Option<i64>) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        let old = self.registration_close_at()?;
+        match close_at {
+            Some(ts) => self.set_setting("registration_close_at", &ts.to_string())?,
+            None => {
+                tx.execute("DELETE FROM settings WHERE key = 'registration_close_at'", [])?;
+            }
+        }
+        if old != close_at {
+            self.log_action("schedule_close", None, json!({ "old": old, "new": close_at }))?;
+        }
+        tx.commit()
+    }
+
+    fn submit_interval_secs(&self) -> SqlResult<u64> {
+        Ok(self.get_setting("submit_interval_secs")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_SUBMIT_INTERVAL_SECS))
+    }
+
+    fn set_submit_interval_secs(&self, secs: u64) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        self.set_setting("submit_interval_secs", &secs.to_string())?;
+        self.log_action("set_submit_interval", None, json!({ "secs": secs }))?;
+        tx.commit()
+    }
+
+    fn guess_range(&self) -> SqlResult<RangeInclusive<i32>> {
+        let bound = |key, default| -> SqlResult<i32> {
+            Ok(self.get_setting(key)?.and_then(|v| v.parse().ok()).unwrap_or(default))
+        };
+        Ok(bound("min_guess", 1)?..=bound("max_guess", i32::MAX)?)
+    }
+
+    fn set_guess_range(&self, min: i32, max: i32) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        self.set_setting("min_guess", &min.to_string())?;
+        self.set_setting("max_guess", &max.to_string())?;
+        self.log_action("set_guess_range", None, json!({ "min": min, "max": max }))?;
+        tx.commit()
+    }
+
+    fn webhook_url(&self) -> SqlResult<String> {
+        Ok(self.get_setting("webhook_url")?.unwrap_or_default())
+    }
+
+    fn event_name(&self) -> SqlResult<String> {
+        Ok(self.get_setting("event_name")?.unwrap_or_default())
+    }
+
+    fn set_event_name(&self, name: &str) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        let old = self.event_name()?;
+        self.set_setting("event_name", name)?;
+        if old != name {
+            self.log_action("set_event_name", None, json!({ "old": old, "new": name }))?;
+        }
+        tx.commit()
+    }
+
+    fn set_webhook_url(&self, url: &str) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        self.set_setting("webhook_url", url)?;
+        // the URL may carry a token, so only whether one is set goes in the log
+        self.log_action("set_webhook", None, json!({ "enabled": !url.is_empty() }))?;
+        tx.commit()
+    }
+
+    /// Stored as "x,y,width,height"; anything unreadable means no saved window.
+    fn window_geometry(&self) -> SqlResult<Option<WindowGeometry>> {
+        let Some(value) = self.get_ui_state("window_geometry")? else {
+            return Ok(None);
+        };
+        let parts: Vec<f32> = value.split(',').filter_map(|v| v.trim().parse().ok()).collect();
+        Ok(match parts[..] {
+            [x, y, width, height] => Some(WindowGeometry { x, y, width, height }),
+            _ => None,
+        })
+    }
+
+    // not audited: moving the window is no organizer decision
+    fn set_window_geometry(&self, g: WindowGeometry) -> SqlResult<()> {
+        self.set_ui_state("window_geometry", &format!("{},{},{},{}", g.x, g.y, g.width, g.height))
+    }
+
+    /// Unreadable JSON, e.g. after a power cut mid-write, counts as nothing saved.
+    fn dev_settings(&self) -> SqlResult<DevSettings> {
+        Ok(self.get_ui_state("dev_settings")?
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default())
+    }
+
+    fn set_dev_settings(&self, settings: &DevSettings) -> SqlResult<()> {
+        let value = serde_json::to_string(settings).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+        self.set_ui_state("dev_settings", &value)
+    }
+
+    fn form_draft(&self) -> SqlResult<Option<[String; 4]>> {
+        Ok(self.get_ui_state("form_draft")?.and_then(|v| serde_json::from_str(&v).ok()))
+    }
+
+    fn set_form_draft(&self, fields: &[String; 4]) -> SqlResult<()> {
+        if fields.iter().all(|f| f.trim().is_empty()) {
+            return self.conn.execute("DELETE FROM ui_state WHERE key = 'form_draft'", []).map(|_| ());
+        }
+        let value = serde_json::to_string(fields).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+        self.set_ui_state("form_draft", &value)
+    }
+
+    fn winners_locked(&self) -> SqlResult<bool> {
+        Ok(self.get_setting("winners_locked")?.as_deref() == Some("1"))
+    }
+
+    /// Freezes or thaws the winner set and stamps it on the latest draw.
+    fn set_winners_locked(&self, locked: bool) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        self.set_setting("winners_locked", if locked { "1" } else { "0" })?;
+        let column = if locked { "locked_at" } else { "unlocked_at" };
+        tx.execute(
+            &format!("UPDATE draws SET {} = ?1 WHERE id = (SELECT MAX(id) FROM draws)", column),
+            [unix_now()],
+        )?;
+        self.log_action(if locked { "lock_winners" } else { "unlock_winners" }, None, json!({}))?;
+        tx.commit()
+    }
+
+    fn set_actor(&mut self, actor: Option<&str>) {
+        self.actor = actor.map(str::to_string);
+    }
+
+    fn get_audit_log(&self, action: Option<&str>, user_id: Option<i32>, limit: usize) -> SqlResult<Vec<AuditEntry>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, created_at, action, user_id, details, actor FROM audit_log
+             WHERE (?1 IS NULL OR action = ?1) AND (?2 IS NULL OR user_id = ?2)
+             ORDER BY id DESC LIMIT ?3"
+        )?;
+        let entries = stmt.query_map(rusqlite::params![action, user_id, limit as i64], |row| {
+            Ok(AuditEntry {
+                id: row.get(0)?,
+                created_at: row.get(1)?,
+                action: row.get(2)?,
+                user_id: row.get(3)?,
+                details: row.get(4)?,
+                actor: row.get(5)?,
+            })
+        })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    fn audit_actions(&self) -> SqlResult<Vec<String>> {
+        let mut stmt = self.conn.prepare_cached("SELECT DISTINCT action FROM audit_log ORDER BY action")?;
+        let actions = stmt.query_map([], |row| row.get(0))?.collect::<Result<Vec<_>, _>>()?;
+        Ok(actions)
+    }
+
+    /// Returns the new registration's (id, ticket number).
+    fn insert_user(&self, firstname: &str, surname: &str, email: &str, number: i32) -> SqlResult<(i32, i32)> {
+        let tx = self.write_transaction()?;
+        let inserted = self.insert_user_row(firstname, surname, email, number)?;
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    fn undoable(&self, id: i32) -> SqlResult<bool> {
+        self.conn.prepare_cached(
+            "SELECT EXISTS(
+                 SELECT 1 FROM users WHERE id = ?1 AND winner = 0 AND deleted_at IS NULL
+                   AND NOT EXISTS(SELECT 1 FROM audit_log WHERE user_id = ?1 AND action <> 'insert')
+             )",
+        )?
+            .query_row([id], |row| row.get(0))
+    }
+
+    /// Takes back a fresh registration by moving it to the recycle bin.
+    /// Returns its values, or None if it is no longer `undoable`.
+    fn undo_registration(&self, id: i32) -> SqlResult<Option<User>> {
+        let tx = self.write_transaction()?;
+        if !self.undoable(id)? {
+            return Ok(None);
+        }
+        let user = self.get_user(id)?;
+        tx.execute("UPDATE users SET deleted_at = ?1 WHERE id = ?2", rusqlite::params![unix_now(), id])?;
+        self.log_action("undo_registration", Some(id), json!({ "old": user }))?;
+        tx.commit()?;
+        Ok(user)
+    }
+
+    fn update_user(&self, id: i32, firstname: &str, surname: &str, email: &str, number: i32) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        self.update_user_row(id, firstname, surname, email, number)?;
+        tx.commit()
+    }
+
+    /// The whole batch in one transaction, so a crash or a lost lock
+    /// can't leave an import half applied. A bad row is reported and
+    /// skipped as in `insert_users_batch_with`.
+    fn upsert_users(&self, rows: &[ImportedUser]) -> SqlResult<BatchReport> {
+        let tx = self.write_transaction()?;
+        let guesses = self.guess_range()?;
+        let deleted: HashSet<i32> = self.get_deleted_users()?.into_iter().map(|u| u.id).collect();
+        let mut outcomes = Vec::with_capacity(rows.len());
+        for row in rows {
+            // a failed row must not take the others with it
+            self.conn.execute_batch("SAVEPOINT upsert_row")?;
+            let outcome = upsert_outcome(
+                self, row, &guesses, &deleted,
+                |id, first_name, surname, email, number| self.update_user_row(id, first_name, surname, email, number),
+                |first_name, surname, email, number| self.insert_user_row(first_name, surname, email, number),
+            )?;
+            if matches!(outcome, RowOutcome::Failed(_)) {
+                self.conn.execute_batch("ROLLBACK TO upsert_row; RELEASE upsert_row")?;
+            } else {
+                self.conn.execute_batch("RELEASE upsert_row")?;
+            }
+            outcomes.push(outcome);
+        }
+        tx.commit()?;
+        Ok(BatchReport { rows: outcomes })
+    }
+
+    fn insert_users_batch(&self, users: &[NewUser]) -> SqlResult<BatchReport> {
+        let report = self.insert_users_batch_with(users, &mut |_| true)?;
+        Ok(report.unwrap_or(BatchReport { rows: Vec::new() }))
+    }
+
+    /// Inserts all users in one transaction. A bad row is reported and
+    /// skipped instead of rolling back the rows around it; a cancel rolls
+    /// back all of them.
+    fn insert_users_batch_with(&self, users: &[NewUser], progress: &mut dyn FnMut(usize) -> bool) -> SqlResult<Option<BatchReport>> {
+        let tx = self.write_transaction()?;
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT INTO users (first_name, surname, email, number, winner, is_test, ticket_number, created_at)
+             VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6, ?7)"
+        )?;
+        let now = unix_now();
+        let guesses = self.guess_range()?;
+        let mut rows = Vec::with_capacity(users.len());
+        for (done, u) in users.iter().enumerate() {
+            // dropping `tx` undoes the rows so far
+            if !progress(done) {
+                log::info!("Import cancelled after {} of {} rows, rolled back", done, users.len());
+                return Ok(None);
+            }
+            let number = u.number.to_string();
+            if let Some(error) = validate_form([&u.first_name, &u.surname, &u.email, &number], &guesses).first() {
+                rows.push(RowOutcome::Skipped(error.message.clone()));
+                continue;
+            }
+            // a failed row must not use up an entry number
+            self.conn.execute_batch("SAVEPOINT batch_row")?;
+            let inserted = self.next_ticket_number().and_then(|ticket| {
+                stmt.execute(rusqlite::params![u.first_name, u.surname, u.email, u.number, u.is_test, ticket, now])?;
+                Ok(ticket)
+            });
+            match inserted {
+                Ok(ticket) => {
+                    self.conn.execute_batch("RELEASE batch_row")?;
+                    rows.push(RowOutcome::Inserted(ticket));
+                }
+                Err(e) => {
+                    self.conn.execute_batch("ROLLBACK TO batch_row; RELEASE batch_row")?;
+                    rows.push(RowOutcome::Failed(e.to_string()));
+                }
+            }
+        }
+        drop(stmt);
+        let report = BatchReport { rows };
+
+        // one entry per batch, a 10k import would otherwise drown the log
+        let test_rows = users.iter().filter(|u| u.is_test).count();
+        self.log_action("import", None, json!({
+            "count": users.len(),
+            "inserted": report.inserted(),
+            "rejected": report.rejected(),
+            "test_rows": test_rows,
+        }))?;
+        tx.commit()?;
+        Ok(Some(report))
+    }
+
+    /// Removes everything the generator inserted, real registrations stay.
+    fn delete_test_users(&self) -> SqlResult<usize> {
+        let tx = self.write_transaction()?;
+        tx.execute(
+            "DELETE FROM draw_winners WHERE user_id IN (SELECT id FROM users WHERE is_test = 1)",
+            [],
+        )?;
+        let deleted = tx.execute("DELETE FROM users WHERE is_test = 1", [])?;
+        self.log_action("delete_test_data", None, json!({ "deleted": deleted }))?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// Fresh start between events: registrations, draw history and the audit
+    /// log go, prizes and settings stay. The only way entries ever leave the
+    /// log; the wipe itself is logged afterwards. Returns the number of
+    /// registrations removed.
+    fn clear_all(&self) -> SqlResult<usize> {
+        let tx = self.write_transaction()?;
+        tx.execute("DELETE FROM draw_winners", [])?;
+        tx.execute("DELETE FROM draws", [])?;
+        // the one delete the append-only trigger lets through
+        tx.execute("INSERT OR REPLACE INTO settings (key, value) VALUES ('clearing_audit_log', '1')", [])?;
+        tx.execute("DELETE FROM audit_log", [])?;
+        tx.execute("DELETE FROM settings WHERE key = 'clearing_audit_log'", [])?;
+        let deleted = tx.execute("DELETE FROM users", [])?;
+        // a new event starts again at entry #1, and draw ids start over too
+        tx.execute("UPDATE meta SET value = 0 WHERE key = 'ticket_counter'", [])?;
+        tx.execute("DELETE FROM settings WHERE key = 'winner_history_since'", [])?;
+        tx.commit()?;
+        self.log_action("clear_all", None, json!({ "deleted": deleted }))?;
+        Ok(deleted)
+    }
+
+    /// Moves a registration to the recycle bin. A winner loses the win,
+    /// which the lock trigger refuses while winners are locked.
+    fn delete_user(&self, id: i32) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        let old = self.get_user(id)?;
+        tx.execute("UPDATE users SET winner = 0, prize_index = NULL WHERE id = ?1 AND winner = 1", [id])?;
+        let changed = tx.execute(
+            "UPDATE users SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            rusqlite::params![unix_now(), id],
+        )?;
+        if changed > 0 {
+            self.log_action("delete", Some(id), json!({ "old": old }))?;
+        }
+        tx.commit()
+    }
+
+    /// Takes a registration out of the recycle bin. Returns false (and changes
+    /// nothing) when a live registration already uses the same email.
+    fn restore_user(&self, id: i32) -> SqlResult<bool> {
+        let tx = self.write_transaction()?;
+        match tx.execute("UPDATE users SET deleted_at = NULL WHERE id = ?1", [id]) {
+            Err(e) if is_constraint_violation(&e) => return Ok(false),
+            result => result?,
+        };
+        self.log_action("restore", Some(id), json!({}))?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Brings back a deleted registration as it was when deleted, winner
+    /// flag included. Re-inserts it with the same id and entry number if
+    /// it was purged meanwhile. False if its email is taken again.
+    fn undelete_user(&self, user: &User) -> SqlResult<bool> {
+        let tx = self.write_transaction()?;
+        let result = tx.execute(
+            "INSERT INTO users (id, first_name, surname, email, number, winner, prize_index, notified, ticket_number, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(id) DO UPDATE SET
+                 deleted_at = NULL, winner = excluded.winner, prize_index = excluded.prize_index, notified = excluded.notified",
+            rusqlite::params![
+                user.id, user.first_name, user.surname, user.email, user.number, user.winner,
+                user.prize_index, user.notified.as_str(), user.ticket_number, unix_now(),
+            ],
+        );
+        match result {
+            // only the email index, a locked winner is a real error
+            Err(rusqlite::Error::SqliteFailure(f, _)) if f.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE => {
+                return Ok(false);
+            }
+            result => result?,
+        };
+        self.log_action("undo_delete", Some(user.id), json!({ "new": user }))?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    fn merge_users(&self, keep: i32, remove: &[i32]) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        for &id in remove.iter().filter(|&&id| id != keep) {
+            tx.execute("UPDATE users SET winner = 0, prize_index = NULL WHERE id = ?1 AND winner = 1", [id])?;
+            tx.execute(
+                "UPDATE users SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                rusqlite::params![unix_now(), id],
+            )?;
+        }
+        self.log_action("merge", Some(keep), json!({ "kept": keep, "removed": remove }))?;
+        tx.commit()
+    }
+
+    /// Permanent removal, only for registrations already in the recycle bin.
+    fn purge_user(&self, id: i32) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        let old = self.get_user(id)?;
+        if tx.execute("DELETE FROM users WHERE id = ?1 AND deleted_at IS NOT NULL", [id])? > 0 {
+            self.log_action("purge", Some(id), json!({ "old": old }))?;
+        }
+        tx.commit()
+    }
+
+    fn get_all_users(&self) -> SqlResult<Vec<User>> {
+        self.users_where("deleted_at IS NULL", [])
+    }
+
+    fn get_deleted_users(&self) -> SqlResult<Vec<User>> {
+        self.users_where("deleted_at IS NOT NULL", [])
+    }
+
+    fn get_user(&self, id: i32) -> SqlResult<Option<User>> {
+        Ok(self.users_where("id = ?1", [id])?.pop())
+    }
+
+    fn get_previous_winners(&self) -> SqlResult<HashMap<i32, Vec<i64>>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT dw.user_id, d.round
+             FROM draw_winners dw
+             JOIN (SELECT id, ROW_NUMBER() OVER (ORDER BY id) AS round FROM draws) d ON d.id = dw.draw_id
+             WHERE dw.replaced_reason IS NULL AND dw.draw_id > ?1 ORDER BY dw.draw_id"
+        )?;
+
+        let mut rounds: HashMap<i32, Vec<i64>> = HashMap::new();
+        let since = self.winner_history_since()?;
+        let rows = stmt.query_map([since], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, i64>(1)?)))?;
+        for row in rows {
+            let (user_id, round) = row?;
+            rounds.entry(user_id).or_default().push(round);
+        }
+
+        Ok(rounds)
+    }
+
+    /// Marks the newest draw as the end of the old history instead of
+    /// deleting anything.
+    fn reset_winner_history(&self) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        let last: i64 = tx.query_row("SELECT COALESCE(MAX(id), 0) FROM draws", [], |row| row.get(0))?;
+        self.set_setting("winner_history_since", &last.to_string())?;
+        self.log_action("reset_winner_history", None, json!({ "since_draw": last }))?;
+        tx.commit()
+    }
+
+    fn stats(&self) -> SqlResult<DbStats> {
+        let (registrations, winners, distinct_emails, first_created, last_created) = self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(winner), 0), COUNT(DISTINCT NULLIF(email, '')),
+                    MIN(created_at), MAX(created_at)
+             FROM users WHERE deleted_at IS NULL",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )?;
+        let pragma = |name: &str| self.conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0));
+        let synchronous = match pragma("synchronous")? {
+            0 => "OFF",
+            1 => "NORMAL",
+            2 => "FULL",
+            _ => "EXTRA",
+        };
+        Ok(DbStats {
+            journal_mode: self.conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?,
+            synchronous: synchronous.to_string(),
+            foreign_keys: pragma("foreign_keys")? == 1,
+            busy_timeout_ms: pragma("busy_timeout")?,
+            registrations,
+            winners,
+            distinct_emails,
+            first_created,
+            last_created,
+            path: self.path.clone(),
+            file_size: std::fs::metadata(&self.path).ok().map(|m| m.len()),
+        })
+    }
+
+    /// ":memory:", or SQLite's nameless temporary database.
+    fn is_in_memory(&self) -> bool {
+        matches!(self.conn.path(), None | Some(""))
+    }
+
+    fn number_stats(&self) -> SqlResult<NumberStats> {
+        let (count, min, max, mean) = self.conn.query_row(
+            "SELECT COUNT(*), MIN(number), MAX(number), AVG(number) FROM users WHERE deleted_at IS NULL",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )?;
+        // average of the middle one (odd count) or two (even count) rows
+        let median = self.conn.query_row(
+            "SELECT AVG(number) FROM (
+                 SELECT number FROM users WHERE deleted_at IS NULL ORDER BY number
+                 LIMIT 2 - (SELECT COUNT(*) FROM users WHERE deleted_at IS NULL) % 2
+                 OFFSET (SELECT (COUNT(*) - 1) / 2 FROM users WHERE deleted_at IS NULL)
+             )",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(NumberStats { count, min, max, mean, median })
+    }
+
+    /// (bin start, count) for every non-empty bin, grouped in SQL so the
+    /// chart only ever sees a few hundred bars.
+    fn number_histogram(&self, bin_width: i64) -> SqlResult<Vec<(i64, i64)>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT (number / ?1) * ?1 AS bin, COUNT(*) FROM users WHERE deleted_at IS NULL GROUP BY bin ORDER BY bin"
+        )?;
+        let bins = stmt.query_map([bin_width.max(1)], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bins)
+    }
+
+    /// Changes whenever registrations are added or removed, so a preview
+    /// can tell that it went stale.
+    fn registration_revision(&self) -> SqlResult<(i64, i64)> {
+        self.conn
+            .prepare_cached("SELECT COUNT(*), COALESCE(MAX(id), 0) FROM users WHERE deleted_at IS NULL")?
+            .query_row([], |row| Ok((row.get(0)?, row.get(1)?)))
+    }
+
+    fn data_revision(&self) -> SqlResult<i64> {
+        self.conn
+            .prepare_cached("SELECT value FROM meta WHERE key = 'change_counter'")?
+            .query_row([], |row| row.get(0))
+    }
+
+    /// Marks one winner per target: the closest guess that hasn't already
+    /// taken an earlier prize, or a weighted lottery pick. Repeating a target
+    /// yields the top N closest.
+    fn calculate_winners(&self, targets: &[i32], exclude_previous: bool, mode: WinnerMode, seed: u64) -> SqlResult<()> {
+        self.run_draw(None, targets, exclude_previous, mode, seed).map(|_| ())
+    }
+
+    fn calculate_winners_if_unchanged(
+        &self,
+        revision: i64,
+        targets: &[i32],
+        exclude_previous: bool,
+        mode: WinnerMode,
+        seed: u64,
+    ) -> SqlResult<bool> {
+        self.run_draw(Some(revision), targets, exclude_previous, mode, seed)
+    }
+
+    /// Replaces a single winner of the latest draw with the next-closest guess
+    /// for the same prize (or a fresh weighted pick for lottery draws), leaving
+    /// the other winners alone. Returns the promoted user, or None (and changes
+    /// nothing) when nobody is left to promote. Winners set by hand were never
+    /// drawn and are refused.
+    fn reroll_winner(&self, user_id: i32, reason: &str) -> SqlResult<Option<User>> {
+        use rusqlite::OptionalExtension;
+        let tx = self.write_transaction()?;
+
+        let drawn: Option<(i64, i32, String, bool, String, Option<i64>)> = tx
+            .query_row(
+                "SELECT dw.draw_id, dw.prize_index, d.targets, d.exclude_previous, d.mode, d.seed
+                 FROM draw_winners dw JOIN draws d ON d.id = dw.draw_id
+                 WHERE dw.user_id = ?1 AND dw.replaced_reason IS NULL
+                   AND dw.draw_id = (SELECT MAX(id) FROM draws)",
+                [user_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+            )
+            .optional()?;
+        let Some((draw_id, prize_index, targets, exclude_previous, mode, seed)) = drawn else {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR),
+                Some(tr("Only winners of the latest draw can be re-rolled").to_string()),
+            ));
+        };
+        let target = targets
+            .split(',')
+            .nth(prize_index as usize)
+            .and_then(|t| t.parse::<i32>().ok())
+            .unwrap_or_default();
+
+        // everyone already in this draw (winners and re-rolled ones) is out,
+        // plus earlier rounds' winners if the draw excluded them
+        let mut stmt = tx.prepare(
+            "SELECT user_id FROM draw_winners
+             WHERE draw_id = ?1 OR (?2 AND draw_id < ?1 AND draw_id > ?3 AND replaced_reason IS NULL)"
+        )?;
+        let since = self.winner_history_since()?;
+        let ineligible = stmt
+            .query_map(rusqlite::params![draw_id, exclude_previous, since], |row| row.get::<_, i32>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+        let draw_rows: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM draw_winners WHERE draw_id = ?1",
+            [draw_id],
+            |row| row.get(0),
+        )?;
+
+        let candidates: Vec<User> = self.get_all_users()?
+            .into_iter()
+            .filter(|u| !ineligible.contains(&u.id))
+            .collect();
+        let picked = match (WinnerMode::parse(&mode), seed) {
+            // every re-roll of the draw gets its own, still reproducible, stream
+            (WinnerMode::WeightedRandom, Some(seed)) => {
+                let mut rng = StdRng::seed_from_u64((seed as u64).wrapping_add(draw_rows as u64));
+                weighted_candidate(&candidates, target, &mut rng)
+            }
+            _ => closest_candidate(&candidates, target),
+        };
+        let Some(pos) = picked else {
+            return Ok(None);
+        };
+        let replacement = candidates[pos].clone();
+
+        tx.execute(
+            "UPDATE draw_winners SET replaced_reason = ?1 WHERE draw_id = ?2 AND user_id = ?3",
+            rusqlite::params![reason, draw_id, user_id],
+        )?;
+        tx.execute(
+            "INSERT INTO draw_winners (draw_id, user_id, prize_index, rank, distance)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![draw_id, replacement.id, prize_index, prize_index + 1, distance(&replacement, target)],
+        )?;
+        tx.execute(
+            "UPDATE users SET winner = 0, prize_index = NULL WHERE id = ?1",
+            [user_id],
+        )?;
+        tx.execute(
+            "UPDATE users SET winner = 1, prize_index = ?1 WHERE id = ?2",
+            rusqlite::params![prize_index, replacement.id],
+        )?;
+        self.log_action("reroll", Some(user_id), json!({
+            "draw_id": draw_id,
+            "prize_index": prize_index,
+            "replacement": replacement.id,
+            "reason": reason,
+        }))?;
+
+        tx.commit()?;
+        Ok(Some(replacement))
+    }
+
+    fn get_draws(&self) -> SqlResult<Vec<Draw>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, created_at, mode, targets, winner_count, seed, participant_count,
+                    ROW_NUMBER() OVER (ORDER BY id)
+             FROM draws ORDER BY id DESC"
+        )?;
+
+        let draws = stmt.query_map([], |row| {
+            Ok(Draw {
+                id: row.get(0)?,
+                round: row.get(7)?,
+                created_at: row.get(1)?,
+                mode: row.get(2)?,
+                targets: row.get(3)?,
+                winner_count: row.get(4)?,
+                seed: row.get(5)?,
+                participant_count: row.get(6)?,
+            })
+        })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(draws)
+    }
+
+    fn get_draw_winners(&self, draw_id: i64) -> SqlResult<Vec<DrawWinner>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT dw.user_id, u.first_name || ' ' || u.surname, u.number, dw.rank, dw.distance, dw.replaced_reason
+             FROM draw_winners dw JOIN users u ON u.id = dw.user_id
+             WHERE dw.draw_id = ?1 ORDER BY dw.rank, dw.replaced_reason IS NULL"
+        )?;
+
+        let winners = stmt.query_map([draw_id], |row| {
+            Ok(DrawWinner {
+                user_id: row.get(0)?,
+                name: row.get(1)?,
+                number: row.get(2)?,
+                rank: row.get(3)?,
+                distance: row.get(4)?,
+                replaced_reason: row.get(5)?,
+            })
+        })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(winners)
+    }
+
+    fn restore_draw(&self, draw_id: i64) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        tx.execute("UPDATE users SET winner = 0, prize_index = NULL, manual_override = 0", [])?;
+        tx.execute(
+            "UPDATE users SET winner = 1, prize_index = (
+                 SELECT prize_index FROM draw_winners
+                 WHERE draw_id = ?1 AND user_id = users.id AND replaced_reason IS NULL
+             )
+             WHERE id IN (SELECT user_id FROM draw_winners WHERE draw_id = ?1 AND replaced_reason IS NULL)
+               AND deleted_at IS NULL",
+            [draw_id],
+        )?;
+        self.log_action("restore_draw", None, json!({ "draw_id": draw_id }))?;
+        tx.commit()
+    }
+
+    fn set_winner(&self, id: i32, winner: bool) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        let old = self.get_user(id)?.map(|u| (u.winner, u.prize_index));
+        tx.execute(
+            "UPDATE users SET winner = ?1, prize_index = NULL, manual_override = 1 WHERE id = ?2 AND deleted_at IS NULL",
+            rusqlite::params![winner, id],
+        )?;
+        self.log_action("set_winner", Some(id), json!({
+            "old": old.map(|(winner, prize_index)| json!({ "winner": winner, "prize_index": prize_index })),
+            "new": { "winner": winner, "prize_index": null },
+        }))?;
+        tx.commit()
+    }
+
+    fn set_notified(&self, id: i32, status: NotifyStatus) -> SqlResult<()> {
+        let tx = self.write_transaction()?;
+        let old = self.get_user(id)?.map(|u| u.notified);
+        tx.execute(
+            "UPDATE users SET notified = ?1, notified_at = ?2 WHERE id = ?3",
+            rusqlite::params![status.as_str(), unix_now(), id],
+        )?;
+        self.log_action("notify_status", Some(id), json!({ "old": old, "new": status }))?;
+        tx.commit()
+    }
+
+    fn notification_summary(&self) -> SqlResult<(i64, i64)> {
+        self.conn
+            .prepare_cached("SELECT COUNT(*) FILTER (WHERE notified = 'confirmed'), COUNT(*) FROM users WHERE winner = 1")?
+            .query_row([], |row| Ok((row.get(0)?, row.get(1)?)))
+    }
+
+    fn has_manual_overrides(&self) -> SqlResult<bool> {
+        self.conn
+            .prepare_cached("SELECT EXISTS(SELECT 1 FROM users WHERE manual_override = 1)")?
+            .query_row([], |row| row.get(0))
+    }
+}
+
+/// One step of the schema history; step N leads to `user_version` N + 1.
+/// Files from before versioning may already carry some later columns, so
+/// every step has to be safe to run on those too.
+type Migration = fn(&Connection) -> SqlResult<()>;
+
+const MIGRATIONS: &[Migration] = &[
+    // 1: the original registrations table
+    |conn| conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY,
+            first_name TEXT NOT NULL,
+            surname TEXT NOT NULL,
+            email TEXT  NULL,
+            number INTEGER NOT NULL,
+            winner INTEGER NOT NULL DEFAULT 0
+        );",
+    ),
+    // 2: every calculation is a round, so past winners survive a recalculation
+    |conn| {
+        add_column_if_missing(conn, "users", "prize_index", "INTEGER NULL")?;
+        add_column_if_missing(conn, "users", "manual_override", "INTEGER NOT NULL DEFAULT 0")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS draws (
+                id INTEGER PRIMARY KEY,
+                created_at INTEGER NOT NULL,
+                mode TEXT NOT NULL DEFAULT 'closest',
+                targets TEXT NOT NULL,
+                exclude_previous INTEGER NOT NULL DEFAULT 0,
+                winner_count INTEGER NOT NULL DEFAULT 0,
+                seed INTEGER NULL,
+                participant_count INTEGER NOT NULL DEFAULT 0,
+                locked_at INTEGER NULL,
+                unlocked_at INTEGER NULL
+            );
+            CREATE TABLE IF NOT EXISTS draw_winners (
+                draw_id INTEGER NOT NULL REFERENCES draws(id),
+                user_id INTEGER NOT NULL REFERENCES users(id),
+                prize_index INTEGER NOT NULL,
+                rank INTEGER NOT NULL,
+                distance INTEGER NOT NULL,
+                replaced_reason TEXT NULL,
+                PRIMARY KEY (draw_id, user_id)
+            );",
+        )
+    },
+    // 3: audit log, prizes, settings and the winner lock
+    |conn| conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY,
+            created_at INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            user_id INTEGER NULL,
+            details TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS prizes (
+            rank INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT ''
+        );
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        -- locked winners are refused by SQLite itself, whichever code path tries it
+        CREATE TRIGGER IF NOT EXISTS winners_locked_update
+        BEFORE UPDATE OF winner ON users
+        WHEN (SELECT value FROM settings WHERE key = 'winners_locked') = '1'
+        BEGIN SELECT RAISE(ABORT, 'Winners are locked'); END;
+
+        CREATE TRIGGER IF NOT EXISTS winners_locked_delete
+        BEFORE DELETE ON users
+        WHEN OLD.winner = 1 AND (SELECT value FROM settings WHERE key = 'winners_locked') = '1'
+        BEGIN SELECT RAISE(ABORT, 'Winners are locked'); END;
+
+        CREATE TRIGGER IF NOT EXISTS audit_log_append_only
+        BEFORE UPDATE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'The audit log is append-only'); END;",
+    ),
+    // 4: contacting winners
+    |conn| {
+        add_column_if_missing(conn, "users", "notified", "TEXT NOT NULL DEFAULT 'none'")?;
+        add_column_if_missing(conn, "users", "notified_at", "INTEGER NULL")?;
+        Ok(())
+    },
+    // 5: registration timestamps and tagged test data
+    |conn| {
+        add_column_if_missing(conn, "users", "created_at", "INTEGER NULL")?;
+        add_column_if_missing(conn, "users", "is_test", "INTEGER NOT NULL DEFAULT 0")?;
+        Ok(())
+    },
+    // 6: recycle bin, and one live registration per email
+    |conn| {
+        add_column_if_missing(conn, "users", "deleted_at", "INTEGER NULL")?;
+        // older files may already hold duplicates, the rest works without the index
+        if let Err(e) = conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS users_live_email ON users(email COLLATE NOCASE)
+             WHERE deleted_at IS NULL AND email <> ''",
+            [],
+        ) {
+            log::warn!("Unique email index not created: {}", e);
+        }
+        Ok(())
+    },
+    // 7: entry numbers; internal counters live in `meta`, unlike `settings`
+    // nothing there is user-facing
+    |conn| {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS meta (
+                key TEXT PRIMARY KEY,
+                value INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        if add_column_if_missing(conn, "users", "ticket_number", "INTEGER NULL")? {
+            conn.execute(
+                "UPDATE users SET ticket_number = (SELECT COUNT(*) FROM users u WHERE u.id <= users.id)",
+                [],
+            )?;
+        }
+        conn.execute(
+            "INSERT OR IGNORE INTO meta (key, value)
+             SELECT 'ticket_counter', COALESCE(MAX(ticket_number), 0) FROM users",
+            [],
+        )?;
+        Ok(())
+    },
+    // 8: lookups and stats by guess stop scanning the table; email checks
+    // already have `users_live_email`
+    |conn| conn.execute_batch("CREATE INDEX IF NOT EXISTS users_number ON users(number);"),
+    // 9: every write bumps a counter, so another process can tell the file changed
+    |conn| {
+        conn.execute("INSERT OR IGNORE INTO meta (key, value) VALUES ('change_counter', 0)", [])?;
+        for table in ["users", "draws", "draw_winners", "prizes", "settings"] {
+            for event in ["insert", "update", "delete"] {
+                conn.execute_batch(&format!(
+                    "CREATE TRIGGER IF NOT EXISTS {table}_{event}_changes
+                    AFTER {event} ON {table}
+                    BEGIN UPDATE meta SET value = value + 1 WHERE key = 'change_counter'; END;"
+                ))?;
+            }
+        }
+        Ok(())
+    },
+    // 10: window and dev window state, saved often; no change trigger, so
+    // other windows don't reload whenever this booth saves it
+    |conn| conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS ui_state (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        INSERT OR IGNORE INTO ui_state (key, value)
+            SELECT key, value FROM settings WHERE key = 'window_geometry';
+        DELETE FROM settings WHERE key = 'window_geometry';",
+    ),
+    // 11: the role that made a change
+    |conn| {
+        add_column_if_missing(conn, "audit_log", "actor", "TEXT NULL")?;
+        Ok(())
+    },
+    // 12: draw history columns. Files from before versioning got the draws
+    // tables with only the first few columns, and step 2 leaves existing
+    // tables alone.
+    |conn| {
+        add_column_if_missing(conn, "draws", "mode", "TEXT NOT NULL DEFAULT 'closest'")?;
+        add_column_if_missing(conn, "draws", "winner_count", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(conn, "draws", "seed", "INTEGER NULL")?;
+        add_column_if_missing(conn, "draws", "participant_count", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(conn, "draws", "locked_at", "INTEGER NULL")?;
+        add_column_if_missing(conn, "draws", "unlocked_at", "INTEGER NULL")?;
+        add_column_if_missing(conn, "draw_winners", "rank", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(conn, "draw_winners", "distance", "INTEGER NOT NULL DEFAULT 0")?;
+        Ok(())
+    },
+    // 13: audit entries can't be deleted either, except by `clear_all`
+    |conn| conn.execute_batch(
+        "CREATE TRIGGER IF NOT EXISTS audit_log_no_delete
+        BEFORE DELETE ON audit_log
+        WHEN (SELECT value FROM settings WHERE key = 'clearing_audit_log') IS NOT '1'
+        BEGIN SELECT RAISE(ABORT, 'The audit log is append-only'); END;",
+    ),
+];
+
+/// Runs the missing migrations in one transaction. Files written by a newer
+/// version are refused, this build would not know what their columns mean.
+fn migrate(conn: &Connection) -> SqlResult<()> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let latest = MIGRATIONS.len() as i64;
+    if version > latest {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR),
+            Some(format!(
+                "Database schema version {} is newer than this program supports ({}), please update",
+                version, latest
+            )),
+        ));
+    }
+    if version == latest {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    for step in &MIGRATIONS[version as usize..] {
+        step(&tx)?;
+    }
+    tx.pragma_update(None, "user_version", latest)?;
+    tx.commit()
+}
+
+/// True for UNIQUE and similar constraint failures, e.g. a taken email.
+pub fn is_constraint_violation(e: &rusqlite::Error) -> bool {
+    matches!(e, rusqlite::Error::SqliteFailure(f, _) if f.code == rusqlite::ErrorCode::ConstraintViolation)
+}
+
+/// How long a write waits for another connection's lock before giving up
+/// with "Database busy"; the booth would rather retry than freeze longer.
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Another connection holds the lock, e.g. the HTTP endpoint mid-write.
+fn is_busy(e: &rusqlite::Error) -> bool {
+    matches!(
+        e,
+        rusqlite::Error::SqliteFailure(f, _)
+            if matches!(f.code, rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+    )
+}
+
+/// Toast text for a failed write.
+pub fn db_error_message(e: &rusqlite::Error) -> String {
+    if is_busy(e) {
+        tr("Database busy, please retry").to_string()
+    } else {
+        tr!("Error: {}", e)
+    }
+}
+
+/// Returns true if the column had to be added.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> SqlResult<bool> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(!exists)
+}
+
+/// Set by `lock_database` whenever it recovered a poisoned lock, until the
+/// UI takes the notice; whichever thread locked first, the notice stays.
+static POISON_RECOVERED: AtomicBool = AtomicBool::new(false);
+
+/// Locks the shared database. A panic in an earlier holder poisons the
+/// mutex, but SQLite keeps the data consistent, so we just carry on.
+pub fn lock_database<T: ?Sized>(database: &Mutex<T>) -> MutexGuard<'_, T> {
+    database.lock().unwrap_or_else(|poisoned| {
+        log::error!("Database lock was poisoned, recovering");
+        POISON_RECOVERED.store(true, Ordering::Relaxed);
+        database.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// True once after `lock_database` recovered from a crash, for the banner.
+pub fn take_poison_notice() -> bool {
+    POISON_RECOVERED.swap(false, Ordering::Relaxed)
+}
+
+/// Seconds since the epoch, what every timestamp column holds.
+pub fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{mpsc, Arc};
+    use crate::fixtures::MemoryStore;
+    use crate::testdata::{TestDistribution, generate_test_users};
+
+    #[test]
+    fn lock_database_recovers_from_poison() {
+        let database = Arc::new(Mutex::new(Database::open(Path::new(":memory:")).unwrap()));
+
+        let poisoner = Arc::clone(&database);
+        let result = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("simulated crash while holding the database");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(database.is_poisoned());
+
+        lock_database(&database).insert_user("Anna", "Schmidt", "anna@example.com", 42).unwrap();
+        assert!(!database.is_poisoned());
+        assert_eq!(lock_database(&database).get_all_users().unwrap().len(), 1);
+        assert!(take_poison_notice());
+        assert!(!take_poison_notice());
+    }
+
+    #[test]
+    fn clear_all_empties_registrations_and_history() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        for (i, number) in [10, 20, 30].into_iter().enumerate() {
+            db.insert_user("Test", &i.to_string(), "", number).unwrap();
+        }
+        db.calculate_winners(&[20, 20], false, WinnerMode::Closest, 0).unwrap();
+
+        assert_eq!(db.clear_all().unwrap(), 3);
+        assert!(db.get_all_users().unwrap().is_empty());
+        assert!(db.get_draws().unwrap().is_empty());
+        let draw_winners: i64 = db.conn.query_row("SELECT COUNT(*) FROM draw_winners", [], |row| row.get(0)).unwrap();
+        assert_eq!(draw_winners, 0);
+        // only the wipe itself is left
+        assert_eq!(db.get_audit_log(None, None, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn audit_log_refuses_edits_and_deletes() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        db.insert_user("Anna", "Schmidt", "anna@example.com", 42).unwrap();
+
+        let update = db.conn.execute("UPDATE audit_log SET action = 'nothing'", []);
+        assert!(update.unwrap_err().to_string().contains("append-only"));
+        let delete = db.conn.execute("DELETE FROM audit_log", []);
+        assert!(delete.unwrap_err().to_string().contains("append-only"));
+        assert_eq!(db.get_audit_log(None, None, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn new_event_starts_from_scratch() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        db.insert_user("Anna", "Schmidt", "anna@example.com", 10).unwrap();
+        db.calculate_winners(&[10], false, WinnerMode::Closest, 0).unwrap();
+        db.reset_winner_history().unwrap();
+        db.set_winners_locked(true).unwrap();
+        db.set_registration_open(false).unwrap();
+        db.set_registration_close_at(Some(unix_now() + 60)).unwrap();
+
+        assert_eq!(db.start_new_event("Winter 2025").unwrap(), 1);
+        assert_eq!(db.event_name().unwrap(), "Winter 2025");
+        assert!(!db.winners_locked().unwrap());
+        assert!(db.registration_open().unwrap());
+        assert_eq!(db.registration_close_at().unwrap(), None);
+
+        // draw ids start at 1 again, the old history cutoff must not hide them
+        let (id, ticket) = db.insert_user("Ben", "Becker", "anna@example.com", 12).unwrap();
+        assert_eq!(ticket, 1);
+        db.calculate_winners(&[12], false, WinnerMode::Closest, 0).unwrap();
+        assert!(db.get_previous_winners().unwrap().contains_key(&id));
+    }
+
+    #[test]
+    fn rounds_count_the_draws_left_not_their_ids() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let (anna, _) = db.insert_user("Anna", "Schmidt", "anna@example.com", 10).unwrap();
+        let (ben, _) = db.insert_user("Ben", "Meyer", "ben@example.com", 20).unwrap();
+        for target in [10, 10, 20] {
+            db.calculate_winners(&[target], false, WinnerMode::Closest, 0).unwrap();
+        }
+        db.conn.execute_batch("DELETE FROM draw_winners WHERE draw_id = 1; DELETE FROM draws WHERE id = 1;").unwrap();
+
+        let rounds: Vec<(i64, i64)> = db.get_draws().unwrap().iter().map(|d| (d.id, d.round)).collect();
+        assert_eq!(rounds, vec![(3, 2), (2, 1)]);
+        let previous = db.get_previous_winners().unwrap();
+        assert_eq!((previous[&anna].as_slice(), previous[&ben].as_slice()), ([1].as_slice(), [2].as_slice()));
+    }
+
+    #[test]
+    fn soft_deleted_user_never_wins() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        db.insert_user("Exact", "Guess", "exact@example.com", 100).unwrap();
+        db.insert_user("Close", "Guess", "close@example.com", 101).unwrap();
+        db.insert_user("Far", "Guess", "far@example.com", 500).unwrap();
+        let exact = db.get_all_users().unwrap()[0].id;
+        db.delete_user(exact).unwrap();
+
+        for mode in [WinnerMode::Closest, WinnerMode::WeightedRandom] {
+            for seed in 0..20 {
+                db.calculate_winners(&[100, 100], false, mode, seed).unwrap();
+                let winners: Vec<i32> = db.get_sorted_users(100).unwrap()
+                    .into_iter()
+                    .filter(|u| u.winner)
+                    .map(|u| u.id)
+                    .collect();
+                assert_eq!(winners.len(), 2);
+                assert!(!winners.contains(&exact), "{:?} seed {}", mode, seed);
+            }
+        }
+
+        // nobody left to re-roll to once the live candidates are used up
+        let winner = db.get_sorted_users(100).unwrap()[0].id;
+        assert!(db.reroll_winner(winner, "test").unwrap().is_none());
+    }
+
+    #[test]
+    fn draw_history_holds_distances_beyond_i32() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        db.insert_user("Anna", "Schmidt", "anna@example.com", i32::MAX).unwrap();
+        db.calculate_winners(&[i32::MIN], false, WinnerMode::Closest, 0).unwrap();
+        let draw = db.get_draws().unwrap()[0].id;
+        assert_eq!(db.get_draw_winners(draw).unwrap()[0].distance, i32::MAX as i64 - i32::MIN as i64);
+    }
+
+    #[test]
+    fn reroll_refuses_winners_not_drawn_in_the_latest_draw() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let (anna, _) = db.insert_user("Anna", "Schmidt", "anna@example.com", 10).unwrap();
+        let (ben, _) = db.insert_user("Ben", "Meyer", "ben@example.com", 20).unwrap();
+        db.insert_user("Cem", "Kaya", "cem@example.com", 30).unwrap();
+        db.calculate_winners(&[10], false, WinnerMode::Closest, 0).unwrap();
+        db.calculate_winners(&[20], false, WinnerMode::Closest, 0).unwrap();
+
+        // Anna only won the earlier round, now she is set by hand
+        db.set_winner(anna, true).unwrap();
+        let e = db.reroll_winner(anna, "test").unwrap_err();
+        assert!(e.to_string().contains("latest draw"), "{}", e);
+        assert_eq!(db.get_draw_winners(1).unwrap().len(), 1);
+
+        assert!(db.reroll_winner(ben, "test").unwrap().is_some());
+    }
+
+    /// (user id, prize index) of the current winners, first prize first.
+    fn winners(db: &Database) -> Vec<(i32, i32)> {
+        let mut winners: Vec<(i32, i32)> = db.get_all_users().unwrap()
+            .into_iter()
+            .filter(|u| u.winner)
+            .map(|u| (u.id, u.prize_index.unwrap()))
+            .collect();
+        winners.sort_by_key(|&(_, prize)| prize);
+        winners
+    }
+
+    fn register(db: &Database, numbers: &[i32]) -> Vec<i32> {
+        numbers
+            .iter()
+            .enumerate()
+            .map(|(i, &number)| db.insert_user("Guest", &i.to_string(), &format!("guest{}@example.com", i), number).unwrap().0)
+            .collect()
+    }
+
+    #[test]
+    fn draw_on_an_empty_database_picks_nobody() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        for mode in [WinnerMode::Closest, WinnerMode::WeightedRandom] {
+            db.calculate_winners(&[100; 5], false, mode, 1).unwrap();
+            assert!(winners(&db).is_empty());
+        }
+        let draw = &db.get_draws().unwrap()[0];
+        assert_eq!((draw.winner_count, draw.participant_count), (0, 0));
+    }
+
+    #[test]
+    fn draw_with_exactly_enough_guests_gives_everyone_a_prize() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let ids = register(&db, &[70, 100, 40]);
+        db.calculate_winners(&[100, 100, 100], false, WinnerMode::Closest, 0).unwrap();
+        assert_eq!(winners(&db), vec![(ids[1], 0), (ids[0], 1), (ids[2], 2)]);
+    }
+
+    #[test]
+    fn draw_with_too_few_guests_leaves_the_last_prizes_empty() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let ids = register(&db, &[90, 101]);
+        for mode in [WinnerMode::Closest, WinnerMode::WeightedRandom] {
+            db.calculate_winners(&[100; 5], false, mode, 3).unwrap();
+            let won = winners(&db);
+            assert_eq!(won.iter().map(|&(_, prize)| prize).collect::<Vec<_>>(), vec![0, 1], "{:?}", mode);
+            assert!(ids.iter().all(|id| won.iter().any(|&(w, _)| w == *id)), "{:?}", mode);
+        }
+        assert_eq!(db.get_draws().unwrap()[0].winner_count, 2);
+    }
+
+    #[test]
+    fn exact_ties_go_to_the_earlier_registration() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        // 99 and 101 are as far off as each other, so are the three exact guesses
+        let ids = register(&db, &[101, 100, 99, 100, 100]);
+        db.calculate_winners(&[100, 100, 100, 100], false, WinnerMode::Closest, 0).unwrap();
+        assert_eq!(winners(&db), vec![(ids[1], 0), (ids[3], 1), (ids[4], 2), (ids[0], 3)]);
+
+        // the same draw again changes nothing
+        db.calculate_winners(&[100, 100, 100, 100], false, WinnerMode::Closest, 0).unwrap();
+        assert_eq!(winners(&db), vec![(ids[1], 0), (ids[3], 1), (ids[4], 2), (ids[0], 3)]);
+    }
+
+    #[test]
+    fn new_draw_replaces_the_previous_winners() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let ids = register(&db, &[10, 50, 90]);
+        db.calculate_winners(&[10], false, WinnerMode::Closest, 0).unwrap();
+        db.set_notified(ids[0], NotifyStatus::Confirmed).unwrap();
+        db.set_winner(ids[2], true).unwrap();
+        assert!(db.has_manual_overrides().unwrap());
+
+        db.calculate_winners(&[50], false, WinnerMode::Closest, 0).unwrap();
+        assert_eq!(winners(&db), vec![(ids[1], 0)]);
+        assert!(!db.has_manual_overrides().unwrap());
+        let users = db.get_all_users().unwrap();
+        assert!(users.iter().all(|u| u.notified == NotifyStatus::None), "old notify states survived");
+        assert_eq!(db.get_draws().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn restore_refuses_taken_email() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        db.insert_user("Old", "Entry", "anna@example.com", 1).unwrap();
+        let old = db.get_all_users().unwrap()[0].id;
+        db.delete_user(old).unwrap();
+        db.insert_user("New", "Entry", "Anna@example.com", 2).unwrap();
+
+        assert!(!db.restore_user(old).unwrap());
+        assert_eq!(db.get_deleted_users().unwrap().len(), 1);
+        assert_eq!(db.get_all_users().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn ticket_numbers_survive_deletions() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let (first, ticket) = db.insert_user("A", "A", "a@example.com", 1).unwrap();
+        assert_eq!(ticket, 1);
+        db.insert_user("B", "B", "b@example.com", 2).unwrap();
+        db.delete_user(first).unwrap();
+        db.purge_user(first).unwrap();
+
+        assert_eq!(db.insert_user("C", "C", "c@example.com", 3).unwrap().1, 3);
+        let tickets: Vec<i32> = db.get_all_users().unwrap().iter().map(|u| u.ticket_number).collect();
+        assert_eq!(tickets, vec![2, 3]);
+    }
+
+    /// Fresh file in the temp dir, removed again when dropped.
+    struct TempDb(PathBuf);
+
+    impl TempDb {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("winter_raten_{}_{}.db", name, std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            TempDb(path)
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+            // WAL side files
+            for suffix in ["-wal", "-shm"] {
+                let mut side = self.0.clone().into_os_string();
+                side.push(suffix);
+                let _ = std::fs::remove_file(side);
+            }
+        }
+    }
+
+    #[test]
+    fn in_memory_databases_say_so() {
+        assert!(Database::open(Path::new(":memory:")).unwrap().is_in_memory());
+        let file = TempDb::new("storage");
+        assert!(!Database::open(&file.0).unwrap().is_in_memory());
+    }
+
+    #[test]
+    fn migrations_upgrade_a_v1_database() {
+        let file = TempDb::new("v1");
+        {
+            let conn = Connection::open(&file.0).unwrap();
+            MIGRATIONS[0](&conn).unwrap();
+            conn.pragma_update(None, "user_version", 1).unwrap();
+            conn.execute_batch(
+                "INSERT INTO users (first_name, surname, email, number) VALUES ('Anna', 'Schmidt', 'anna@example.com', 90);
+                 INSERT INTO users (first_name, surname, email, number) VALUES ('Jonas', 'Weber', 'jonas@example.com', 120);",
+            ).unwrap();
+        }
+
+        let mut db = Database::open(&file.0).unwrap();
+        let version: i64 = db.conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len() as i64);
+
+        let tickets: Vec<i32> = db.get_all_users().unwrap().iter().map(|u| u.ticket_number).collect();
+        assert_eq!(tickets, vec![1, 2]);
+        let (id, ticket) = db.insert_user("Mia", "Koch", "mia@example.com", 100).unwrap();
+        assert_eq!(ticket, 3);
+
+        db.calculate_winners(&[100], false, WinnerMode::Closest, 0).unwrap();
+        assert!(db.get_user(id).unwrap().unwrap().winner);
+        db.set_notified(id, NotifyStatus::Confirmed).unwrap();
+        db.save_prizes(&[Prize { rank: 1, name: "Sled".into(), description: String::new() }]).unwrap();
+        db.set_winners_locked(true).unwrap();
+        assert!(db.set_winner(id, false).is_err());
+        db.set_winners_locked(false).unwrap();
+
+        db.delete_user(id).unwrap();
+        assert!(db.restore_user(id).unwrap());
+        assert_eq!(db.stats().unwrap().registrations, 3);
+        assert!(db.stats().unwrap().last_created.is_some());
+        assert!(!db.get_audit_log(None, None, 10).unwrap().is_empty());
+        db.set_actor(Some("operator"));
+        db.delete_user(id).unwrap();
+        let newest = db.get_audit_log(None, None, 2).unwrap();
+        assert_eq!((newest[0].action.as_str(), newest[0].actor.as_deref()), ("delete", Some("operator")));
+        assert_eq!(newest[1].actor, None);
+        db.set_actor(None);
+        assert!(db.restore_user(id).unwrap());
+
+        // opening again is a no-op
+        drop(db);
+        assert_eq!(Database::open(&file.0).unwrap().get_all_users().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn migrations_complete_the_draw_tables_of_early_files() {
+        // unversioned, and stamped by a build whose step 2 left the old tables alone
+        for stamped in [0, 11] {
+            let file = TempDb::new(&format!("draws_v{}", stamped));
+            {
+                let conn = Connection::open(&file.0).unwrap();
+                conn.execute_batch(
+                    "CREATE TABLE users (
+                        id INTEGER PRIMARY KEY,
+                        first_name TEXT NOT NULL,
+                        surname TEXT NOT NULL,
+                        email TEXT  NULL,
+                        number INTEGER NOT NULL,
+                        winner INTEGER NOT NULL DEFAULT 0,
+                        prize_index INTEGER NULL,
+                        manual_override INTEGER NOT NULL DEFAULT 0
+                    );
+                    CREATE TABLE draws (
+                        id INTEGER PRIMARY KEY,
+                        created_at INTEGER NOT NULL,
+                        targets TEXT NOT NULL,
+                        exclude_previous INTEGER NOT NULL DEFAULT 0
+                    );
+                    CREATE TABLE draw_winners (
+                        draw_id INTEGER NOT NULL REFERENCES draws(id),
+                        user_id INTEGER NOT NULL REFERENCES users(id),
+                        prize_index INTEGER NOT NULL,
+                        replaced_reason TEXT NULL,
+                        PRIMARY KEY (draw_id, user_id)
+                    );
+                    INSERT INTO users (first_name, surname, email, number) VALUES ('Anna', 'Schmidt', 'anna@example.com', 90);
+                    INSERT INTO draws (created_at, targets) VALUES (1700000000, '90');
+                    INSERT INTO draw_winners (draw_id, user_id, prize_index) VALUES (1, 1, 0);",
+                ).unwrap();
+                for step in &MIGRATIONS[..stamped] {
+                    step(&conn).unwrap();
+                }
+                conn.pragma_update(None, "user_version", stamped as i64).unwrap();
+            }
+
+            let db = Database::open(&file.0).unwrap();
+            db.insert_user("Ben", "Meyer", "ben@example.com", 100).unwrap();
+            db.calculate_winners(&[100], false, WinnerMode::Closest, 0).unwrap();
+            let draws = db.get_draws().unwrap();
+            assert_eq!(draws.len(), 2, "stamped {}", stamped);
+            assert_eq!(draws[0].mode, "closest");
+            assert_eq!(db.get_draw_winners(draws[0].id).unwrap().len(), 1);
+            assert_eq!(db.get_draw_winners(draws[1].id).unwrap().len(), 1);
+        }
+    }
+
+    #[test]
+    fn refuses_newer_schema() {
+        let file = TempDb::new("future");
+        Connection::open(&file.0).unwrap()
+            .pragma_update(None, "user_version", MIGRATIONS.len() as i64 + 1)
+            .unwrap();
+
+        let err = Database::open(&file.0).err().expect("newer schema must be refused");
+        assert!(err.to_string().contains("newer"), "{}", err);
+        assert!(Database::check_backup_schema(&file.0).is_err());
+    }
+
+    #[test]
+    fn backup_check_covers_every_table() {
+        let file = TempDb::new("backup_tables");
+        Database::open(&file.0).unwrap();
+        assert_eq!(Database::check_backup_schema(&file.0), Ok(()));
+
+        // a draws table the migrations would not repair
+        let conn = Connection::open(&file.0).unwrap();
+        conn.execute_batch(
+            "DROP TABLE draw_winners; DROP TABLE draws;
+             CREATE TABLE draws (id INTEGER PRIMARY KEY, mode TEXT);",
+        ).unwrap();
+        drop(conn);
+        let err = Database::check_backup_schema(&file.0).unwrap_err();
+        assert!(err.contains("draws table has no"), "{}", err);
+    }
+
+    #[test]
+    fn concurrent_reader_sees_no_lock_errors() {
+        let file = TempDb::new("wal");
+        let db = Database::open(&file.0).unwrap();
+        assert_eq!(db.stats().unwrap().journal_mode, "wal");
+
+        let path = file.0.clone();
+        let writer = std::thread::spawn(move || {
+            let db = Database::open(&path).unwrap();
+            for i in 0..300 {
+                db.insert_user("Writer", &i.to_string(), &format!("w{}@example.com", i), i + 1).unwrap();
+            }
+        });
+
+        let mut reads = 0;
+        while !writer.is_finished() {
+            db.get_all_users().expect("reader must not hit a lock error");
+            reads += 1;
+        }
+        writer.join().unwrap();
+        assert!(reads > 0);
+        assert_eq!(db.get_all_users().unwrap().len(), 300);
+    }
+
+    #[test]
+    fn writes_from_another_connection_bump_the_revision() {
+        let file = TempDb::new("revision");
+        let gui = Database::open(&file.0).unwrap();
+        let before = gui.data_revision().unwrap();
+        assert_eq!(gui.data_revision().unwrap(), before);
+
+        let cli = Database::open(&file.0).unwrap();
+        let (id, _) = cli.insert_user("Extern", "Writer", "extern@example.com", 7).unwrap();
+        let inserted = gui.data_revision().unwrap();
+        assert!(inserted > before);
+
+        cli.set_registration_open(false).unwrap();
+        cli.set_winner(id, true).unwrap();
+        assert!(gui.data_revision().unwrap() > inserted);
+    }
+
+    #[test]
+    fn draw_refuses_when_another_booth_wrote_since_the_preview() {
+        let file = TempDb::new("stale_draw");
+        let booth = Database::open(&file.0).unwrap();
+        booth.insert_user("Anna", "Schmidt", "anna@example.com", 42).unwrap();
+        let previewed = booth.data_revision().unwrap();
+
+        let other = Database::open(&file.0).unwrap();
+        other.insert_user("Ben", "Meyer", "ben@example.com", 40).unwrap();
+
+        assert!(!booth.calculate_winners_if_unchanged(previewed, &[40], false, WinnerMode::Closest, 0).unwrap());
+        assert!(booth.get_all_users().unwrap().iter().all(|u| !u.winner));
+        assert!(booth.get_draws().unwrap().is_empty());
+
+        let fresh = booth.data_revision().unwrap();
+        assert!(booth.calculate_winners_if_unchanged(fresh, &[40], false, WinnerMode::Closest, 0).unwrap());
+        assert_eq!(booth.get_draws().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn dry_run_picks_the_same_winners_without_writing() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        for (i, number) in [150, 290, 310, 300].into_iter().enumerate() {
+            db.insert_user("Guest", &i.to_string(), &format!("guest{}@example.com", i), number).unwrap();
+        }
+        for mode in [WinnerMode::Closest, WinnerMode::WeightedRandom] {
+            let revision = db.data_revision().unwrap();
+            let dry: Vec<i32> = db.preview_winners(&[300, 300], false, mode, 7).unwrap()
+                .into_iter()
+                .map(|w| w.user.id)
+                .collect();
+            assert_eq!(db.data_revision().unwrap(), revision);
+            assert!(db.get_draws().unwrap().len() < 2);
+
+            db.calculate_winners(&[300, 300], false, mode, 7).unwrap();
+            let users = db.get_all_users().unwrap();
+            let mut won: Vec<&User> = users.iter().filter(|u| u.winner).collect();
+            won.sort_by_key(|u| u.prize_index);
+            assert_eq!(won.iter().map(|u| u.id).collect::<Vec<_>>(), dry, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn previous_winners_sit_out_until_the_history_is_reset() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let (anna, _) = db.insert_user("Anna", "Schmidt", "anna@example.com", 42).unwrap();
+        db.insert_user("Ben", "Meyer", "ben@example.com", 45).unwrap();
+        let winner = |db: &Database| db.get_all_users().unwrap().into_iter().find(|u| u.winner).unwrap().first_name;
+
+        db.calculate_winners(&[42], true, WinnerMode::Closest, 0).unwrap();
+        assert_eq!(winner(&db), "Anna");
+        db.calculate_winners(&[42], true, WinnerMode::Closest, 0).unwrap();
+        assert_eq!(winner(&db), "Ben");
+        assert_eq!(db.get_previous_winners().unwrap()[&anna].len(), 1);
+
+        db.reset_winner_history().unwrap();
+        assert!(db.get_previous_winners().unwrap().is_empty());
+        db.calculate_winners(&[42], true, WinnerMode::Closest, 0).unwrap();
+        assert_eq!(winner(&db), "Anna");
+        assert_eq!(db.get_draws().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn window_geometry_round_trips() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        assert_eq!(db.window_geometry().unwrap(), None);
+
+        let geometry = WindowGeometry { x: 10.0, y: -20.5, width: 1024.0, height: 700.0 };
+        db.set_window_geometry(geometry).unwrap();
+        assert_eq!(db.window_geometry().unwrap(), Some(geometry));
+
+        db.set_ui_state("window_geometry", "garbage").unwrap();
+        assert_eq!(db.window_geometry().unwrap(), None);
+    }
+
+    #[test]
+    fn dev_settings_survive_reopening_and_corruption() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        assert_eq!(db.dev_settings().unwrap(), DevSettings::default());
+
+        let settings = DevSettings {
+            targets: Some("300, 250".to_string()),
+            mode: Some("weighted".to_string()),
+            exclude_previous: Some(true),
+            table_window: Some(ToolWindow {
+                open: true,
+                rect: Some(WindowGeometry { x: 900.0, y: 0.0, width: 700.0, height: 500.0 }),
+            }),
+            ..DevSettings::default()
+        };
+        let revision = db.data_revision().unwrap();
+        db.set_dev_settings(&settings).unwrap();
+        assert_eq!(db.dev_settings().unwrap(), settings);
+        // saving them is no data change that other windows need to pick up
+        assert_eq!(db.data_revision().unwrap(), revision);
+
+        db.set_ui_state("dev_settings", "{\"targets\": ").unwrap();
+        assert_eq!(db.dev_settings().unwrap(), DevSettings::default());
+    }
+
+    #[test]
+    fn form_draft_is_gone_once_the_form_is_empty() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let draft = ["Anna".to_string(), String::new(), "anna@exa".to_string(), String::new()];
+        db.set_form_draft(&draft).unwrap();
+        assert_eq!(db.form_draft().unwrap(), Some(draft));
+
+        db.set_form_draft(&Default::default()).unwrap();
+        assert_eq!(db.form_draft().unwrap(), None);
+    }
+
+    #[test]
+    fn upsert_corrects_known_ids_and_adds_the_rest() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let (anna, _) = db.insert_user("Anna", "Schmidt", "anna@example.com", 42).unwrap();
+        let (ben, _) = db.insert_user("Ben", "Meyer", "ben@example.com", 90).unwrap();
+        let row = |id, first_name: &str, email: &str, number: &str| ImportedUser {
+            row: 0,
+            id,
+            first_name: first_name.to_string(),
+            surname: "Meyer".to_string(),
+            email: email.to_string(),
+            number: number.to_string(),
+        };
+
+        let report = db.upsert_users(&[
+            row(Some(anna), "Anna", "anna@example.com", "43"),
+            row(Some(ben), "Ben", "ben@example.com", "90"),
+            row(None, "Carl", "carl@example.com", "120"),
+            row(Some(999), "Dora", "dora@example.com", "x"),
+        ]).unwrap();
+        assert_eq!(report.rows[0], RowOutcome::Updated(anna));
+        assert_eq!(report.rows[1], RowOutcome::Unchanged);
+        assert_eq!(report.rows[2], RowOutcome::Inserted(3));
+        assert_eq!(report.rows[3], RowOutcome::Skipped("Number must be a whole number".to_string()));
+        assert_eq!((report.updated(), report.inserted(), report.rejected()), (1, 1, 1));
+
+        let anna = db.get_user(anna).unwrap().unwrap();
+        assert_eq!((anna.surname.as_str(), anna.number), ("Meyer", 43));
+    }
+
+    #[test]
+    fn upsert_leaves_deleted_rows_alone_and_keeps_going_past_failures() {
+        let sqlite = Database::open(Path::new(":memory:")).unwrap();
+        let memory = MemoryStore::default();
+        // the override and the trait default share `upsert_outcome`
+        for db in [&sqlite as &dyn UserStore, &memory] {
+            let (anna, _) = db.insert_user("Anna", "Schmidt", "anna@example.com", 42).unwrap();
+            let (ben, _) = db.insert_user("Ben", "Meyer", "ben@example.com", 90).unwrap();
+            db.delete_user(anna).unwrap();
+            let row = |id, email: &str, number: &str| ImportedUser {
+                row: 0,
+                id,
+                first_name: "Anna".to_string(),
+                surname: "Meyer".to_string(),
+                email: email.to_string(),
+                number: number.to_string(),
+            };
+
+            let report = db.upsert_users(&[
+                row(Some(anna), "anna@example.com", "43"),
+                row(Some(ben), "carl@example.com", "91"),
+                row(None, "carl@example.com", "120"),
+                row(None, "dora@example.com", "7"),
+            ]).unwrap();
+            assert!(matches!(&report.rows[0], RowOutcome::Skipped(why) if why.contains("recycle bin")));
+            assert_eq!(report.rows[1], RowOutcome::Updated(ben));
+            assert!(matches!(report.rows[2], RowOutcome::Failed(_)), "email is taken by now");
+            assert!(matches!(report.rows[3], RowOutcome::Inserted(_)));
+
+            assert_eq!(db.get_deleted_users().unwrap()[0].number, 42);
+            let emails: Vec<String> = db.get_all_users().unwrap().into_iter().map(|u| u.email).collect();
+            assert_eq!(emails, vec!["carl@example.com", "dora@example.com"]);
+        }
+    }
+
+    #[test]
+    fn batch_insert_respects_the_guess_range() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        assert_eq!(db.guess_range().unwrap(), 1..=i32::MAX);
+        db.set_guess_range(1, 1000).unwrap();
+        assert_eq!(db.guess_range().unwrap(), 1..=1000);
+
+        let report = db.insert_users_batch(&[
+            new_user("Anna", "anna@example.com", 1000),
+            new_user("Ben", "ben@example.com", 1001),
+        ]).unwrap();
+        assert_eq!(report.rows[1], RowOutcome::Skipped("Guess must be between 1 and 1000".to_string()));
+        assert_eq!(report.inserted(), 1);
+    }
+
+    #[test]
+    fn write_gives_up_as_busy_after_the_timeout() {
+        let file = TempDb::new("busy_timeout");
+        let db = Database::open(&file.0).unwrap();
+        db.conn.busy_timeout(std::time::Duration::from_millis(50)).unwrap();
+
+        let holder = Connection::open(&file.0).unwrap();
+        holder.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let started = std::time::Instant::now();
+        let e = db.insert_user("Busy", "Booth", "busy@example.com", 7).unwrap_err();
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert_eq!(db_error_message(&e), "Database busy, please retry");
+
+        // the failed BEGIN left nothing open, the next write goes through
+        holder.execute_batch("COMMIT").unwrap();
+        db.insert_user("Busy", "Booth", "busy@example.com", 7).unwrap();
+    }
+
+    #[test]
+    fn insert_waits_out_a_held_write_lock() {
+        let file = TempDb::new("busy");
+        let db = Database::open(&file.0).unwrap();
+        // BEGIN IMMEDIATE waits inside SQLite until the holder commits
+
+        let holder = Connection::open(&file.0).unwrap();
+        holder.execute_batch("BEGIN IMMEDIATE; INSERT INTO settings (key, value) VALUES ('x', '1');").unwrap();
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let release = std::thread::spawn(move || {
+            locked_tx.send(()).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(150));
+            holder.execute_batch("COMMIT").unwrap();
+        });
+        locked_rx.recv().unwrap();
+
+        let (id, _) = db.insert_user("Busy", "Booth", "busy@example.com", 7).unwrap();
+        release.join().unwrap();
+        assert_eq!(db.get_user(id).unwrap().unwrap().number, 7);
+    }
+
+    fn new_user(first_name: &str, email: &str, number: i32) -> NewUser {
+        NewUser {
+            first_name: first_name.to_string(),
+            surname: "Test".to_string(),
+            email: email.to_string(),
+            number,
+            is_test: false,
+        }
+    }
+
+    #[test]
+    fn batch_insert_reports_bad_rows_and_keeps_the_rest() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let report = db.insert_users_batch(&[
+            new_user("Anna", "anna@example.com", 10),
+            new_user("", "nobody@example.com", 11),
+            new_user("Ben", "ben@example.com", 0),
+            new_user("Anna again", "ANNA@example.com", 12),
+            new_user("Carl", "carl@example.com", 13),
+        ]).unwrap();
+
+        assert_eq!(report.rows[0], RowOutcome::Inserted(1));
+        assert_eq!(report.rows[1], RowOutcome::Skipped("First name is required".to_string()));
+        assert_eq!(report.rows[2], RowOutcome::Skipped("Number must be >= 1".to_string()));
+        assert!(matches!(report.rows[3], RowOutcome::Failed(_)));
+        // the failed row gave its entry number back
+        assert_eq!(report.rows[4], RowOutcome::Inserted(2));
+        assert_eq!((report.inserted(), report.rejected()), (2, 3));
+        assert_eq!(db.get_all_users().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn cancelled_batch_insert_is_rolled_back() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let users = [new_user("Anna", "anna@example.com", 10), new_user("Ben", "ben@example.com", 11), new_user("Carl", "carl@example.com", 12)];
+        let mut seen = Vec::new();
+        let report = db.insert_users_batch_with(&users, &mut |done| {
+            seen.push(done);
+            done < 2
+        }).unwrap();
+        assert!(report.is_none());
+        assert_eq!(seen, vec![0, 1, 2]);
+        assert!(db.get_all_users().unwrap().is_empty());
+        // the entry numbers were given back as well
+        assert_eq!(db.insert_users_batch(&users[..1]).unwrap().rows, vec![RowOutcome::Inserted(1)]);
+    }
+
+    #[test]
+    fn batch_insert_of_10k_rows() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let users = generate_test_users(&mut StdRng::seed_from_u64(7), 10_000, 1, TestDistribution::Uniform, 1..=1000);
+
+        let report = db.insert_users_batch(&users).unwrap();
+        assert_eq!(report.inserted(), 10_000);
+        assert_eq!(db.get_all_users().unwrap().len(), 10_000);
+    }
+
+    #[test]
+    fn undelete_brings_back_the_winner_flag() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let (id, ticket) = db.insert_user("Anna", "Schmidt", "anna@example.com", 300).unwrap();
+        db.calculate_winners(&[300], false, WinnerMode::Closest, 0).unwrap();
+        let before = db.get_user(id).unwrap().unwrap();
+        assert!(before.winner);
+
+        db.delete_user(id).unwrap();
+        assert!(db.undelete_user(&before).unwrap());
+        let back = db.get_user(id).unwrap().unwrap();
+        assert_eq!((back.winner, back.prize_index, back.ticket_number), (true, Some(0), ticket));
+
+        // purged in between: inserted again with the same values
+        let (other, _) = db.insert_user("Ben", "Braun", "ben@example.com", 5).unwrap();
+        let ben = db.get_user(other).unwrap().unwrap();
+        db.delete_user(other).unwrap();
+        db.purge_user(other).unwrap();
+        assert!(db.undelete_user(&ben).unwrap());
+        assert_eq!(db.get_all_users().unwrap().len(), 2);
+
+        db.delete_user(other).unwrap();
+        db.insert_user("Benno", "Braun", "BEN@example.com", 6).unwrap();
+        assert!(!db.undelete_user(&ben).unwrap());
+    }
+
+    /// Same registrations into both stores.
+    fn fill(store: &dyn UserStore) {
+        for (i, number) in [120, 310, 290, 300, 505, 42, 299, 301].into_iter().enumerate() {
+            store.insert_user("User", &i.to_string(), &format!("u{}@example.com", i), number).unwrap();
+        }
+    }
+
+    #[test]
+    fn memory_store_draws_like_sqlite() {
+        let sqlite = Database::open(Path::new(":memory:")).unwrap();
+        let memory = MemoryStore::default();
+        let stores: [&dyn UserStore; 2] = [&sqlite, &memory];
+
+        for (mode, seed) in [(WinnerMode::Closest, 0), (WinnerMode::WeightedRandom, 99)] {
+            let results: Vec<Vec<(i32, Option<i32>)>> = stores.iter().map(|store| {
+                if store.get_all_users().unwrap().is_empty() {
+                    fill(*store);
+                }
+                store.calculate_winners(&[300, 300, 500], false, mode, seed).unwrap();
+                store.get_sorted_users(300).unwrap()
+                    .into_iter()
+                    .filter(|u| u.winner)
+                    .map(|u| (u.id, u.prize_index))
+                    .collect()
+            }).collect();
+            assert_eq!(results[0].len(), 3);
+            assert_eq!(results[0], results[1], "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn memory_store_behind_the_app_mutex() {
+        let store: Arc<Mutex<dyn UserStore + Send>> = Arc::new(Mutex::new(MemoryStore::default()));
+        let db = lock_database(&store);
+
+        let (id, ticket) = db.insert_user("Anna", "Schmidt", "anna@example.com", 42).unwrap();
+        assert_eq!(ticket, 1);
+        let taken = db.insert_user("Anna", "S", "ANNA@example.com", 7).unwrap_err();
+        assert!(is_constraint_violation(&taken));
+
+        assert_eq!(db.undo_registration(id).unwrap().unwrap().number, 42);
+        assert!(db.get_all_users().unwrap().is_empty());
+        assert!(db.restore_user(id).unwrap());
+
+        db.set_winners_locked(true).unwrap();
+        assert!(db.calculate_winners(&[40], false, WinnerMode::Closest, 0).is_err());
+        assert!(db.stats().is_err(), "SQLite-only features report themselves as unsupported");
+    }
+
+    /// Timing only, run with `cargo test --release -- --ignored`; debug
+    /// builds on a busy machine are too noisy for a fixed bound.
+    #[test]
+    #[ignore]
+    fn bench_batch_insert_10k() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let users = generate_test_users(&mut StdRng::seed_from_u64(7), 10_000, 1, TestDistribution::Uniform, 1..=1000);
+
+        let start = std::time::Instant::now();
+        let report = db.insert_users_batch(&users).unwrap();
+        let elapsed = start.elapsed();
+
+        assert_eq!(report.inserted(), 10_000);
+        assert!(elapsed < std::time::Duration::from_secs(1), "took {:?}", elapsed);
+    }
+
+    /// Timing only, run with `cargo test -- --ignored`. Covers the queries
+    /// the indexes serve; `get_sorted_users` sorts in Rust and gains nothing.
+    #[test]
+    #[ignore]
+    fn bench_indexed_lookups_100k() {
+        let file = TempDb::new("bench");
+        let db = Database::open(&file.0).unwrap();
+        let users = generate_test_users(&mut StdRng::seed_from_u64(1), 100_000, 1, TestDistribution::Uniform, 1..=1000);
+        db.insert_users_batch(&users).unwrap();
+
+        let time = || {
+            let start = std::time::Instant::now();
+            for _ in 0..10 {
+                db.number_stats().unwrap();
+                for number in 1..100 {
+                    let _: i64 = db.conn
+                        .prepare_cached("SELECT COUNT(*) FROM users WHERE number = ?1").unwrap()
+                        .query_row([number], |row| row.get(0)).unwrap();
+                }
+            }
+            start.elapsed() / 10
+        };
+
+        db.conn.execute_batch("DROP INDEX users_number;").unwrap();
+        let before = time();
+        db.conn.flush_prepared_statement_cache();
+        MIGRATIONS[7](&db.conn).unwrap();
+        let after = time();
+        assert!(after < before, "index made it slower: {:?} vs {:?}", after, before);
+    }
+}
@@ -0,0 +1,211 @@
+//! Turning guesses into winners.
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::db::{Prize, User};
+
+/// How a draw turns guesses into winners.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum WinnerMode {
+    /// closest guess per target wins
+    #[default]
+    Closest,
+    /// lottery where closer guesses have better odds
+    #[value(name = "weighted")]
+    WeightedRandom,
+}
+
+impl WinnerMode {
+    /// Name stored in the draw history and used on the command line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WinnerMode::Closest => "closest",
+            WinnerMode::WeightedRandom => "weighted",
+        }
+    }
+
+    /// Inverse of `as_str`, anything unknown is a closest-guess draw.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "weighted" => WinnerMode::WeightedRandom,
+            _ => WinnerMode::Closest,
+        }
+    }
+}
+
+/// Prize name for a winner's prize index, "—" if there is none for that rank.
+pub fn prize_name(prizes: &[Prize], prize_index: Option<i32>) -> String {
+    prize_index
+        .and_then(|idx| prizes.iter().find(|p| p.rank == idx + 1))
+        .map(|p| p.name.clone())
+        .unwrap_or_else(|| "—".to_string())
+}
+
+/// A would-be winner: who, for which prize, and how far off the guess was.
+#[derive(Clone)]
+pub struct RankedWinner {
+    pub user: User,
+    pub prize_index: usize,
+    pub distance: i64,
+}
+
+/// The draw itself, shared by every `UserStore` so they all pick alike.
+pub fn pick_winners(candidates: Vec<User>, targets: &[i32], mode: WinnerMode, seed: u64) -> Vec<RankedWinner> {
+    match mode {
+        WinnerMode::Closest => rank_winners(candidates, targets),
+        WinnerMode::WeightedRandom => weighted_winners(candidates, targets, &mut StdRng::seed_from_u64(seed)),
+    }
+}
+
+/// Table order: winners by prize, then everyone else by distance to `max_number`.
+/// Winners set by hand have no prize and come after the drawn ones.
+pub fn sort_for_table(users: &mut [User], max_number: i32) {
+    users.sort_by(|a, b| {
+        match (a.winner, b.winner) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            (true, true) => (a.prize_index.is_none(), a.prize_index).cmp(&(b.prize_index.is_none(), b.prize_index)),
+            _ => distance(a, max_number).cmp(&distance(b, max_number)),
+        }
+    });
+}
+
+/// Everyone in `users` with their distance to `target`, closest first. On
+/// equal distance the earlier registration comes first. Both modes rank
+/// alike, since the weighted lottery's odds fall with the distance too.
+pub fn rank_by_distance(users: &[User], target: i32, _mode: WinnerMode) -> Vec<(User, i64)> {
+    let mut ranked: Vec<(User, i64)> = users.iter().map(|u| (u.clone(), distance(u, target))).collect();
+    ranked.sort_by_key(|(_, d)| *d);
+    ranked
+}
+
+/// How far `user`'s guess is off `target`, in i64 so no guess can overflow.
+pub fn distance(user: &User, target: i32) -> i64 {
+    (i64::from(user.number) - i64::from(target)).abs()
+}
+
+/// Picks one winner per target from `candidates`, never the same user twice.
+/// Shared by the preview and the committed draw so both always agree.
+pub fn rank_winners(mut candidates: Vec<User>, targets: &[i32]) -> Vec<RankedWinner> {
+    let mut winners = Vec::new();
+    for (prize_index, &target) in targets.iter().enumerate() {
+        let Some((user, distance)) = rank_by_distance(&candidates, target, WinnerMode::Closest).into_iter().next() else {
+            break;
+        };
+        candidates.retain(|c| c.id != user.id);
+        winners.push(RankedWinner { user, prize_index, distance });
+    }
+    winners
+}
+
+/// Index of the guess closest to `target`. On equal distance the first one
+/// wins, i.e. the earlier registration.
+pub fn closest_candidate(candidates: &[User], target: i32) -> Option<usize> {
+    candidates.iter()
+        .enumerate()
+        .min_by_key(|(_, u)| distance(u, target))
+        .map(|(pos, _)| pos)
+}
+
+/// Lottery version of `rank_winners`: each remaining candidate's chance is
+/// proportional to 1 / (distance + 1).
+pub fn weighted_winners(mut candidates: Vec<User>, targets: &[i32], rng: &mut impl Rng) -> Vec<RankedWinner> {
+    let mut winners = Vec::new();
+    for (prize_index, &target) in targets.iter().enumerate() {
+        let Some(pos) = weighted_candidate(&candidates, target, rng) else {
+            break;
+        };
+        let user = candidates.remove(pos);
+        let distance = distance(&user, target);
+        winners.push(RankedWinner { user, prize_index, distance });
+    }
+    winners
+}
+
+/// Lottery pick for one target, odds falling with the distance to it.
+pub fn weighted_candidate(candidates: &[User], target: i32, rng: &mut impl Rng) -> Option<usize> {
+    let weights = candidates.iter().map(|u| 1.0 / (distance(u, target) as f64 + 1.0));
+    rand::distributions::WeightedIndex::new(weights).ok().map(|dist| rng.sample(dist))
+}
+
+/// Parses "300" or "120, 300, 450" into one target per prize. A single
+/// target is repeated `winner_count` times so it keeps the classic top-N draw.
+/// None for anything that gives no target at all, e.g. a winner count of 0.
+pub fn parse_targets(input: &str, winner_count: usize) -> Option<Vec<i32>> {
+    let targets = input
+        .split(',')
+        .map(|t| t.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+
+    let targets = match targets.len() {
+        1 => vec![targets[0]; winner_count],
+        _ => targets,
+    };
+    (!targets.is_empty()).then_some(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::user;
+
+    #[test]
+    fn parse_targets_repeats_a_single_target_and_rejects_none() {
+        assert_eq!(parse_targets("300", 3), Some(vec![300, 300, 300]));
+        assert_eq!(parse_targets(" 120, 450 ", 3), Some(vec![120, 450]));
+        assert_eq!(parse_targets("300", 0), None);
+        assert_eq!(parse_targets("3x0", 1), None);
+    }
+
+    #[test]
+    fn rank_winners_prefers_earlier_registration_on_ties() {
+        let users = vec![user(1, 290), user(2, 310), user(3, 300)];
+        let winners = rank_winners(users, &[300, 300]);
+
+        let ids: Vec<i32> = winners.iter().map(|w| w.user.id).collect();
+        assert_eq!(ids, vec![3, 1]);
+        assert_eq!(winners[1].distance, 10);
+    }
+
+    #[test]
+    fn rank_winners_never_picks_the_same_user_twice() {
+        let users = vec![user(1, 100), user(2, 500)];
+        let winners = rank_winners(users, &[100, 100, 100]);
+
+        let ids: Vec<i32> = winners.iter().map(|w| w.user.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(winners[1].prize_index, 1);
+    }
+
+    #[test]
+    fn table_puts_drawn_winners_first_and_manual_ones_after_them() {
+        let mut users = vec![user(1, 300), user(2, 10), user(3, 290), user(4, i32::MIN), user(5, 50)];
+        (users[1].winner, users[1].prize_index) = (true, None);
+        (users[2].winner, users[2].prize_index) = (true, Some(1));
+        (users[4].winner, users[4].prize_index) = (true, Some(0));
+        sort_for_table(&mut users, i32::MAX);
+
+        let ids: Vec<i32> = users.iter().map(|u| u.id).collect();
+        assert_eq!(ids, vec![5, 3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn rank_by_distance_lists_everyone_closest_first() {
+        let users = vec![user(1, 290), user(2, 350), user(3, 300), user(4, 310)];
+        let ranked = rank_by_distance(&users, 300, WinnerMode::Closest);
+
+        let order: Vec<(i32, i64)> = ranked.iter().map(|(u, d)| (u.id, *d)).collect();
+        assert_eq!(order, vec![(3, 0), (1, 10), (4, 10), (2, 50)]);
+        assert_eq!(rank_by_distance(&users, 300, WinnerMode::WeightedRandom).len(), 4);
+    }
+
+    #[test]
+    fn rank_by_distance_does_not_overflow_on_extreme_guesses() {
+        let users = vec![user(1, i32::MIN), user(2, i32::MAX)];
+        let ranked = rank_by_distance(&users, i32::MAX, WinnerMode::Closest);
+
+        assert_eq!(ranked[0].1, 0);
+        assert_eq!(ranked[1].1, i64::from(i32::MAX) - i64::from(i32::MIN));
+    }
+}
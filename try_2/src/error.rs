@@ -0,0 +1,46 @@
+//! The one error type the UI reports, as a toast or in the error banner.
+
+use std::fmt;
+use crate::db::{db_error_message, is_constraint_violation};
+use crate::tr;
+
+/// What a database call or a file import behind a button can end in.
+#[derive(Debug)]
+pub enum AppError {
+    /// SQLite refused or failed the call
+    Db(rusqlite::Error),
+    /// a file to import could not be read or doesn't fit our columns
+    File(String),
+}
+
+impl AppError {
+    /// Text for the toast, "Database busy" instead of SQLite's wording.
+    pub fn message(&self) -> String {
+        match self {
+            AppError::Db(e) => db_error_message(e),
+            AppError::File(why) => tr!("Error: {}", why),
+        }
+    }
+
+    /// A UNIQUE or CHECK constraint refused the write, e.g. a taken email.
+    pub fn is_constraint_violation(&self) -> bool {
+        matches!(self, AppError::Db(e) if is_constraint_violation(e))
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Db(e) => e.fmt(f),
+            AppError::File(why) => f.write_str(why),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::Db(e)
+    }
+}
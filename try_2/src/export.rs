@@ -0,0 +1,365 @@
+//! Writers for the data leaving the app.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use simple_excel_writer::*;
+use crate::db::{Prize, User};
+use crate::draw::prize_name;
+use crate::form::normalize_email;
+use crate::i18n::tr;
+use crate::password::salted_hash;
+use crate::tr;
+
+/// File types the export can write.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Xlsx,
+    Csv,
+    Json,
+}
+
+/// Writes `users` to `filename` in `format`, the table formats with
+/// `columns`; what the `export` subcommand calls.
+pub fn export_users(
+    users: &[User],
+    prizes: &[Prize],
+    format: ExportFormat,
+    columns: &[ExportColumn],
+    filename: &str,
+) -> Result<String, String> {
+    match format {
+        ExportFormat::Xlsx => write_excel(users, prizes, columns, filename),
+        ExportFormat::Csv => write_csv(users, prizes, columns, filename),
+        ExportFormat::Json => {
+            let json = serde_json::to_string_pretty(users).map_err(|e| e.to_string())?;
+            std::fs::write(filename, json).map_err(|e| save_error(&e))?;
+            Ok(tr!("Exported {} users to {}", users.len(), filename))
+        }
+    }
+}
+
+/// `path`, or `name_1.ext`, `name_2.ext`, ... if that is taken already.
+pub fn unique_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    let mut candidate = path.to_path_buf();
+    let mut counter = 1;
+    while candidate.exists() {
+        candidate = path.with_file_name(format!("{}_{}{}", stem, counter, ext));
+        counter += 1;
+    }
+    candidate
+}
+
+/// `prefix`, with the event name in front in a form every file system takes,
+/// e.g. "Winter Fest 2025" gives "Winter_Fest_2025_registrations".
+pub fn file_prefix(event_name: &str, prefix: &str) -> String {
+    let event: String = event_name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    if event.is_empty() { prefix.to_string() } else { format!("{}_{}", event, prefix) }
+}
+
+/// Toast text for a failed save. Excel locks the files it has open, which
+/// Windows reports as a sharing violation (32) or access denied.
+fn save_error(e: &io::Error) -> String {
+    if e.kind() == io::ErrorKind::PermissionDenied || matches!(e.raw_os_error(), Some(32 | 33)) {
+        tr("Please close the existing Excel file and try again").to_string()
+    } else {
+        tr!("Save error: {}", e)
+    }
+}
+
+/// Current winners' addresses, ready for a mail client's To: field.
+/// Re-rolled winners have lost their flag and are left out.
+pub fn winner_emails(users: &[User]) -> String {
+    users.iter()
+        .filter(|u| u.winner && !u.email.trim().is_empty())
+        .map(|u| u.email.trim())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Hex digits of the salted hash kept in place of an email.
+const ANONYMIZED_EMAIL_LEN: usize = 16;
+
+/// `u` for sharing with sponsors: the email becomes a salted hash, so one
+/// mailbox still shows up as one person without saying who it is.
+pub fn anonymize_user(u: &User, salt: &str) -> User {
+    let email = normalize_email(&u.email);
+    let email = if email.is_empty() {
+        email
+    } else {
+        salted_hash(&email, salt)[..ANONYMIZED_EMAIL_LEN].to_string()
+    };
+    User { email, ..u.clone() }
+}
+
+/// "Anna Lena" -> "A. L.", for exports that shouldn't carry full names.
+pub fn initials(name: &str) -> String {
+    name.split_whitespace()
+        .filter_map(|word| word.chars().next())
+        .map(|c| format!("{}.", c))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// One column of the "Registrations" sheet and the CSV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportColumn {
+    Id,
+    FirstName,
+    Surname,
+    Email,
+    Number,
+    Winner,
+    Prize,
+}
+
+impl ExportColumn {
+    /// Order of a fresh install.
+    pub const ALL: [ExportColumn; 7] = [
+        ExportColumn::Id,
+        ExportColumn::FirstName,
+        ExportColumn::Surname,
+        ExportColumn::Email,
+        ExportColumn::Number,
+        ExportColumn::Winner,
+        ExportColumn::Prize,
+    ];
+
+    /// Header cell. Not translated, the import finds its columns by it.
+    pub fn header(self) -> &'static str {
+        match self {
+            ExportColumn::Id => "ID",
+            ExportColumn::FirstName => "First Name",
+            ExportColumn::Surname => "Surname",
+            ExportColumn::Email => "Email",
+            ExportColumn::Number => "Number",
+            ExportColumn::Winner => "Winner",
+            ExportColumn::Prize => "Prize",
+        }
+    }
+
+    fn width(self) -> f32 {
+        match self {
+            ExportColumn::Id => 8.0,
+            ExportColumn::Email => 25.0,
+            ExportColumn::Number => 12.0,
+            ExportColumn::Winner => 10.0,
+            ExportColumn::FirstName | ExportColumn::Surname | ExportColumn::Prize => 15.0,
+        }
+    }
+
+    fn value(self, user: &User, prizes: &[Prize]) -> String {
+        match self {
+            ExportColumn::Id => user.id.to_string(),
+            ExportColumn::FirstName => user.first_name.clone(),
+            ExportColumn::Surname => user.surname.clone(),
+            ExportColumn::Email => user.email.clone(),
+            ExportColumn::Number => user.number.to_string(),
+            ExportColumn::Winner => if user.winner { "YES" } else { "NO" }.to_string(),
+            ExportColumn::Prize if user.winner => prize_name(prizes, user.prize_index),
+            ExportColumn::Prize => String::new(),
+        }
+    }
+}
+
+/// (column, exported) in file order, as saved in the settings: doubles are
+/// dropped and columns the list lacks are added at the end, exported.
+pub fn column_spec(saved: &[(ExportColumn, bool)]) -> Vec<(ExportColumn, bool)> {
+    let mut spec: Vec<(ExportColumn, bool)> = Vec::new();
+    for &(column, on) in saved {
+        if !spec.iter().any(|(c, _)| *c == column) {
+            spec.push((column, on));
+        }
+    }
+    for column in ExportColumn::ALL {
+        if !spec.iter().any(|(c, _)| *c == column) {
+            spec.push((column, true));
+        }
+    }
+    spec
+}
+
+/// The exported columns of `spec`, in order.
+pub fn enabled_columns(spec: &[(ExportColumn, bool)]) -> Vec<ExportColumn> {
+    spec.iter().filter(|(_, on)| *on).map(|(c, _)| *c).collect()
+}
+
+/// Writes a "Registrations" sheet with `columns` and a "Winners" sheet
+/// to `filename` and returns the message for the toast.
+pub fn write_excel(users: &[User], prizes: &[Prize], columns: &[ExportColumn], filename: &str) -> Result<String, String>
+{
+    if columns.is_empty() {
+        return Err(tr("No columns selected for the export").to_string());
+    }
+    let mut workbook = Workbook::create(filename);
+    let mut sheet = workbook.create_sheet("Registrations");
+
+    for column in columns {
+        sheet.add_column(Column { width: column.width() });
+    }
+
+    workbook.write_sheet(&mut sheet, |sheet_writer|
+        {
+            let sw = sheet_writer;
+
+            let mut header = Row::new();
+            for column in columns {
+                header.add_cell(column.header());
+            }
+            sw.append_row(header)?;
+
+        for user in users.iter()
+        {
+            let mut row = Row::new();
+            for column in columns {
+                row.add_cell(column.value(user, prizes));
+            }
+            sw.append_row(row)?;
+        }
+
+        Ok(())
+    }).map_err(|e| tr!("Write error: {}", format!("{:?}", e)))?;
+
+    let mut winners: Vec<&User> = users.iter().filter(|u| u.winner).collect();
+    winners.sort_by_key(|u| u.prize_index.unwrap_or(i32::MAX));
+
+    let mut winner_sheet = workbook.create_sheet("Winners");
+    winner_sheet.add_column(Column { width: 8.0 });
+    winner_sheet.add_column(Column { width: 15.0 });
+    winner_sheet.add_column(Column { width: 15.0 });
+    winner_sheet.add_column(Column { width: 12.0 });
+    winner_sheet.add_column(Column { width: 25.0 });
+    winner_sheet.add_column(Column { width: 12.0 });
+
+    workbook.write_sheet(&mut winner_sheet, |sw|
+        {
+            sw.append_row(row!["Rank", "First Name", "Surname", "Number", "Prize", "Notified"])?;
+
+            for user in &winners
+            {
+                sw.append_row
+                (
+                    row!
+                    [
+                        user.prize_index.map(|p| (p + 1).to_string()).unwrap_or_default(),
+                        user.first_name.clone(),
+                        user.surname.clone(),
+                        user.number.to_string(),
+                        prize_name(prizes, user.prize_index),
+                        user.notified.as_str()
+                    ]
+                )?;
+            }
+
+            Ok(())
+        }).map_err(|e| tr!("Write error: {}", format!("{:?}", e)))?;
+
+    workbook.close().map_err(|e| save_error(&e))?;
+
+    Ok(tr!("Exported {} users to {}", users.len(), filename))
+}
+
+/// Same columns as the Excel "Registrations" sheet.
+pub fn write_csv(users: &[User], prizes: &[Prize], columns: &[ExportColumn], filename: &str) -> Result<String, String> {
+    if columns.is_empty() {
+        return Err(tr("No columns selected for the export").to_string());
+    }
+    let header: Vec<&str> = columns.iter().map(|c| c.header()).collect();
+    let mut out = header.join(",");
+    out.push('\n');
+    for user in users {
+        let fields: Vec<String> = columns.iter().map(|c| csv_field(&c.value(user, prizes))).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    std::fs::write(filename, out).map_err(|e| save_error(&e))?;
+    Ok(tr!("Exported {} users to {}", users.len(), filename))
+}
+
+/// Quotes a field when it holds a comma, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::named;
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("Anna"), "Anna");
+        assert_eq!(csv_field("Schmidt, Jr."), "\"Schmidt, Jr.\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn anonymized_emails_hash_the_same_mailbox_alike() {
+        let user = |email: &str| User { email: email.to_string(), ..named(1, "Anna", "Schmidt", 42) };
+        let anna = anonymize_user(&user("anna@example.com"), "salt");
+        assert_eq!(anna.email.len(), ANONYMIZED_EMAIL_LEN);
+        assert!(!anna.email.contains('@'));
+        assert_eq!((anna.first_name.as_str(), anna.number), ("Anna", 42));
+        assert_eq!(anonymize_user(&user(" Anna+Raffle@Example.com"), "salt").email, anna.email);
+        assert_ne!(anonymize_user(&user("anna@example.com"), "other").email, anna.email);
+        assert_eq!(anonymize_user(&user(""), "salt").email, "");
+
+        assert_eq!(initials("Anna Lena"), "A. L.");
+        assert_eq!(initials(" Özlem "), "Ö.");
+        assert_eq!(initials(""), "");
+    }
+
+    #[test]
+    fn columns_follow_the_spec() {
+        let spec = column_spec(&[(ExportColumn::Email, true), (ExportColumn::Id, false), (ExportColumn::Email, false)]);
+        assert_eq!(spec.len(), ExportColumn::ALL.len());
+        assert_eq!(spec[..3], [(ExportColumn::Email, true), (ExportColumn::Id, false), (ExportColumn::FirstName, true)]);
+        assert_eq!(column_spec(&[]), ExportColumn::ALL.map(|c| (c, true)));
+
+        let path = std::env::temp_dir().join(format!("winter_raten_columns_{}.csv", std::process::id()));
+        let user = User { winner: true, prize_index: Some(0), ..named(3, "Anna", "Schmidt", 42) };
+        let prizes = [Prize { rank: 1, name: "Sled".to_string(), description: String::new() }];
+        let columns = [ExportColumn::Prize, ExportColumn::Number, ExportColumn::FirstName];
+        write_csv(&[user], &prizes, &columns, path.to_str().unwrap()).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(text, "Prize,Number,First Name\nSled,42,Anna\n");
+        assert!(write_csv(&[], &prizes, &[], path.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn event_name_goes_in_front() {
+        assert_eq!(file_prefix("", "registrations"), "registrations");
+        assert_eq!(file_prefix(" Winter Fest/2025 ", "registrations"), "Winter_Fest_2025_registrations");
+    }
+
+    #[test]
+    fn taken_paths_get_a_counter() {
+        let dir = std::env::temp_dir().join(format!("winter_raten_export_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("registrations_1.xlsx");
+        assert_eq!(unique_path(&path), path);
+
+        std::fs::write(&path, "").unwrap();
+        std::fs::write(dir.join("registrations_1_1.xlsx"), "").unwrap();
+        assert_eq!(unique_path(&path), dir.join("registrations_1_2.xlsx"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn locked_files_get_a_readable_message() {
+        let locked = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert_eq!(save_error(&locked), "Please close the existing Excel file and try again");
+        assert!(save_error(&io::Error::from(io::ErrorKind::NotFound)).starts_with("Save error: "));
+    }
+}
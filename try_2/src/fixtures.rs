@@ -0,0 +1,267 @@
+//! Registrations and a store for unit tests, so every module builds them alike.
+
+use std::cell::{Cell, RefCell};
+use rusqlite::Result as SqlResult;
+use crate::db::{BatchReport, NewUser, NotifyStatus, Prize, RowOutcome, User, UserStore};
+use crate::draw::WinnerMode;
+use crate::form::validate_form;
+
+/// A registration with placeholder details, "First3 Last3" at
+/// user3@example.com, whose entry number is its id. Change single fields
+/// with `User { winner: true, ..user(3, 42) }`.
+pub fn user(id: i32, number: i32) -> User {
+    User {
+        id,
+        first_name: format!("First{}", id),
+        surname: format!("Last{}", id),
+        email: format!("user{}@example.com", id),
+        number,
+        winner: false,
+        prize_index: None,
+        notified: NotifyStatus::None,
+        ticket_number: id,
+    }
+}
+
+/// `user` with a real name; the email is the lowercased first name at example.com.
+pub fn named(id: i32, first_name: &str, surname: &str, number: i32) -> User {
+    User {
+        first_name: first_name.to_string(),
+        surname: surname.to_string(),
+        email: format!("{}@example.com", first_name.to_lowercase()),
+        ..user(id, number)
+    }
+}
+
+struct MemoryRow {
+    user: User,
+    deleted: bool,
+    manual_override: bool,
+    edited: bool,
+}
+
+/// `UserStore` on a plain list, for tests that don't need SQLite.
+#[derive(Default)]
+pub struct MemoryStore {
+    rows: RefCell<Vec<MemoryRow>>,
+    last_ticket: Cell<i32>,
+    prizes: RefCell<Vec<Prize>>,
+    winners_locked: Cell<bool>,
+    registration_closed: Cell<bool>,
+    registration_close_at: Cell<Option<i64>>,
+}
+
+/// Same code and message SQLite gives for a refused write.
+fn constraint(message: &str) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+        Some(message.to_string()),
+    )
+}
+
+impl MemoryStore {
+    fn email_taken(&self, email: &str, except: i32) -> bool {
+        !email.is_empty() && self.rows.borrow().iter().any(|r| {
+            !r.deleted && r.user.id != except && r.user.email.eq_ignore_ascii_case(email)
+        })
+    }
+
+    fn with_row<R>(&self, id: i32, f: impl FnOnce(&mut MemoryRow) -> R) -> SqlResult<R> {
+        let mut rows = self.rows.borrow_mut();
+        let row = rows.iter_mut().find(|r| r.user.id == id).ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+        Ok(f(row))
+    }
+}
+
+impl UserStore for MemoryStore {
+    fn insert_user(&self, firstname: &str, surname: &str, email: &str, number: i32) -> SqlResult<(i32, i32)> {
+        if self.email_taken(email, 0) {
+            return Err(constraint("UNIQUE constraint failed: users.email"));
+        }
+        let mut rows = self.rows.borrow_mut();
+        let id = rows.len() as i32 + 1;
+        let ticket = self.last_ticket.get() + 1;
+        self.last_ticket.set(ticket);
+        rows.push(MemoryRow {
+            user: User {
+                id,
+                first_name: firstname.to_string(),
+                surname: surname.to_string(),
+                email: email.to_string(),
+                number,
+                winner: false,
+                prize_index: None,
+                notified: NotifyStatus::None,
+                ticket_number: ticket,
+            },
+            deleted: false,
+            manual_override: false,
+            edited: false,
+        });
+        Ok((id, ticket))
+    }
+
+    fn insert_users_batch(&self, users: &[NewUser]) -> SqlResult<BatchReport> {
+        let guesses = self.guess_range()?;
+        let rows = users.iter().map(|u| {
+            let number = u.number.to_string();
+            if let Some(error) = validate_form([&u.first_name, &u.surname, &u.email, &number], &guesses).first() {
+                return RowOutcome::Skipped(error.message.clone());
+            }
+            match self.insert_user(&u.first_name, &u.surname, &u.email, u.number) {
+                Ok((_, ticket)) => RowOutcome::Inserted(ticket),
+                Err(e) => RowOutcome::Failed(e.to_string()),
+            }
+        }).collect();
+        Ok(BatchReport { rows })
+    }
+
+    fn update_user(&self, id: i32, firstname: &str, surname: &str, email: &str, number: i32) -> SqlResult<()> {
+        if self.email_taken(email, id) {
+            return Err(constraint("UNIQUE constraint failed: users.email"));
+        }
+        self.with_row(id, |r| {
+            r.user.first_name = firstname.to_string();
+            r.user.surname = surname.to_string();
+            r.user.email = email.to_string();
+            r.user.number = number;
+            r.edited = true;
+        })
+    }
+
+    fn undoable(&self, id: i32) -> SqlResult<bool> {
+        Ok(self.with_row(id, |r| {
+            !r.deleted && !r.user.winner && !r.edited && r.user.notified == NotifyStatus::None
+        }).unwrap_or(false))
+    }
+
+    fn undo_registration(&self, id: i32) -> SqlResult<Option<User>> {
+        if !self.undoable(id)? {
+            return Ok(None);
+        }
+        self.with_row(id, |r| {
+            r.deleted = true;
+            Some(r.user.clone())
+        })
+    }
+
+    fn delete_user(&self, id: i32) -> SqlResult<()> {
+        self.with_row(id, |r| {
+            r.deleted = true;
+            r.user.winner = false;
+            r.user.prize_index = None;
+        })
+    }
+
+    fn restore_user(&self, id: i32) -> SqlResult<bool> {
+        let email = self.with_row(id, |r| r.user.email.clone())?;
+        if self.email_taken(&email, id) {
+            return Ok(false);
+        }
+        self.with_row(id, |r| r.deleted = false)?;
+        Ok(true)
+    }
+
+    fn undelete_user(&self, user: &User) -> SqlResult<bool> {
+        if self.email_taken(&user.email, user.id) {
+            return Ok(false);
+        }
+        let mut rows = self.rows.borrow_mut();
+        rows.retain(|r| r.user.id != user.id);
+        rows.push(MemoryRow { user: user.clone(), deleted: false, manual_override: false, edited: false });
+        rows.sort_by_key(|r| r.user.id);
+        Ok(true)
+    }
+
+    fn purge_user(&self, id: i32) -> SqlResult<()> {
+        self.rows.borrow_mut().retain(|r| r.user.id != id || !r.deleted);
+        Ok(())
+    }
+
+    fn get_all_users(&self) -> SqlResult<Vec<User>> {
+        Ok(self.rows.borrow().iter().filter(|r| !r.deleted).map(|r| r.user.clone()).collect())
+    }
+
+    fn get_deleted_users(&self) -> SqlResult<Vec<User>> {
+        Ok(self.rows.borrow().iter().filter(|r| r.deleted).map(|r| r.user.clone()).collect())
+    }
+
+    fn get_user(&self, id: i32) -> SqlResult<Option<User>> {
+        Ok(self.rows.borrow().iter().find(|r| r.user.id == id).map(|r| r.user.clone()))
+    }
+
+    fn registration_revision(&self) -> SqlResult<(i64, i64)> {
+        let users = self.get_all_users()?;
+        Ok((users.len() as i64, users.iter().map(|u| u.id as i64).max().unwrap_or(0)))
+    }
+
+    fn calculate_winners(&self, targets: &[i32], exclude_previous: bool, mode: WinnerMode, seed: u64) -> SqlResult<()> {
+        if self.winners_locked.get() {
+            return Err(constraint("Winners are locked"));
+        }
+        let winners = self.preview_winners(targets, exclude_previous, mode, seed)?;
+        for r in self.rows.borrow_mut().iter_mut() {
+            let won = winners.iter().find(|w| w.user.id == r.user.id);
+            r.user.winner = won.is_some();
+            r.user.prize_index = won.map(|w| w.prize_index as i32);
+            r.user.notified = NotifyStatus::None;
+            r.manual_override = false;
+        }
+        Ok(())
+    }
+
+    fn set_winner(&self, id: i32, winner: bool) -> SqlResult<()> {
+        if self.winners_locked.get() {
+            return Err(constraint("Winners are locked"));
+        }
+        self.with_row(id, |r| {
+            r.user.winner = winner;
+            r.user.prize_index = None;
+            r.manual_override = true;
+        })
+    }
+
+    fn set_notified(&self, id: i32, status: NotifyStatus) -> SqlResult<()> {
+        self.with_row(id, |r| r.user.notified = status)
+    }
+
+    fn has_manual_overrides(&self) -> SqlResult<bool> {
+        Ok(self.rows.borrow().iter().any(|r| r.manual_override))
+    }
+
+    fn winners_locked(&self) -> SqlResult<bool> {
+        Ok(self.winners_locked.get())
+    }
+
+    fn set_winners_locked(&self, locked: bool) -> SqlResult<()> {
+        self.winners_locked.set(locked);
+        Ok(())
+    }
+
+    fn get_prizes(&self) -> SqlResult<Vec<Prize>> {
+        Ok(self.prizes.borrow().clone())
+    }
+
+    fn save_prizes(&self, prizes: &[Prize]) -> SqlResult<()> {
+        *self.prizes.borrow_mut() = prizes.to_vec();
+        Ok(())
+    }
+
+    fn registration_open(&self) -> SqlResult<bool> {
+        Ok(!self.registration_closed.get())
+    }
+
+    fn set_registration_open(&self, open: bool) -> SqlResult<()> {
+        self.registration_closed.set(!open);
+        Ok(())
+    }
+
+    fn registration_close_at(&self) -> SqlResult<Option<i64>> {
+        Ok(self.registration_close_at.get())
+    }
+
+    fn set_registration_close_at(&self, close_at: Option<i64>) -> SqlResult<()> {
+        self.registration_close_at.set(close_at);
+        Ok(())
+    }
+}
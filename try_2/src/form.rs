@@ -0,0 +1,563 @@
+//! Checks on what participants type in, and spotting double registrations.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+use crate::db::{ImportedUser, User};
+use crate::i18n::tr;
+use crate::tr;
+
+/// Longest first name or surname the form accepts, in characters.
+pub const MAX_NAME_LEN: usize = 50;
+/// Longest email the form accepts, in characters.
+pub const MAX_EMAIL_LEN: usize = 100;
+
+/// Cuts `text` down to `max` characters (not bytes, so umlauts stay intact).
+pub fn truncate_chars(text: &mut String, max: usize) {
+    if let Some((idx, _)) = text.char_indices().nth(max) {
+        text.truncate(idx);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The registration form's inputs, in display order.
+pub enum FormField {
+    FirstName,
+    Surname,
+    Email,
+    Number,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A problem with one input, shown below it.
+pub struct FieldError {
+    pub field: FormField,
+    pub message: String,
+}
+
+/// Everything wrong with the registration form, in field order.
+/// `guesses` is the range organizers allow, see `guess_range_label`.
+pub fn validate_form(fields: [&str; 4], guesses: &RangeInclusive<i32>) -> Vec<FieldError> {
+    let [first_name, surname, email, number] = fields.map(str::trim);
+    let mut errors = Vec::new();
+    let mut required = |field, value: &str, message: &str| {
+        if value.is_empty() {
+            errors.push(FieldError { field, message: message.to_string() });
+        }
+    };
+    required(FormField::FirstName, first_name, tr("First name is required"));
+    required(FormField::Surname, surname, tr("Surname is required"));
+    required(FormField::Email, email, tr("Email is required"));
+    required(FormField::Number, number, tr("Number is required"));
+
+    // only what is surely a typo; the mailbox itself can't be checked here
+    let plausible = match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && !domain.is_empty() && !domain.contains('@'),
+        None => false,
+    };
+    if !email.is_empty() && (!plausible || email.contains(char::is_whitespace)) {
+        errors.push(FieldError { field: FormField::Email, message: tr("Email must look like name@example.com").to_string() });
+    }
+
+    if !number.is_empty() {
+        let message = match number.parse::<i32>() {
+            Ok(n) if guesses.contains(&n) => None,
+            Ok(_) if *guesses.end() == i32::MAX => Some(tr!("Number must be >= {}", guesses.start())),
+            Ok(_) => Some(tr!("Guess must be between {} and {}", guesses.start(), guesses.end())),
+            Err(_) => Some(tr("Number must be a whole number").to_string()),
+        };
+        if let Some(message) = message {
+            errors.push(FieldError { field: FormField::Number, message });
+        }
+    }
+    errors
+}
+
+/// "1 to 1000", or "1 to ∞" while there is no upper bound.
+pub fn guess_range_label(guesses: &RangeInclusive<i32>) -> String {
+    if *guesses.end() == i32::MAX {
+        tr!("{} to ∞", guesses.start())
+    } else {
+        tr!("{} to {}", guesses.start(), guesses.end())
+    }
+}
+
+/// Trims all form fields; None if any of them is blank.
+pub fn trim_form_fields(fields: [&str; 4]) -> Option<[&str; 4]> {
+    let trimmed = fields.map(str::trim);
+    if trimmed.iter().any(|f| f.is_empty()) {
+        None
+    } else {
+        Some(trimmed)
+    }
+}
+
+/// Lower-case inside a name, "Anna von der Heide", unless they end it.
+const NAME_PARTICLES: [&str; 14] = [
+    "von", "vom", "zu", "zum", "zur", "der", "den", "van", "ten", "ter", "de", "da", "di", "du",
+];
+
+/// "JOHN  o'brien" -> "John O'Brien": capital after a space, hyphen or
+/// apostrophe, the rest lower-case, runs of spaces collapsed. Particles
+/// like "von" stay small unless they are the last word. Only names typed
+/// all small or all capitals are touched; "De Luca" or "McDonald" was
+/// spelled on purpose and only gets its spacing collapsed.
+pub fn title_case(name: &str) -> String {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    let has_lower = name.chars().any(char::is_lowercase);
+    let has_upper = name.chars().any(char::is_uppercase);
+    if has_lower && has_upper {
+        return words.join(" ");
+    }
+    let mut result = String::with_capacity(name.len());
+    for (i, word) in words.iter().enumerate() {
+        if !result.is_empty() {
+            result.push(' ');
+        }
+        let lower = word.to_lowercase();
+        if i + 1 < words.len() && NAME_PARTICLES.contains(&lower.as_str()) {
+            result.push_str(&lower);
+            continue;
+        }
+        let mut capital = true;
+        for c in word.chars() {
+            if capital {
+                result.extend(c.to_uppercase());
+            } else {
+                result.extend(c.to_lowercase());
+            }
+            capital = matches!(c, '-' | '\'' | '’');
+        }
+    }
+    result
+}
+
+/// What gets stored for a registration, from the form and the importers
+/// alike: trimmed, runs of spaces in names collapsed, the email lower-case.
+/// `title_case_names` also runs the names through `title_case`.
+pub fn normalize_registration(first_name: &str, surname: &str, email: &str, title_case_names: bool) -> [String; 3] {
+    let name = |name: &str| {
+        if title_case_names {
+            title_case(name)
+        } else {
+            name.split_whitespace().collect::<Vec<_>>().join(" ")
+        }
+    };
+    [name(first_name), name(surname), email.trim().to_lowercase()]
+}
+
+/// `normalize_registration` for every row of an import.
+pub fn normalize_imported(rows: &mut [ImportedUser], title_case_names: bool) {
+    for row in rows {
+        [row.first_name, row.surname, row.email] =
+            normalize_registration(&row.first_name, &row.surname, &row.email, title_case_names);
+    }
+}
+
+/// Lets each key through at most once per `interval`: the booth uses a
+/// single key, the HTTP server one per client address.
+pub struct RateLimiter<K> {
+    pub interval: Duration,
+    last: HashMap<K, Instant>,
+}
+
+impl<K: Hash + Eq> RateLimiter<K> {
+    pub fn new(interval: Duration) -> Self {
+        RateLimiter { interval, last: HashMap::new() }
+    }
+
+    /// Whether `key` may submit at `now`. Refused attempts don't restart the wait.
+    pub fn allow(&mut self, key: K, now: Instant) -> bool {
+        if let Some(last) = self.last.get(&key)
+            && now.duration_since(*last) < self.interval
+        {
+            return false;
+        }
+        // clients that went quiet are forgotten, so the map stays small
+        self.last.retain(|_, t| now.duration_since(*t) < self.interval);
+        self.last.insert(key, now);
+        true
+    }
+}
+
+/// Guesses per number, for the hint under the form's number field.
+pub fn count_numbers(users: &[User]) -> HashMap<i32, usize> {
+    let mut counts = HashMap::new();
+    for user in users {
+        *counts.entry(user.number).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Lower-case, trimmed and without a "+tag", so anna+raffle@x.de and
+/// Anna@x.de count as the same mailbox.
+pub fn normalize_email(email: &str) -> String {
+    let email = email.trim().to_lowercase();
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            let local = local.split('+').next().unwrap_or(local);
+            format!("{}@{}", local, domain)
+        }
+        None => email,
+    }
+}
+
+/// Edit distance in characters (insert, delete, substitute).
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            row[j + 1] = substitute.min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// Lower-case with accents and umlauts dropped, "Müller" -> "muller".
+fn fold(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.trim().to_lowercase().chars() {
+        match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => out.push('a'),
+            'ç' | 'č' => out.push('c'),
+            'è' | 'é' | 'ê' | 'ë' => out.push('e'),
+            'ì' | 'í' | 'î' | 'ï' => out.push('i'),
+            'ñ' => out.push('n'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => out.push('o'),
+            'š' => out.push('s'),
+            'ù' | 'ú' | 'û' | 'ü' => out.push('u'),
+            'ý' | 'ÿ' => out.push('y'),
+            'ž' => out.push('z'),
+            'ß' => out.push_str("ss"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Jaro-Winkler similarity, 1.0 for equal strings and 0.0 for nothing in common.
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    if a.is_empty() || b.is_empty() {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut taken = vec![false; b.len()];
+    let mut matched = Vec::new();
+    for (i, ca) in a.iter().enumerate() {
+        let range = i.saturating_sub(window)..(i + window + 1).min(b.len());
+        if let Some(j) = range.into_iter().find(|&j| !taken[j] && b[j] == *ca) {
+            taken[j] = true;
+            matched.push(*ca);
+        }
+    }
+    if matched.is_empty() {
+        return 0.0;
+    }
+    let in_b = b.iter().zip(&taken).filter(|(_, t)| **t).map(|(c, _)| c);
+    let transpositions = matched.iter().zip(in_b).filter(|(x, y)| x != y).count() / 2;
+    let m = matched.len() as f64;
+    let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0;
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count() as f64;
+    jaro + prefix * 0.1 * (1.0 - jaro)
+}
+
+/// Below this a user is not listed by `fuzzy_search` at all.
+const FUZZY_THRESHOLD: f64 = 0.85;
+
+/// Users matching `query` despite typos and missing accents, best first.
+/// Every word of the query is scored against the closest name or email
+/// word; ticket ("#12") and guess numbers only match exactly.
+pub fn fuzzy_search<'a>(users: &'a [User], query: &str) -> Vec<(&'a User, f64)> {
+    let query = fold(query);
+    let words: Vec<&str> = query.split_whitespace().collect();
+    if words.is_empty() {
+        return users.iter().map(|u| (u, 1.0)).collect();
+    }
+
+    let mut ranked: Vec<(&User, f64)> = users
+        .iter()
+        .filter_map(|user| {
+            if query.trim_start_matches('#') == user.ticket_number.to_string() || query == user.number.to_string() {
+                return Some((user, 1.0));
+            }
+            let name = fold(&format!("{} {}", user.first_name, user.surname));
+            let email = fold(&user.email);
+            if name.contains(&query) || email.contains(&query) {
+                return Some((user, 1.0));
+            }
+            let local = email.split('@').next().unwrap_or_default();
+            let tokens: Vec<&str> = name.split_whitespace().chain(local.split(['.', '_', '-'])).collect();
+            let score = words
+                .iter()
+                .map(|word| tokens.iter().map(|t| jaro_winkler(word, t)).fold(0.0, f64::max))
+                .sum::<f64>()
+                / words.len() as f64;
+            (score >= FUZZY_THRESHOLD).then_some((user, score))
+        })
+        .collect();
+    // stable, so equal scores keep the table order
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+/// Groups of suspected double registrations: same normalized email, or
+/// full names at most `max_name_distance` edits apart. Groups keep id order.
+/// Only names sharing the first name or the surname are compared, so a big
+/// event doesn't compare everyone with everyone; typos in both parts at
+/// once go unnoticed.
+pub fn find_duplicates(users: &[User], max_name_distance: usize) -> Vec<Vec<User>> {
+    // tiny union-find over the indices
+    let mut parent: Vec<usize> = (0..users.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut by_email: HashMap<String, usize> = HashMap::new();
+    for (i, u) in users.iter().enumerate() {
+        let email = normalize_email(&u.email);
+        if email.is_empty() {
+            continue;
+        }
+        if let Some(&first) = by_email.get(&email) {
+            let (a, b) = (root(&mut parent, first), root(&mut parent, i));
+            parent[b] = a;
+        } else {
+            by_email.insert(email, i);
+        }
+    }
+
+    let names: Vec<String> = users.iter()
+        .map(|u| format!("{} {}", u.first_name.trim(), u.surname.trim()).to_lowercase())
+        .collect();
+    let mut blocks: HashMap<(usize, String), Vec<usize>> = HashMap::new();
+    for (i, u) in users.iter().enumerate() {
+        for (part, name) in [&u.first_name, &u.surname].into_iter().enumerate() {
+            blocks.entry((part, name.trim().to_lowercase())).or_default().push(i);
+        }
+    }
+    let lengths: Vec<usize> = names.iter().map(|n| n.chars().count()).collect();
+    for block in blocks.values() {
+        for (n, &i) in block.iter().enumerate() {
+            for &j in &block[n + 1..] {
+                // the distance is at least the difference in length
+                if lengths[i].abs_diff(lengths[j]) <= max_name_distance
+                    && levenshtein(&names[i], &names[j]) <= max_name_distance
+                {
+                    let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                    parent[b] = a;
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<User>> = HashMap::new();
+    for (i, u) in users.iter().enumerate() {
+        groups.entry(root(&mut parent, i)).or_default().push(u.clone());
+    }
+    let mut groups: Vec<Vec<User>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    groups.sort_by_key(|g| g[0].id);
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::user;
+
+    #[test]
+    fn trim_form_fields_strips_whitespace() {
+        assert_eq!(
+            trim_form_fields(["  Anna ", "Schmidt\t", " anna@example.com", " 42 "]),
+            Some(["Anna", "Schmidt", "anna@example.com", "42"])
+        );
+    }
+
+    #[test]
+    fn trim_form_fields_rejects_whitespace_only() {
+        assert_eq!(trim_form_fields(["Anna", "   ", "anna@example.com", "42"]), None);
+        assert_eq!(trim_form_fields(["", "Schmidt", "anna@example.com", "42"]), None);
+    }
+
+    #[test]
+    fn title_case_handles_hyphens_apostrophes_and_spacing() {
+        assert_eq!(title_case("JOHN"), "John");
+        assert_eq!(title_case("mary-jane"), "Mary-Jane");
+        assert_eq!(title_case("o'brien"), "O'Brien");
+        assert_eq!(title_case("  anna   lena "), "Anna Lena");
+        assert_eq!(title_case("ÉLODIE VON DER HEIDE"), "Élodie von der Heide");
+        assert_eq!(title_case("ursula de"), "Ursula De");
+        assert_eq!(title_case(""), "");
+    }
+
+    #[test]
+    fn title_case_keeps_mixed_case_spellings() {
+        assert_eq!(title_case("De Luca"), "De Luca");
+        assert_eq!(title_case("McDonald"), "McDonald");
+        assert_eq!(title_case("jOhN"), "jOhN");
+        assert_eq!(title_case("  van  Dijk "), "van Dijk");
+    }
+
+    #[test]
+    fn normalize_registration_trims_collapses_and_lowercases() {
+        assert_eq!(
+            normalize_registration("  ANNA   LENA ", " von  der heide", " Anna.Heide@Example.COM ", true),
+            ["Anna Lena", "von der Heide", "anna.heide@example.com"]
+        );
+        // without title case the spelling is kept, only the spacing changes
+        assert_eq!(
+            normalize_registration(" McDONALD ", "de  la Cruz\t", "A@B.de", false),
+            ["McDONALD", "de la Cruz", "a@b.de"]
+        );
+
+        let mut rows = vec![ImportedUser {
+            row: 2,
+            id: None,
+            first_name: "JÜRGEN".to_string(),
+            surname: "groß-mann ".to_string(),
+            email: "J@X.de ".to_string(),
+            number: "7".to_string(),
+        }];
+        normalize_imported(&mut rows, true);
+        assert_eq!((rows[0].first_name.as_str(), rows[0].surname.as_str(), rows[0].email.as_str()), ("Jürgen", "Groß-Mann", "j@x.de"));
+    }
+
+    #[test]
+    fn rate_limiter_waits_per_key() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(Duration::from_secs(2));
+        assert!(limiter.allow("a", start));
+        assert!(!limiter.allow("a", start + Duration::from_millis(1500)));
+        assert!(limiter.allow("b", start + Duration::from_millis(1500)));
+        // the refused attempt above didn't push the window
+        assert!(limiter.allow("a", start + Duration::from_secs(2)));
+
+        let mut off = RateLimiter::new(Duration::ZERO);
+        assert!(off.allow((), start) && off.allow((), start));
+    }
+
+    #[test]
+    fn validate_form_names_each_failing_field() {
+        let any = 1..=i32::MAX;
+        assert!(validate_form(["Anna", "Schmidt", "anna@example.com", "42"], &any).is_empty());
+
+        let errors = validate_form(["Anna", " ", "anna@example.com", ""], &any);
+        let fields: Vec<FormField> = errors.iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec![FormField::Surname, FormField::Number]);
+        assert_eq!(errors[1].message, "Number is required");
+
+        assert_eq!(validate_form(["Anna", "Schmidt", "a@b", "0"], &any)[0].message, "Number must be >= 1");
+        assert_eq!(validate_form(["Anna", "Schmidt", "a@b", "4x"], &any)[0].message, "Number must be a whole number");
+        for email in ["anna", "anna@", "@example.com", "a@b@c", "anna @example.com"] {
+            let errors = validate_form(["Anna", "Schmidt", email, "42"], &any);
+            assert_eq!(errors.iter().map(|e| e.field).collect::<Vec<_>>(), vec![FormField::Email], "{:?}", email);
+        }
+    }
+
+    #[test]
+    fn validate_form_enforces_the_guess_range() {
+        let range = 1..=1000;
+        assert!(validate_form(["Anna", "Schmidt", "a@b", "1000"], &range).is_empty());
+        assert_eq!(validate_form(["Anna", "Schmidt", "a@b", "1001"], &range)[0].message, "Guess must be between 1 and 1000");
+        assert_eq!(guess_range_label(&range), "1 to 1000");
+        assert_eq!(guess_range_label(&(5..=i32::MAX)), "5 to ∞");
+    }
+
+    #[test]
+    fn truncate_chars_limits_length() {
+        let mut name = "x".repeat(10_000);
+        truncate_chars(&mut name, MAX_NAME_LEN);
+        assert_eq!(name.len(), MAX_NAME_LEN);
+
+        let mut short = "Anna".to_string();
+        truncate_chars(&mut short, MAX_NAME_LEN);
+        assert_eq!(short, "Anna");
+    }
+
+    #[test]
+    fn truncate_chars_respects_char_boundaries() {
+        let mut name = "Jürgen".to_string();
+        truncate_chars(&mut name, 2);
+        assert_eq!(name, "Jü");
+    }
+
+    #[test]
+    fn levenshtein_counts_edits() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("anna", "anna"), 0);
+        assert_eq!(levenshtein("anna", "ana"), 1);
+        assert_eq!(levenshtein("müller", "muller"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn jaro_winkler_scores_similarity() {
+        assert_eq!(jaro_winkler("anna", "anna"), 1.0);
+        assert_eq!(jaro_winkler("abc", "xyz"), 0.0);
+        assert!((jaro_winkler("martha", "marhta") - 0.961).abs() < 0.001);
+        assert!(jaro_winkler("schmidt", "schmitd") > jaro_winkler("schmidt", "schulz"));
+    }
+
+    #[test]
+    fn fuzzy_search_finds_accented_and_misspelled_names() {
+        let mut users = vec![user(1, 10), user(2, 20), user(3, 30), user(4, 40), user(5, 50)];
+        (users[0].first_name, users[0].surname) = ("José".into(), "Müller".into());
+        (users[1].first_name, users[1].surname) = ("Jonas".into(), "Mueller".into());
+        (users[2].first_name, users[2].surname) = ("Anna".into(), "Schmidt".into());
+        (users[3].first_name, users[3].surname) = ("Lena".into(), "Weber".into());
+        (users[4].first_name, users[4].surname) = ("Renée".into(), "Krämer".into());
+        let ids = |query: &str| -> Vec<i32> { fuzzy_search(&users, query).iter().map(|(u, _)| u.id).collect() };
+
+        // accents and umlauts need not be typed
+        assert_eq!(ids("jose muller"), vec![1]);
+        assert_eq!(ids("Renee Kramer"), vec![5]);
+        // the exact spelling ranks above "Mueller"
+        assert_eq!(ids("Muller"), vec![1, 2]);
+        // typos
+        assert_eq!(ids("Schmitd"), vec![3]);
+        assert_eq!(ids("ana shmidt"), vec![3]);
+        assert_eq!(ids("Weeber"), vec![4]);
+        assert!(ids("Zimmermann").is_empty());
+        // numbers stay exact
+        assert_eq!(ids("#3"), vec![3]);
+        assert_eq!(ids("40"), vec![4]);
+        assert_eq!(ids("  ").len(), 5);
+    }
+
+    #[test]
+    fn normalize_email_ignores_case_and_tags() {
+        assert_eq!(normalize_email(" Anna+Raffle@Example.com "), "anna@example.com");
+        assert_eq!(normalize_email("no-at-sign"), "no-at-sign");
+    }
+
+    #[test]
+    fn find_duplicates_groups_by_email_and_similar_name() {
+        let mut users = vec![user(1, 10), user(2, 20), user(3, 30), user(4, 40)];
+        (users[0].first_name, users[0].surname, users[0].email) = ("Anna".into(), "Schmidt".into(), "anna@x.de".into());
+        (users[1].first_name, users[1].surname, users[1].email) = ("Jonas".into(), "Weber".into(), "ANNA+2@x.de".into());
+        (users[2].first_name, users[2].surname, users[2].email) = ("Lena".into(), "Koch".into(), "lena@x.de".into());
+        (users[3].first_name, users[3].surname, users[3].email) = ("Lena".into(), "Kooch".into(), "other@x.de".into());
+
+        let groups: Vec<Vec<i32>> = find_duplicates(&users, 1)
+            .iter()
+            .map(|g| g.iter().map(|u| u.id).collect())
+            .collect();
+        assert_eq!(groups, vec![vec![1, 2], vec![3, 4]]);
+
+        assert_eq!(find_duplicates(&users, 0).len(), 1);
+
+        // neither part matches, so the pair is never compared
+        (users[3].first_name, users[3].surname) = ("Lina".into(), "Kooch".into());
+        assert_eq!(find_duplicates(&users, 2).len(), 1);
+    }
+}
@@ -0,0 +1,280 @@
+//! Small read-only JSON API for the venue's info screens.
+//!
+//! Plain HTTP/1.1 on a std `TcpListener`, one request per connection;
+//! the screens only poll a handful of tiny endpoints.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use serde_json::{Value, json};
+use crate::db::{UserStore, lock_database};
+use crate::draw::prize_name;
+use crate::form::RateLimiter;
+
+/// How long the accept loop sleeps when nobody is connecting; also the
+/// longest `stop` has to wait for the thread.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Slow or stuck clients are dropped after this long.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Every endpoint only reads, so one client may ask for each of them this
+/// often; far below any info screen's polling, only floods are refused.
+const READ_INTERVAL: Duration = Duration::from_millis(200);
+/// Where `local_ip` pretends to send to: the private ranges first, so a LAN
+/// without internet still has a route, then the internet for v4 and v6.
+const ROUTE_PROBES: [&str; 5] = [
+    "192.168.254.254:9",
+    "10.254.254.254:9",
+    "172.31.254.254:9",
+    "1.1.1.1:9",
+    "[2606:4700:4700::1111]:9",
+];
+
+type SharedStore = Arc<Mutex<dyn UserStore + Send>>;
+
+/// Running server; stops and joins its thread when dropped.
+pub struct HttpServer {
+    addr: SocketAddr,
+    target: Arc<Mutex<Option<i32>>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HttpServer {
+    /// Binds `addr` and answers requests on a background thread.
+    pub fn start(addr: &str, database: SharedStore) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let target = Arc::new(Mutex::new(None));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (target, stop) = (Arc::clone(&target), Arc::clone(&stop));
+            std::thread::spawn(move || {
+                let mut limiter = RateLimiter::new(READ_INTERVAL);
+                while !stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, peer)) => {
+                            let target = *target.lock().unwrap_or_else(|p| p.into_inner());
+                            if let Err(e) = serve(stream, peer.ip(), &database, target, &mut limiter) {
+                                log::warn!("HTTP request from {} failed: {}", peer, e);
+                            }
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+                        Err(e) => log::error!("HTTP accept failed: {}", e),
+                    }
+                }
+            })
+        };
+
+        Ok(HttpServer { addr, target, stop, thread: Some(thread) })
+    }
+
+    /// Where it actually listens, e.g. after binding port 0.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Target number `/stats` reports, owned by the dev window.
+    pub fn set_target(&self, target: Option<i32>) {
+        *self.target.lock().unwrap_or_else(|p| p.into_inner()) = target;
+    }
+}
+
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// This machine's address as other devices on the LAN see it. Connecting
+/// a UDP socket sends nothing, the OS only picks the interface it would
+/// route through. A private IPv4 beats a public one beats IPv6; None
+/// without any network.
+pub fn local_ip() -> Option<IpAddr> {
+    let probe = |remote: &str| {
+        let remote: SocketAddr = remote.parse().ok()?;
+        let socket = UdpSocket::bind(if remote.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }).ok()?;
+        socket.connect(remote).ok()?;
+        socket.local_addr().ok().map(|addr| addr.ip())
+    };
+    best_local_ip(ROUTE_PROBES.iter().filter_map(|remote| probe(remote)))
+}
+
+fn best_local_ip(candidates: impl Iterator<Item = IpAddr>) -> Option<IpAddr> {
+    candidates
+        .filter(|ip| !ip.is_loopback() && !ip.is_unspecified())
+        .min_by_key(|ip| match ip {
+            IpAddr::V4(v4) if v4.is_private() => 0,
+            IpAddr::V4(_) => 1,
+            IpAddr::V6(_) => 2,
+        })
+}
+
+fn serve(
+    stream: TcpStream,
+    peer: IpAddr,
+    database: &SharedStore,
+    target: Option<i32>,
+    limiter: &mut RateLimiter<(IpAddr, String)>,
+) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // headers are not needed, but have to be read before answering
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    log::debug!("HTTP {} {} from {}", method, path, peer);
+    // the lock is held only while the answer is built, not while sending it
+    let endpoint = path.split('?').next().unwrap_or(path);
+    let (status, body) = if endpoint == "/healthz" || limiter.allow((peer, endpoint.to_string()), Instant::now()) {
+        respond(method, path, &*lock_database(database), target)
+    } else {
+        ("429 Too Many Requests", json!({ "error": "please wait a moment" }))
+    };
+
+    let body = body.to_string();
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Status line and JSON body for one request.
+fn respond(method: &str, path: &str, db: &dyn UserStore, target: Option<i32>) -> (&'static str, Value) {
+    if method != "GET" {
+        return ("405 Method Not Allowed", json!({ "error": "only GET is supported" }));
+    }
+    // query strings are ignored
+    let path = path.split('?').next().unwrap_or(path);
+    let result = match path {
+        "/healthz" => return ("200 OK", json!({ "status": "ok" })),
+        "/stats" => stats(db, target),
+        "/winners" => winners(db),
+        _ => return ("404 Not Found", json!({ "error": "not found" })),
+    };
+    match result {
+        Ok(body) => ("200 OK", body),
+        Err(e) => ("500 Internal Server Error", json!({ "error": e.to_string() })),
+    }
+}
+
+fn stats(db: &dyn UserStore, target: Option<i32>) -> rusqlite::Result<Value> {
+    let (count, _) = db.registration_revision()?;
+    Ok(json!({
+        "registrations": count,
+        "registration_open": db.registration_open()?,
+        "target": target,
+    }))
+}
+
+/// Current winners by prize, without email addresses.
+fn winners(db: &dyn UserStore) -> rusqlite::Result<Value> {
+    let prizes = db.get_prizes()?;
+    let mut winners: Vec<_> = db.get_all_users()?.into_iter().filter(|u| u.winner).collect();
+    winners.sort_by_key(|u| (u.prize_index.is_none(), u.prize_index));
+    Ok(winners
+        .iter()
+        .map(|u| json!({
+            "ticket_number": u.ticket_number,
+            "first_name": u.first_name,
+            "surname": u.surname,
+            "number": u.number,
+            "prize": u.prize_index.map(|p| p + 1),
+            "prize_name": prize_name(&prizes, u.prize_index),
+        }))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::path::Path;
+    use crate::db::Database;
+    use crate::draw::WinnerMode;
+
+    #[test]
+    fn local_ip_prefers_private_ipv4() {
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+        let found = [ip("fd00::5"), ip("127.0.0.1"), ip("85.10.1.2"), ip("192.168.1.20")];
+        assert_eq!(best_local_ip(found.into_iter()), Some(ip("192.168.1.20")));
+        assert_eq!(best_local_ip([ip("fd00::5"), ip("85.10.1.2")].into_iter()), Some(ip("85.10.1.2")));
+        assert_eq!(best_local_ip([ip("::1"), ip("0.0.0.0")].into_iter()), None);
+
+        // whatever this machine's network looks like, never the loopback
+        assert!(!local_ip().is_some_and(|ip| ip.is_loopback()));
+    }
+
+    #[test]
+    fn winners_leave_out_emails() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        db.insert_user("Anna", "Schmidt", "anna@example.com", 42).unwrap();
+        db.insert_user("Ben", "Meyer", "ben@example.com", 90).unwrap();
+        db.calculate_winners(&[40], false, WinnerMode::Closest, 0).unwrap();
+
+        let (status, body) = respond("GET", "/winners", &db, None);
+        assert_eq!(status, "200 OK");
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["first_name"], "Anna");
+        assert!(!body.to_string().contains("@"), "{}", body);
+
+        let (_, stats) = respond("GET", "/stats?x=1", &db, Some(40));
+        assert_eq!(stats, json!({ "registrations": 2, "registration_open": true, "target": 40 }));
+        assert_eq!(respond("POST", "/stats", &db, None).0, "405 Method Not Allowed");
+        assert_eq!(respond("GET", "/nope", &db, None).0, "404 Not Found");
+    }
+
+    #[test]
+    fn serves_over_tcp_and_stops_on_drop() {
+        let database: SharedStore = Arc::new(Mutex::new(Database::open(Path::new(":memory:")).unwrap()));
+        let server = HttpServer::start("127.0.0.1:0", database).unwrap();
+        let addr = server.addr();
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /healthz HTTP/1.1\r\nHost: test\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.ends_with(r#"{"status":"ok"}"#), "{}", response);
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        // an info screen polls every endpoint in a row
+        assert!(get("/stats").starts_with("HTTP/1.1 200 OK"));
+        assert!(get("/winners").starts_with("HTTP/1.1 200 OK"));
+        std::thread::sleep(READ_INTERVAL);
+        assert!(get("/stats?again").starts_with("HTTP/1.1 200 OK"));
+        // only a flood on one endpoint is turned away
+        assert!(get("/stats").starts_with("HTTP/1.1 429"));
+
+        drop(server);
+        assert!(TcpStream::connect(addr).is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! UI language. The English text is the key, every other language is a
+//! table of (English, translation) pairs; whatever a table lacks stays
+//! English. A new language is one more entry in `TABLES`.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Languages the UI can be shown in, declared in `TABLES` order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    De,
+}
+
+/// (English, translation) pairs of one language.
+type Table = &'static [(&'static str, &'static str)];
+
+/// (language, config code, name in that language, translations)
+const TABLES: [(Lang, &str, &str, Table); 2] = [
+    (Lang::En, "en", "English", &[]),
+    (Lang::De, "de", "Deutsch", DE),
+];
+
+impl Lang {
+    pub const ALL: [Lang; TABLES.len()] = [Lang::En, Lang::De];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// "en", "de", as in `config.toml`.
+    pub fn code(self) -> &'static str {
+        TABLES[self.index()].1
+    }
+
+    /// What the language selector shows.
+    pub fn name(self) -> &'static str {
+        TABLES[self.index()].2
+    }
+
+    pub fn parse(code: &str) -> Option<Lang> {
+        TABLES.iter().find(|t| t.1.eq_ignore_ascii_case(code.trim())).map(|t| t.0)
+    }
+}
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Switches every `tr` from the next frame on.
+pub fn set_lang(lang: Lang) {
+    CURRENT.store(lang.index(), Ordering::Relaxed);
+}
+
+pub fn lang() -> Lang {
+    Lang::ALL[CURRENT.load(Ordering::Relaxed)]
+}
+
+fn maps() -> &'static [HashMap<&'static str, &'static str>] {
+    static MAPS: OnceLock<Vec<HashMap<&'static str, &'static str>>> = OnceLock::new();
+    MAPS.get_or_init(|| TABLES.iter().map(|t| t.3.iter().copied().collect()).collect())
+}
+
+/// `key` in `lang`, or `key` itself if there is no translation.
+pub fn translate(lang: Lang, key: &'static str) -> &'static str {
+    maps()[lang.index()].get(key).copied().unwrap_or(key)
+}
+
+/// `key` in the current language.
+pub fn tr(key: &'static str) -> &'static str {
+    translate(lang(), key)
+}
+
+/// Puts `args` into the `{}` of `template` in turn; translations may not
+/// reorder them.
+pub fn fill(template: &str, args: &[&dyn std::fmt::Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some(pos) = rest.find("{}") {
+        out.push_str(&rest[..pos]);
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        rest = &rest[pos + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `format!` for translated text: `tr!("Saved {}", path)`. Only plain `{}`
+/// placeholders, anything else is formatted before.
+#[macro_export]
+macro_rules! tr {
+    ($key:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill($crate::i18n::tr($key), &[$(&$arg as &dyn std::fmt::Display),+])
+    };
+}
+
+const DE: Table = &[
+    ("#{} {} | {} | target {} | {} of {} won", "#{} {} | {} | Ziel {} | {} von {} gewonnen"),
+    ("⚠ No network, other devices cannot connect", "⚠ Kein Netzwerk, andere Geräte können sich nicht verbinden"),
+    ("Background:", "Hintergrund:"),
+    ("Christmas", "Weihnachten"),
+    ("Closest to {}:", "Am nächsten an {}:"),
+    ("Dark", "Dunkel"),
+    ("Drag ☰ to change the order", "☰ ziehen, um die Reihenfolge zu ändern"),
+    ("Email", "E-Mail"),
+    ("Export columns", "Exportspalten"),
+    ("First Name", "Vorname"),
+    ("Flakes:", "Flocken:"),
+    ("instead of the picture", "statt des Bildes"),
+    ("Light", "Hell"),
+    ("New Year", "Silvester"),
+    ("Next winner{}", "Nächster Gewinner{}"),
+    ("No columns selected for the export", "Keine Spalten für den Export gewählt"),
+    ("Number", "Zahl"),
+    ("Only winners of the latest draw can be re-rolled", "Nur Gewinner der letzten Ziehung können neu gezogen werden"),
+    ("Preset:", "Vorlage:"),
+    ("Prize", "Preis"),
+    ("registration {} is in the recycle bin, restore it first", "Anmeldung {} ist im Papierkorb, zuerst wiederherstellen"),
+    ("Reload theme", "Design neu laden"),
+    ("Re-read the [theme] colors from the config file", "Liest die [theme]-Farben neu aus der Konfigurationsdatei"),
+    ("Reset", "Zurücksetzen"),
+    ("Reveal all now", "Alle sofort zeigen"),
+    ("Reveal winners", "Gewinner enthüllen"),
+    ("Size (far, near):", "Größe (fern, nah):"),
+    ("Snow and background", "Schnee und Hintergrund"),
+    ("Speed (far, near):", "Geschwindigkeit (fern, nah):"),
+    ("Surname", "Nachname"),
+    ("Theme:", "Design:"),
+    ("Theme reloaded", "Design neu geladen"),
+    ("This machine: {}", "Dieser Rechner: {}"),
+    ("(unnamed)", "(ohne Namen)"),
+    ("1 other chose {}", "1 andere Person hat {} gewählt"),
+    ("API listening on http://{}", "API läuft auf http://{}"),
+    ("Action:", "Aktion:"),
+    ("Add prize", "Preis hinzufügen"),
+    ("Admin or operator password", "Admin- oder Betreiberpasswort"),
+    ("Admin password", "Adminpasswort"),
+    ("Admin windows locked after inactivity", "Adminfenster nach Inaktivität gesperrt"),
+    ("Admin", "Admin"),
+    ("All", "Alle"),
+    ("An export is already running", "Ein Export läuft bereits"),
+    ("An operation crashed while using the database - check the last change", "Ein Vorgang ist bei der Datenbankarbeit abgestürzt - bitte die letzte Änderung prüfen"),
+    ("Anonymize", "Anonymisieren"),
+    ("Another program may still have it open, or the file is damaged.", "Ein anderes Programm hat sie vielleicht noch geöffnet, oder die Datei ist beschädigt."),
+    ("Audit log", "Protokoll"),
+    ("Back up the current database first", "Vorher die aktuelle Datenbank sichern"),
+    ("Backup database", "Datenbank sichern"),
+    ("Backup failed: {}", "Sicherung fehlgeschlagen: {}"),
+    ("Bin width:", "Klassenbreite:"),
+    ("Broken .xlsx: {}", "Beschädigte .xlsx: {}"),
+    ("Calculate Winners (Top {} closest)", "Gewinner berechnen (die {} nächsten)"),
+    ("Calculate anyway", "Trotzdem berechnen"),
+    ("Cancel", "Abbrechen"),
+    ("Check for changes every 2 s", "Alle 2 s auf Änderungen prüfen"),
+    ("Clear form? Esc or Clear again to confirm", "Formular leeren? Zum Bestätigen Esc oder nochmal Leeren"),
+    ("Clear", "Leeren"),
+    ("Close automatically at:", "Automatisch schließen um:"),
+    ("Close kiosk", "Kiosk beenden"),
+    ("Close registration when the countdown ends", "Anmeldung schließen, wenn der Countdown endet"),
+    ("Closes at {}", "Schließt um {}"),
+    ("Closest", "Am nächsten"),
+    ("Color-blind safe", "Farbenblind-freundlich"),
+    ("Colors:", "Farben:"),
+    ("Commit this draw", "Diese Ziehung übernehmen"),
+    ("Compact", "Kompakt"),
+    ("Confirm winner override", "Gewinner manuell ändern"),
+    ("Confirm", "Bestätigen"),
+    ("Congratulations!", "Herzlichen Glückwunsch!"),
+    ("Copied {} winner emails", "{} Gewinner-E-Mails kopiert"),
+    ("Copied {}", "{} kopiert"),
+    ("Copy all winner emails", "Alle Gewinner-E-Mails kopieren"),
+    ("Copy email", "E-Mail kopieren"),
+    ("Could not open the log folder: {}", "Log-Ordner konnte nicht geöffnet werden: {}"),
+    ("Could not open {}: {}", "{} konnte nicht geöffnet werden: {}"),
+    ("Could not read {}: {}", "{} konnte nicht gelesen werden: {}"),
+    ("Could not start the API on {}: {}", "API auf {} konnte nicht gestartet werden: {}"),
+    ("Count: {} | Min: {} | Max: {} | Mean: {} | Median: {}", "Anzahl: {} | Min: {} | Max: {} | Mittel: {} | Median: {}"),
+    ("Countdown to (HH:MM):", "Countdown bis (HH:MM):"),
+    ("Ctrl+Shift+D - Dev Settings", "Strg+Umschalt+D - Entwicklereinstellungen"),
+    ("Ctrl+Shift+S - Statistics", "Strg+Umschalt+S - Statistik"),
+    ("Ctrl+Win+L - Table View", "Strg+Win+L - Tabelle"),
+    ("Ctrl+Z - Undo last registration", "Strg+Z - Letzte Anmeldung zurücknehmen"),
+    ("Current event: {}", "Aktuelle Veranstaltung: {}"),
+    ("Danger zone", "Gefahrenbereich"),
+    ("Database ID {}", "Datenbank-ID {}"),
+    ("Database busy, please retry", "Datenbank beschäftigt, bitte erneut versuchen"),
+    ("Database error: {}", "Datenbankfehler: {}"),
+    ("Database saved to {}", "Datenbank gesichert in {}"),
+    ("Database", "Datenbank"),
+    ("Delete all registrations", "Alle Anmeldungen löschen"),
+    ("Delete permanently", "Endgültig löschen"),
+    ("Delete test data", "Testdaten löschen"),
+    ("Deleted {} registrations. {}", "{} Anmeldungen gelöscht. {}"),
+    ("Deleted {} test users", "{} Testnutzer gelöscht"),
+    ("Deletes all registrations, draw history and audit entries. A backup is saved first.", "Löscht alle Anmeldungen, Ziehungen und Protokolleinträge. Vorher wird eine Sicherung gespeichert."),
+    ("Description", "Beschreibung"),
+    ("Developed by Pierre Maurice Hesse", "Entwickelt von Pierre Maurice Hesse"),
+    ("Developer Settings", "Entwicklereinstellungen"),
+    ("Discard", "Verwerfen"),
+    ("Dismiss", "Ausblenden"),
+    ("Distance: {} ({})", "Abstand: {} ({})"),
+    ("Distinct emails: {}", "Verschiedene E-Mails: {}"),
+    ("Draw history", "Ziehungsverlauf"),
+    ("Draw in {}", "Ziehung in {}"),
+    ("Draw time!", "Zeit für die Ziehung!"),
+    ("Draw {} Winners (weighted)", "{} Gewinner ziehen (gewichtet)"),
+    ("Duplicates", "Doppelte"),
+    ("Earlier winners can win again", "Frühere Gewinner können wieder gewinnen"),
+    ("Edit registration", "Anmeldung bearbeiten"),
+    ("Edit", "Bearbeiten"),
+    ("Email is required", "E-Mail fehlt"),
+    ("Email must look like name@example.com", "E-Mail muss wie name@example.com aussehen"),
+    ("Email:", "E-Mail:"),
+    ("Emails become a hash, the same for the same person in every export", "E-Mails werden zu einem Hash, für dieselbe Person in jedem Export gleich"),
+    ("Error: {}", "Fehler: {}"),
+    ("Event:", "Veranstaltung:"),
+    ("Events side by side", "Mehrere Veranstaltungen"),
+    ("Every event has its own file with its own registrations, targets and draws.", "Jede Veranstaltung hat eine eigene Datei mit eigenen Anmeldungen, Zielzahlen und Ziehungen."),
+    ("Exclude previous winners", "Frühere Gewinner ausschließen"),
+    ("Export All Data to Excel", "Alle Daten nach Excel exportieren"),
+    ("Export current view", "Aktuelle Ansicht exportieren"),
+    ("Export thread stopped unexpectedly", "Export wurde unerwartet beendet"),
+    ("Exported {} users to {}", "{} Nutzer exportiert nach {}"),
+    ("Exporting...", "Exportiere..."),
+    ("File: {}", "Datei: {}"),
+    ("Filter:", "Filter:"),
+    ("Find duplicates", "Doppelte suchen"),
+    ("First Name:", "Vorname:"),
+    ("First name:", "Vorname:"),
+    ("First name is required", "Vorname fehlt"),
+    ("First registration: {}", "Erste Anmeldung: {}"),
+    ("Fuzzy", "Unscharf"),
+    ("Generate test users", "Testnutzer erzeugen"),
+    ("Generated {} test users", "{} Testnutzer erzeugt"),
+    ("Generated {} test users, {} rows were rejected", "{} Testnutzer erzeugt, {} Zeilen abgelehnt"),
+    ("Guess must be between {} and {}", "Tipp muss zwischen {} und {} liegen"),
+    ("Guesses from {}", "Tipps von {}"),
+    ("Guesses from", "Tipps von"),
+    ("Import CSV...", "CSV importieren..."),
+    ("Import cancelled, nothing was added", "Import abgebrochen, nichts hinzugefügt"),
+    ("Import corrections...", "Korrekturen importieren..."),
+    ("Import failed: {}", "Import fehlgeschlagen: {}"),
+    ("Import thread stopped unexpectedly", "Import wurde unerwartet beendet"),
+    ("Imported {}: {} of {} rows added, {} skipped or invalid", "{} importiert: {} von {} Zeilen hinzugefügt, {} übersprungen oder ungültig"),
+    ("Imported {}: {} updated, {} added, {} skipped", "{} importiert: {} aktualisiert, {} hinzugefügt, {} übersprungen"),
+    ("Info-screen API:", "Infobildschirm-API:"),
+    ("Initials only", "Nur Initialen"),
+    ("Invalid max number!", "Ungültige Zielzahl!"),
+    ("Invalid number format!", "Ungültiges Zahlenformat!"),
+    ("Invalid number of test users", "Ungültige Anzahl Testnutzer"),
+    ("Invalid number range", "Ungültiger Zahlenbereich"),
+    ("Invalid seed", "Ungültiger Seed"),
+    ("Invalid time, use HH:MM or YYYY-MM-DD HH:MM", "Ungültige Zeit, bitte HH:MM oder JJJJ-MM-TT HH:MM"),
+    ("Keep unlocked until the app closes", "Entsperrt lassen, bis die App geschlossen wird"),
+    ("Language:", "Sprache:"),
+    ("Last registration undone", "Letzte Anmeldung zurückgenommen"),
+    ("Last registration: {}", "Letzte Anmeldung: {}"),
+    ("Locks in {} without input", "Sperrt in {} ohne Eingabe"),
+    ("Manual override saved for {}", "Manuelle Änderung für {} gespeichert"),
+    ("Manual winner overrides exist and will be replaced.", "Manuell gesetzte Gewinner werden ersetzt."),
+    ("Mark winner", "Als Gewinner markieren"),
+    ("Mark {} as a winner by hand?", "{} von Hand als Gewinner markieren?"),
+    ("Max Number (Zielzahl), comma separated for one prize each:", "Zielzahl, kommagetrennt für je einen Preis:"),
+    ("Max name distance:", "Max. Namensabstand:"),
+    ("Merge, keep this", "Zusammenführen, diesen behalten"),
+    ("Merged {} duplicates into ID {}", "{} Doppelte in ID {} zusammengeführt"),
+    ("Min and max guess must be whole numbers", "Kleinster und größter Tipp müssen ganze Zahlen sein"),
+    ("Min guess must be at least 1 and not above max", "Kleinster Tipp muss mindestens 1 und nicht größer als der größte sein"),
+    ("Mode:", "Modus:"),
+    ("Move to recycle bin", "In den Papierkorb"),
+    ("Name", "Name"),
+    ("Name:", "Name:"),
+    ("New event \"{}\": {}", "Neue Veranstaltung \"{}\": {}"),
+    ("New event file...", "Neue Veranstaltungsdatei..."),
+    ("New event name:", "Name der neuen Veranstaltung:"),
+    ("No data to export!", "Keine Daten zum Exportieren!"),
+    ("No draws yet.", "Noch keine Ziehungen."),
+    ("No further candidates to re-roll to", "Keine weiteren Kandidaten zum Nachziehen"),
+    ("No registrations yet.", "Noch keine Anmeldungen."),
+    ("No suspected duplicates.", "Keine vermuteten Doppelten."),
+    ("No winners to copy", "Keine Gewinner zum Kopieren"),
+    ("No winners to reveal - calculate winners first", "Keine Gewinner zum Enthüllen - zuerst Gewinner berechnen"),
+    ("Nobody chose {} yet", "Noch niemand hat {} gewählt"),
+    ("Normal", "Normal"),
+    ("Nothing deleted.", "Nichts gelöscht."),
+    ("Number ({}):", "Zahl ({}):"),
+    ("Number is required", "Zahl fehlt"),
+    ("Number must be >= 1", "Zahl muss >= 1 sein"),
+    ("Number must be >= {}", "Zahl muss >= {} sein"),
+    ("Number must be a whole number", "Zahl muss eine ganze Zahl sein"),
+    ("Number: {}", "Zahl: {}"),
+    ("Number:", "Zahl:"),
+    ("Numbers:", "Zahlen:"),
+    ("Only an admin can close the kiosk", "Nur ein Admin kann den Kiosk beenden"),
+    ("Open another file...", "Andere Datei öffnen..."),
+    ("Open event...", "Veranstaltung öffnen..."),
+    ("Open log folder", "Log-Ordner öffnen"),
+    ("Open report", "Bericht öffnen"),
+    ("Operator", "Betreiber"),
+    ("Operators only have the registrations table", "Betreiber haben nur die Anmeldetabelle"),
+    ("Past winners stay in the draw history and cannot be purged", "Frühere Gewinner bleiben im Ziehungsverlauf und können nicht endgültig gelöscht werden"),
+    ("Please close the existing Excel file and try again", "Bitte die geöffnete Excel-Datei schließen und erneut versuchen"),
+    ("Please confirm your details", "Bitte Angaben bestätigen"),
+    ("Please enter a reason for the re-roll", "Bitte einen Grund für das Nachziehen angeben"),
+    ("Please fill all fields!", "Bitte alle Felder ausfüllen!"),
+    ("Please wait a moment", "Bitte einen Moment warten"),
+    ("Preview ({} winners):", "Vorschau ({} Gewinner):"),
+    ("Preview winners", "Gewinner-Vorschau"),
+    ("Prize {}: {}", "Preis {}: {}"),
+    ("Prizes saved", "Preise gespeichert"),
+    ("Prizes", "Preise"),
+    ("Range must satisfy 1 <= low <= high", "Bereich muss 1 <= von <= bis erfüllen"),
+    ("Re-roll reason:", "Grund fürs Nachziehen:"),
+    ("Re-roll", "Nachziehen"),
+    ("Really clear?", "Wirklich leeren?"),
+    ("Really delete", "Wirklich löschen"),
+    ("Really start new event", "Wirklich neue Veranstaltung starten"),
+    ("Recycle bin ({})", "Papierkorb ({})"),
+    ("Refresh from disk", "Von der Festplatte neu laden"),
+    ("Refresh", "Aktualisieren"),
+    ("Register", "Anmelden"),
+    ("Registration closed", "Anmeldung geschlossen"),
+    ("Registration is closed", "Die Anmeldung ist geschlossen"),
+    ("Registration open", "Anmeldung offen"),
+    ("Registration opened", "Anmeldung geöffnet"),
+    ("Registration successful! You are entry #{}", "Anmeldung erfolgreich! Du bist Eintrag #{}"),
+    ("Registration updated", "Anmeldung aktualisiert"),
+    ("Registrations Table", "Anmeldetabelle"),
+    ("Registrations changed, preview discarded", "Anmeldungen geändert, Vorschau verworfen"),
+    ("Registrations: {}", "Anmeldungen: {}"),
+    ("Reloaded from disk", "Von der Festplatte neu geladen"),
+    ("Remove the winner flag from {}?", "Gewinnermarkierung von {} entfernen?"),
+    ("Removes all registrations and draws, unlocks the winners and opens the registration.", "Entfernt alle Anmeldungen und Ziehungen, entsperrt die Gewinner und öffnet die Anmeldung."),
+    ("Reset winner history", "Gewinnerverlauf zurücksetzen"),
+    ("Restore failed: {}", "Wiederherstellen fehlgeschlagen: {}"),
+    ("Restore flags", "Markierungen wiederherstellen"),
+    ("Restore from backup...", "Aus Sicherung wiederherstellen..."),
+    ("Restore unsubmitted registration?", "Nicht abgeschickte Anmeldung wiederherstellen?"),
+    ("Restore", "Wiederherstellen"),
+    ("Restored from {}", "Wiederhergestellt aus {}"),
+    ("Restoring {} overwrites ALL current data.", "Das Wiederherstellen von {} überschreibt ALLE aktuellen Daten."),
+    ("Retry", "Erneut versuchen"),
+    ("Row {}: ID \"{}\" is not a number", "Zeile {}: ID \"{}\" ist keine Zahl"),
+    ("Save current settings to config", "Aktuelle Einstellungen in die Konfiguration speichern"),
+    ("Save error: {}", "Fehler beim Speichern: {}"),
+    ("Save prizes", "Preise speichern"),
+    ("Save", "Speichern"),
+    ("Saved {}", "{} gespeichert"),
+    ("Schedule", "Planen"),
+    ("Seconds between submissions:", "Sekunden zwischen Anmeldungen:"),
+    ("Seed:", "Seed:"),
+    ("Shortcuts:", "Tastenkürzel:"),
+    ("Show how many picked the typed number", "Zeigen, wie viele die eingegebene Zahl gewählt haben"),
+    ("Show the target number on the main screen", "Zielzahl auf dem Hauptbildschirm zeigen"),
+    ("Simple snow (circles, for slow machines)", "Einfacher Schnee (Kreise, für langsame Rechner)"),
+    ("Size: in memory", "Größe: im Arbeitsspeicher"),
+    ("Size: {} KiB", "Größe: {} KiB"),
+    ("Space / click: next    Esc: back", "Leertaste / Klick: weiter    Esc: zurück"),
+    ("Space", "Leerzeichen"),
+    ("Standard", "Standard"),
+    ("Start new event", "Neue Veranstaltung starten"),
+    ("Start reveal", "Enthüllung starten"),
+    ("Start with a fresh database", "Mit einer neuen Datenbank starten"),
+    ("Start", "Start"),
+    ("Statistics", "Statistik"),
+    ("Stop", "Stopp"),
+    ("Storage: IN-MEMORY (data will be lost!)", "Speicher: IM ARBEITSSPEICHER (Daten gehen verloren!)"),
+    ("Storage: {}", "Speicher: {}"),
+    ("Submit", "Absenden"),
+    ("Surname is required", "Nachname fehlt"),
+    ("Surname:", "Nachname:"),
+    ("Target", "Ziel"),
+    ("Target: {}", "Ziel: {}"),
+    ("Targets: {}", "Ziele: {}"),
+    ("That registration was changed meanwhile", "Diese Anmeldung wurde inzwischen geändert"),
+    ("The Registrations sheet has no \"{}\" column", "Das Blatt Registrations hat keine Spalte \"{}\""),
+    ("The Registrations sheet is empty", "Das Blatt Registrations ist leer"),
+    ("The app crashed last time, a report was saved.", "Die App ist beim letzten Mal abgestürzt, ein Bericht wurde gespeichert."),
+    ("The data changed since the preview - check the new preview", "Die Daten haben sich seit der Vorschau geändert - bitte die neue Vorschau prüfen"),
+    ("The registration database could not be opened", "Die Anmeldedatenbank konnte nicht geöffnet werden"),
+    ("The workbook has no \"{}\" sheet", "Die Arbeitsmappe hat kein Blatt \"{}\""),
+    ("This email is already registered", "Diese E-Mail ist bereits angemeldet"),
+    ("Tolerates typos and missing accents, best matches first", "Verzeiht Tippfehler und fehlende Akzente, beste Treffer zuerst"),
+    ("Too many wrong passwords, try again in {}s", "Zu viele falsche Passwörter, erneut in {} s"),
+    ("Total registrations: {} | Target number: {}", "Anmeldungen gesamt: {} | Zielzahl: {}"),
+    ("Touch mode", "Touch-Modus"),
+    ("Two lines per row, for small screens", "Zwei Zeilen pro Eintrag, für kleine Bildschirme"),
+    ("Type DELETE:", "DELETE eingeben:"),
+    ("Undo last registration", "Letzte Anmeldung zurücknehmen"),
+    ("Uniform", "Gleichverteilt"),
+    ("Unlock winners", "Gewinner entsperren"),
+    ("Unlock", "Entsperren"),
+    ("Unlock...", "Entsperren..."),
+    ("Unlocking allows the winners to change again.", "Nach dem Entsperren können sich die Gewinner wieder ändern."),
+    ("Unmark winner", "Gewinnermarkierung entfernen"),
+    ("User ID:", "Nutzer-ID:"),
+    ("View", "Ansehen"),
+    ("Waiting for the confirmation", "Warte auf die Bestätigung"),
+    ("Webhook URL:", "Webhook-URL:"),
+    ("Webhook removed", "Webhook entfernt"),
+    ("Webhook saved", "Webhook gespeichert"),
+    ("Weighted random", "Gewichteter Zufall"),
+    ("Winner", "Gewinner"),
+    ("Winners (Top {} closest)", "Gewinner (die {} nächsten)"),
+    ("Winners are locked - unlock them first", "Die Gewinner sind gesperrt - zuerst entsperren"),
+    ("Winners calculated successfully!", "Gewinner erfolgreich berechnet!"),
+    ("Winners locked", "Gewinner gesperrt"),
+    ("Winners of draw #{} restored", "Gewinner der Ziehung #{} wiederhergestellt"),
+    ("Winners of draw #{}:", "Gewinner der Ziehung #{}:"),
+    ("Winners unlocked", "Gewinner entsperrt"),
+    ("Winners: {}", "Gewinner: {}"),
+    ("Winners:", "Gewinner:"),
+    ("Winter Registration", "Winter-Anmeldung"),
+    ("Write error: {}", "Schreibfehler: {}"),
+    ("Wrong password", "Falsches Passwort"),
+    ("Yes", "Ja"),
+    ("by {}", "von {}"),
+    ("close", "nah"),
+    ("confirmed", "bestätigt"),
+    ("emailed", "angeschrieben"),
+    ("excluded, {}", "ausgeschlossen, {}"),
+    ("far", "weit"),
+    ("medium", "mittel"),
+    ("nd", "."),
+    ("none", "offen"),
+    ("random", "zufällig"),
+    ("rd", "."),
+    ("row {}: Number \"{}\" is not a whole number", "Zeile {}: Zahl \"{}\" ist keine ganze Zahl"),
+    ("row {}: {}", "Zeile {}: {}"),
+    ("seed {}", "Seed {}"),
+    ("st", "."),
+    ("th", "."),
+    ("to", "bis"),
+    ("unreachable", "nicht erreichbar"),
+    ("user {}", "Nutzer {}"),
+    ("winners unlocked, registration open", "Gewinner entsperrt, Anmeldung offen"),
+    ("with on-screen keyboard", "mit Bildschirmtastatur"),
+    ("won round {}", "gewann Runde {}"),
+    ("{} - Password", "{} - Passwort"),
+    ("{} - nothing was changed", "{} - nichts wurde geändert"),
+    ("{} - nothing was deleted", "{} - nichts wurde gelöscht"),
+    ("{} - re-rolled: {}", "{} - nachgezogen: {}"),
+    ("{} / {} rows", "{} / {} Zeilen"),
+    ("{} entries (newest first, at most 500)", "{} Einträge (neueste zuerst, höchstens 500)"),
+    ("{} is already used by a live registration", "{} wird schon von einer aktiven Anmeldung verwendet"),
+    ("{} of {} winners confirmed", "{} von {} Gewinnern bestätigt"),
+    ("{} others chose {}", "{} andere haben {} gewählt"),
+    ("{} place{}", "{} Platz{}"),
+    ("{} registrations and all draws removed", "{} Anmeldungen und alle Ziehungen entfernt"),
+    ("{} shown", "{} angezeigt"),
+    ("{} to {}", "{} bis {}"),
+    ("{} to ∞", "{} bis ∞"),
+    ("{} {} is back", "{} {} ist wieder da"),
+    ("{} {} moved to the recycle bin", "{} {} in den Papierkorb verschoben"),
+    ("{} {} replaced by {} {}", "{} {} ersetzt durch {} {}"),
+    ("{} {} restored", "{} {} wiederhergestellt"),
+    ("{}. {} (ID {}) - number {}, distance {}", "{}. {} (ID {}) - Zahl {}, Abstand {}"),
+    ("{}. {} {} - number {}, distance {}", "{}. {} {} - Zahl {}, Abstand {}"),
+    ("↩ Undo delete ({} {})", "↩ Löschen rückgängig ({} {})"),
+    ("★ WINNER", "★ GEWINNER"),
+    ("⚠ {} webhook messages could not be delivered", "⚠ {} Webhook-Nachrichten konnten nicht zugestellt werden"),
+    ("✔ Registered!", "✔ Angemeldet!"),
+    ("🔒 Winners locked", "🔒 Gewinner gesperrt"),
+    ("🔓 Lock winners", "🔓 Gewinner sperren"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_match_the_config() {
+        assert_eq!(Lang::ALL.map(Lang::code), crate::config::LANGUAGES);
+        for lang in Lang::ALL {
+            assert_eq!(Lang::parse(lang.code()), Some(lang));
+        }
+        assert_eq!(Lang::parse(" DE "), Some(Lang::De));
+        assert_eq!(Lang::parse("fr"), None);
+    }
+
+    #[test]
+    fn missing_translations_stay_english() {
+        assert_eq!(translate(Lang::De, "Save"), "Speichern");
+        assert_eq!(translate(Lang::En, "Save"), "Save");
+        assert_eq!(translate(Lang::De, "no such key"), "no such key");
+        assert_eq!(fill(translate(Lang::De, "Saved {}"), &[&"config.toml"]), "config.toml gespeichert");
+        assert_eq!(fill("{} of {}", &[&1]), "1 of ");
+    }
+
+    #[test]
+    fn translations_keep_their_placeholders() {
+        for (_, _, _, table) in TABLES {
+            let mut seen = std::collections::HashSet::new();
+            for (key, text) in table {
+                assert!(seen.insert(key), "{:?} is translated twice", key);
+                assert_eq!(key.matches("{}").count(), text.matches("{}").count(), "{:?}", key);
+            }
+        }
+    }
+}
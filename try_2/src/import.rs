@@ -0,0 +1,178 @@
+//! Readers for data coming back in, e.g. an export corrected in Excel.
+
+use std::path::Path;
+use calamine::{Reader, Xlsx, open_workbook};
+use crate::db::ImportedUser;
+use crate::error::AppError;
+use crate::i18n::tr;
+use crate::tr;
+
+/// Header cells `write_excel` puts on the "Registrations" sheet, found by
+/// name so reordered columns still import. Only "ID" may be missing, e.g.
+/// in a sign-up list from elsewhere.
+const COLUMNS: [&str; 5] = ["ID", "First Name", "Surname", "Email", "Number"];
+
+/// Reads the "Registrations" sheet of an `.xlsx` written by `write_excel`.
+/// Rows are only read here; `UserStore::upsert_users` checks and stores them.
+pub fn import_from_xlsx(path: &Path) -> Result<Vec<ImportedUser>, AppError> {
+    let mut workbook: Xlsx<_> = open_workbook(path).map_err(|e| AppError::File(tr!("Could not open {}: {}", path.display(), e)))?;
+    if !workbook.sheet_names().iter().any(|name| name == "Registrations") {
+        return Err(AppError::File(tr!("The workbook has no \"{}\" sheet", "Registrations")));
+    }
+    let range = workbook.worksheet_range("Registrations").map_err(|e| AppError::File(tr!("Broken .xlsx: {}", e)))?;
+
+    // the range starts at the first used cell, rows before it are empty
+    let (first_row, _) = range.start().unwrap_or_default();
+    let rows = range
+        .rows()
+        .enumerate()
+        .map(|(i, cells)| (first_row as usize + i + 1, cells.iter().map(|c| c.to_string()).collect()))
+        .collect();
+    users_from_rows(rows)
+}
+
+/// Reads a CSV with a header row like the one `write_csv` writes. German
+/// Excel saves with ";" between the cells, which is detected from the header.
+pub fn import_from_csv(path: &Path) -> Result<Vec<ImportedUser>, AppError> {
+    let text = std::fs::read_to_string(path).map_err(|e| AppError::File(tr!("Could not read {}: {}", path.display(), e)))?;
+    users_from_rows(csv_rows(text.trim_start_matches('\u{feff}')))
+}
+
+/// (line number, cells) per record; quoted cells may hold the separator,
+/// `""` and line breaks.
+fn csv_rows(text: &str) -> Vec<(usize, Vec<String>)> {
+    let header = text.lines().next().unwrap_or_default();
+    let separator = if header.matches(';').count() > header.matches(',').count() { ';' } else { ',' };
+
+    let mut rows = Vec::new();
+    let (mut cells, mut cell) = (Vec::new(), String::new());
+    let (mut line, mut start_line, mut quoted) = (1, 1, false);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => cells.push(std::mem::take(&mut cell)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                cells.push(std::mem::take(&mut cell));
+                rows.push((start_line, std::mem::take(&mut cells)));
+                line += 1;
+                start_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                cell.push(c);
+            }
+        }
+    }
+    if !cell.is_empty() || !cells.is_empty() {
+        cells.push(cell);
+        rows.push((start_line, cells));
+    }
+    rows
+}
+
+fn users_from_rows(rows: Vec<(usize, Vec<String>)>) -> Result<Vec<ImportedUser>, AppError> {
+    let mut rows = rows.into_iter();
+    let (_, header) = rows.next().ok_or_else(|| AppError::File(tr("The Registrations sheet is empty").to_string()))?;
+    let mut columns = [None; COLUMNS.len()];
+    for (slot, name) in columns.iter_mut().zip(COLUMNS) {
+        *slot = header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+        if slot.is_none() && name != "ID" {
+            return Err(AppError::File(tr!("The Registrations sheet has no \"{}\" column", name)));
+        }
+    }
+
+    let mut users = Vec::new();
+    for (row, cells) in rows {
+        if cells.iter().all(|c| c.trim().is_empty()) {
+            continue;
+        }
+        let [id, first_name, surname, email, number] =
+            columns.map(|c| c.and_then(|c| cells.get(c)).map(|v| v.trim().to_string()).unwrap_or_default());
+        let id = match id.as_str() {
+            "" => None,
+            id => Some(id.parse().map_err(|_| AppError::File(tr!("Row {}: ID \"{}\" is not a number", row, id)))?),
+        };
+        users.push(ImportedUser { row, id, first_name, surname, email, number });
+    }
+    Ok(users)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::User;
+    use crate::fixtures::named;
+    use crate::export::{ExportColumn, write_csv, write_excel};
+
+    fn user(id: i32, first_name: &str, number: i32) -> User {
+        named(id, first_name, "Schmidt", number)
+    }
+
+    #[test]
+    fn reads_back_what_write_excel_wrote() {
+        let path = std::env::temp_dir().join(format!("winter_raten_import_{}.xlsx", std::process::id()));
+        write_excel(&[user(3, "Anna", 42), user(7, "Ben", 300)], &[], &ExportColumn::ALL, path.to_str().unwrap()).unwrap();
+
+        let rows = import_from_xlsx(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], ImportedUser {
+            row: 2,
+            id: Some(3),
+            first_name: "Anna".to_string(),
+            surname: "Schmidt".to_string(),
+            email: "anna@example.com".to_string(),
+            number: "42".to_string(),
+        });
+        assert_eq!((rows[1].id, rows[1].number.as_str()), (Some(7), "300"));
+    }
+
+    #[test]
+    fn reads_back_what_write_csv_wrote() {
+        let path = std::env::temp_dir().join(format!("winter_raten_import_{}.csv", std::process::id()));
+        let mut odd = user(7, "Ben", 300);
+        odd.surname = "Meyer, \"Jr.\"".to_string();
+        write_csv(&[user(3, "Anna", 42), odd], &[], &ExportColumn::ALL, path.to_str().unwrap()).unwrap();
+
+        let rows = import_from_csv(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(rows.len(), 2);
+        assert_eq!((rows[0].id, rows[0].first_name.as_str(), rows[0].number.as_str()), (Some(3), "Anna", "42"));
+        assert_eq!((rows[1].row, rows[1].surname.as_str()), (3, "Meyer, \"Jr.\""));
+    }
+
+    #[test]
+    fn csv_from_german_excel_without_ids() {
+        let text = "\u{feff}Vorname;First Name;Surname;Email;Number\r\nx;Jürgen;\"Groß\nMann\";j@x.de;7\r\n\r\n;Lena;Koch;l@x.de;8";
+        let rows = csv_rows(text.trim_start_matches('\u{feff}'));
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[1], (2, ["x", "Jürgen", "Groß\nMann", "j@x.de", "7"].map(String::from).to_vec()));
+        // the quoted line break moved the next record down a line
+        assert_eq!(rows[3].0, 5);
+
+        let users = users_from_rows(rows).unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!((users[1].id, users[1].first_name.as_str(), users[1].row), (None, "Lena", 5));
+    }
+
+    #[test]
+    fn cells_are_found_by_header() {
+        let header = ["Number", "Email", "ID", "Surname", "First Name"].map(String::from).to_vec();
+        let row = ["5", "c@d", "", "Meyer", "Carl"].map(String::from).to_vec();
+        let users = users_from_rows(vec![(1, header.clone()), (2, row), (3, vec![String::new()])]).unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!((users[0].id, users[0].first_name.as_str(), users[0].number.as_str()), (None, "Carl", "5"));
+
+        let bad_id = ["5", "c@d", "x1", "Meyer", "Carl"].map(String::from).to_vec();
+        assert!(users_from_rows(vec![(1, header), (4, bad_id)]).unwrap_err().to_string().starts_with("Row 4"));
+        assert!(users_from_rows(vec![(1, vec!["ID".to_string()])]).unwrap_err().to_string().contains("First Name"));
+    }
+}
@@ -0,0 +1,20 @@
+//! Snow Drift Registration: guesses are stored in SQLite, the closest ones win.
+//! `main.rs` only parses the command line and starts the window.
+
+pub mod config;
+pub mod crash;
+pub mod db;
+pub mod draw;
+pub mod error;
+pub mod export;
+#[cfg(test)]
+mod fixtures;
+pub mod form;
+pub mod http;
+pub mod i18n;
+pub mod import;
+pub mod logging;
+pub mod password;
+pub mod testdata;
+pub mod ui;
+pub mod webhook;
@@ -0,0 +1,169 @@
+//! One log file per day in `logs/`, so an error that only flashed by as a
+//! toast can still be looked up after the event. The last `KEEP_DAYS` files
+//! are kept.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use chrono::{DateTime, Local, NaiveDate};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Overrides the level, e.g. `WINTER_LOG=debug` or `WINTER_LOG=warn`.
+pub const LEVEL_VAR: &str = "WINTER_LOG";
+const KEEP_DAYS: usize = 14;
+
+/// `logs` next to the executable.
+pub fn default_dir() -> PathBuf {
+    std::env::current_exe()
+        .map(|exe| exe.with_file_name("logs"))
+        .unwrap_or_else(|_| PathBuf::from("logs"))
+}
+
+/// Installs the file logger for the rest of the run; debug messages are
+/// only written with `verbose` or `LEVEL_VAR`. With `echo` every line also
+/// goes to stderr.
+pub fn init(dir: &Path, verbose: bool, echo: bool) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    prune(dir, KEEP_DAYS);
+    log::set_boxed_logger(Box::new(FileLogger { echo, ..FileLogger::new(dir) })).map_err(|e| e.to_string())?;
+    log::set_max_level(level(verbose, std::env::var(LEVEL_VAR).ok().as_deref()));
+    Ok(())
+}
+
+/// A bad `LEVEL_VAR` is ignored rather than silencing the log.
+fn level(verbose: bool, var: Option<&str>) -> LevelFilter {
+    match var.map(|v| LevelFilter::from_str(v.trim())) {
+        Some(Ok(level)) => level,
+        _ if verbose => LevelFilter::Debug,
+        _ => LevelFilter::Info,
+    }
+}
+
+/// Deletes all but the newest `keep` daily files.
+fn prune(dir: &Path, keep: usize) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with("winter-") && name.ends_with(".log")
+        })
+        .collect();
+    // the dates sort as text
+    files.sort();
+    for old in files.iter().rev().skip(keep) {
+        if let Err(e) = std::fs::remove_file(old) {
+            eprintln!("Could not delete old log {}: {}", old.display(), e);
+        }
+    }
+}
+
+fn file_name(day: NaiveDate) -> String {
+    format!("winter-{}.log", day.format("%Y-%m-%d"))
+}
+
+struct FileLogger {
+    dir: PathBuf,
+    echo: bool,
+    /// today's file, swapped for a new one after midnight
+    file: Mutex<Option<(NaiveDate, File)>>,
+}
+
+impl FileLogger {
+    fn new(dir: &Path) -> Self {
+        FileLogger { dir: dir.to_path_buf(), echo: false, file: Mutex::new(None) }
+    }
+
+    fn write_line(&self, at: DateTime<Local>, level: Level, message: &str) -> io::Result<()> {
+        let mut current = self.file.lock().unwrap_or_else(|p| p.into_inner());
+        let day = at.date_naive();
+        let file = match &mut *current {
+            Some((open_day, file)) if *open_day == day => file,
+            _ => {
+                let file = OpenOptions::new().create(true).append(true).open(self.dir.join(file_name(day)))?;
+                &mut current.insert((day, file)).1
+            }
+        };
+        let line = format!("{} {:<5} {}", at.format("%H:%M:%S%.3f"), level, message);
+        if self.echo {
+            eprintln!("{}", line);
+        }
+        writeln!(file, "{}", line)
+    }
+}
+
+impl Log for FileLogger {
+    /// Our own messages down to the max level; the GUI libraries are chatty,
+    /// so only their warnings and errors.
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+            && (metadata.level() <= Level::Warn || metadata.target().starts_with(env!("CARGO_CRATE_NAME")))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Err(e) = self.write_line(Local::now(), record.level(), &record.args().to_string()) {
+            eprintln!("Could not write the log: {}", e);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some((_, file)) = &mut *self.file.lock().unwrap_or_else(|p| p.into_inner()) {
+            let _ = file.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn starts_a_new_file_every_day() {
+        let dir = std::env::temp_dir().join(format!("winter_raten_logs_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let logger = FileLogger::new(&dir);
+
+        let evening = Local.with_ymd_and_hms(2024, 12, 24, 23, 59, 0).unwrap();
+        logger.write_line(evening, Level::Info, "Registered entry #1").unwrap();
+        logger.write_line(evening + chrono::Duration::minutes(2), Level::Error, "Save error").unwrap();
+
+        let first = std::fs::read_to_string(dir.join("winter-2024-12-24.log")).unwrap();
+        assert!(first.starts_with("23:59:00.000 INFO  Registered entry #1"), "{}", first);
+        let second = std::fs::read_to_string(dir.join("winter-2024-12-25.log")).unwrap();
+        assert!(second.ends_with("ERROR Save error\n"), "{}", second);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn old_files_are_pruned() {
+        let dir = std::env::temp_dir().join(format!("winter_raten_prune_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for day in ["2024-12-22", "2024-12-23", "2024-12-24"] {
+            std::fs::write(dir.join(format!("winter-{}.log", day)), "").unwrap();
+        }
+        std::fs::write(dir.join("crash_1700000000.txt"), "").unwrap();
+
+        prune(&dir, 2);
+        assert!(!dir.join("winter-2024-12-22.log").exists());
+        assert!(dir.join("winter-2024-12-23.log").exists() && dir.join("winter-2024-12-24.log").exists());
+        assert!(dir.join("crash_1700000000.txt").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn level_comes_from_the_environment_first() {
+        assert_eq!(level(false, None), LevelFilter::Info);
+        assert_eq!(level(true, None), LevelFilter::Debug);
+        assert_eq!(level(true, Some("warn")), LevelFilter::Warn);
+        assert_eq!(level(false, Some("DEBUG")), LevelFilter::Debug);
+        assert_eq!(level(false, Some("chatty")), LevelFilter::Info);
+    }
+}
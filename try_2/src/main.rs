@@ -2,598 +2,281 @@
 // [dependencies]
 // eframe = "0.24"
 // egui = "0.24"
-// rusqlite = { version = "0.30", features = ["bundled"] }
+// rusqlite = { version = "0.30", features = ["bundled", "backup"] }
 // rand = "0.8"
 // image = "0.24"
 // simple_excel_writer = "0.2"
-
+// clap = { version = "4", features = ["derive"] }
+// serde = { version = "1", features = ["derive"] }
+// serde_json = "1"
+// toml = "1"
+// log = "0.4"
+// rfd = { version = "0.17", default-features = false, features = ["xdg-portal"] }
+// chrono = "0.4"
+// egui_plot = "0.24"
+// argon2 = "0.5"
+// hmac = "0.12"
+// sha2 = "0.10"
+// pbkdf2 = "0.12"
+// ureq = "2"
+// url = "2"
+// calamine = "0.36.1"
+
+use clap::{Parser, Subcommand};
 use eframe::egui;
-use rusqlite::{Connection, Result as SqlResult};
 use rand::Rng;
-use std::sync::{Arc, Mutex};
-use simple_excel_writer::*;
-
-#[derive(Clone)]
-struct Snowflake {
-    x: f32,
-    y: f32,
-    speed: f32,
-    side_toside_speed: f32,
-    size: f32,
-}
-
-#[derive(Debug, Clone)]
-struct User {
-    id: i32,
-    first_name: String,
-    surname: String,
-    email: String,
-    number: i32,
-    winner: bool,
+use std::path::PathBuf;
+use try_2::config::AppConfig;
+use try_2::crash;
+use try_2::db::{Database, User, UserStore};
+use try_2::draw::WinnerMode;
+use try_2::export::{ExportFormat, column_spec, enabled_columns, export_users};
+use try_2::logging;
+use try_2::ui::{APP_TITLE, Booth};
+
+const MIN_WINDOW_SIZE: [f32; 2] = [640.0, 480.0];
+
+#[derive(Parser)]
+#[command(about = "Snow Drift Registration - by Pierre Maurice Hesse")]
+struct Cli {
+    /// SQLite file holding the registrations
+    #[arg(long, global = true, default_value = "registrations.db")]
+    db: PathBuf,
+
+    /// Settings file, config.toml next to the executable by default
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Another event's SQLite file, shown as a tab next to --db; repeat for
+    /// more, e.g. `--event kids.db`
+    #[arg(long = "event", value_name = "FILE")]
+    events: Vec<PathBuf>,
+
+    /// Serve the read-only info-screen API, e.g. 0.0.0.0:8080
+    #[arg(long)]
+    serve: Option<String>,
+
+    /// Fullscreen booth that can't be closed without the admin password,
+    /// like `kiosk = true` in the config
+    #[arg(long)]
+    kiosk: bool,
+
+    /// Also write debug messages to the log files in logs/; WINTER_LOG=<level>
+    /// overrides this
+    #[arg(long, global = true)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-struct Database {
-    conn: Connection,
+#[derive(Subcommand)]
+enum Command {
+    /// Run a draw without the GUI and print the winners as JSON
+    Draw {
+        /// Zielzahl
+        #[arg(long)]
+        target: i32,
+        #[arg(long, visible_alias = "winners", default_value_t = 5)]
+        count: usize,
+        #[arg(long)]
+        exclude_previous: bool,
+        #[arg(long, value_enum, default_value_t = WinnerMode::Closest)]
+        mode: WinnerMode,
+        /// Seed for the weighted draw, random if omitted
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Print who would win without saving the draw
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write all registrations to a file
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Xlsx)]
+        format: ExportFormat,
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Print registration and guess statistics as JSON
+    Stats,
+    /// Ask for the admin password that guards the dev and table windows;
+    /// an empty password removes it
+    SetPassword {
+        /// Set the operator password instead, which opens the table window
+        /// for fixing entries but nothing else
+        #[arg(long)]
+        operator: bool,
+    },
 }
 
-impl Database {
-    fn new() -> SqlResult<Self> {
-        let conn = Connection::open_in_memory()?;
-        conn.execute(
-            "CREATE TABLE users (
-                id INTEGER PRIMARY KEY,
-                first_name TEXT NOT NULL,
-                surname TEXT NOT NULL,
-                email TEXT  NULL,
-                number INTEGER NOT NULL,
-                winner INTEGER NOT NULL DEFAULT 0
-            )",
-            [],
-        )?;
-        Ok(Database { conn })
+/// Reads the new password twice from stdin and stores its hash in the config.
+fn set_password(mut config: AppConfig, path: &std::path::Path, operator: bool) -> i32 {
+    let role = if operator { "operator" } else { "admin" };
+    let read_line = |prompt: &str| -> String {
+        eprint!("{}", prompt);
+        let mut line = String::new();
+        let _ = std::io::stdin().read_line(&mut line);
+        line.trim_end_matches(['\r', '\n']).to_string()
+    };
+    let password = read_line(&format!("New {} password (empty to remove): ", role));
+    if read_line("Repeat: ") != password {
+        eprintln!("The passwords do not match, nothing changed");
+        return 1;
     }
-
-    fn insert_user(&self, firstname: &str, surname: &str, email: &str, number: i32) -> SqlResult<()> {
-        self.conn.execute(
-            "INSERT INTO users (first_name, surname, email, number, winner) VALUES (?1, ?2, ?3, ?4, 0)",
-            [firstname, surname, email, &number.to_string()],
-        )?;
-        Ok(())
+    let hash = (!password.is_empty()).then(|| try_2::password::hash_password(&password));
+    if operator {
+        config.operator_password_hash = hash;
+    } else {
+        config.admin_password_hash = hash;
     }
-
-    fn get_all_users(&self) -> SqlResult<Vec<User>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, first_name, surname, email, number, winner FROM users ORDER BY id"
-        )?;
-
-        let users = stmt.query_map([], |row| {
-            Ok(User {
-                id: row.get(0)?,
-                first_name: row.get(1)?,
-                surname: row.get(2)?,
-                email: row.get(3)?,
-                number: row.get(4)?,
-                winner: row.get::<_, i32>(5)? == 1,
-            })
-        })?
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(users)
-    }
-
-    fn calculate_winners(&self, max_number: i32) -> SqlResult<()> {
-        self.conn.execute("UPDATE users SET winner = 0", [])?;
-        let users = self.get_all_users()?;
-
-        if users.is_empty() {
-            return Ok(());
+    match config.validate().and_then(|_| config.save(path)) {
+        Ok(()) => {
+            let message = format!(
+                "{} password {}",
+                if operator { "Operator" } else { "Admin" },
+                if password.is_empty() { "removed" } else { "set" }
+            );
+            log::info!("{} in {}", message, path.display());
+            eprintln!("{} in {}", message, path.display());
+            0
         }
-
-        let mut users_with_distance: Vec<_> = users.iter()
-            .map(|u| (u.id, (u.number - max_number).abs()))
-            .collect();
-
-        users_with_distance.sort_by_key(|&(_, dist)| dist);
-
-        let winner_count = users_with_distance.len().min(5);
-        for i in 0..winner_count {
-            let user_id = users_with_distance[i].0;
-            self.conn.execute(
-                "UPDATE users SET winner = 1 WHERE id = ?1",
-                [user_id],
-            )?;
+        Err(e) => {
+            log::error!("{}", e);
+            eprintln!("{}", e);
+            1
         }
-
-        Ok(())
-    }
-
-    fn get_sorted_users(&self, max_number: i32) -> SqlResult<Vec<User>> {
-        let mut users = self.get_all_users()?;
-
-        users.sort_by(|a, b| {
-            match (b.winner, a.winner) {
-                (true, false) => std::cmp::Ordering::Greater,
-                (false, true) => std::cmp::Ordering::Less,
-                _ => {
-                    let dist_a = (a.number - max_number).abs();
-                    let dist_b = (b.number - max_number).abs();
-                    dist_a.cmp(&dist_b)
-                }
-            }
-        });
-
-        Ok(users)
     }
 }
 
-struct DevWindow
-{
-    open: bool,
-    max_number: String,
-}
-
-struct TableWindow
-{
-    open: bool,
-}
-
-struct MyApp
-{
-    first_name: String,
-    surname: String,
-    email: String,
-    number: String,
-    snowflakes: Vec<Snowflake>,
-    database: Arc<Mutex<Database>>,
-    dev_window: DevWindow,
-    table_window: TableWindow,
-    message: String,
-    background_texture: Option<egui::TextureHandle>,
-    export_message: String,
-}
-
-impl MyApp
-{
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let mut rng = rand::thread_rng();
-        let snowflakes: Vec<Snowflake> = (0..500) // einstelung der Geschwindikeit menge und Gröze der Flocken
-            .map(|_| Snowflake {
-                x: rng.gen_range(0.0..1.0),
-                y: rng.gen_range(-0.8..0.0),
-                speed: rng.gen_range(0.001..0.0025),
-                size: rng.gen_range(2.0..11.0),
-                side_toside_speed:rng.gen_range(-0.0025..0.0025),
-            })
-            .collect();
-
-        let background_texture = Self::load_background_image(&cc.egui_ctx);
-
-        Self {
-            first_name: String::new(),
-            surname: String::new(),
-            email: String::new(),
-            number: String::new(),
-            snowflakes,
-            database: Arc::new(Mutex::new(Database::new().unwrap())),
-            dev_window: DevWindow {
-                open: false,
-                max_number: "300".to_string(),
-            },
-            table_window: TableWindow {
-                open: false,
-            },
-            message: String::new(),
-            background_texture,
-            export_message: String::new(),
-        }
-    }
-
-    fn load_background_image(ctx: &egui::Context) -> Option<egui::TextureHandle> {
-        // why wont it ucking Load
-        // fixed it
-        let possible_paths = vec![
-            "src/img/p4.jpg",
-            "img/p4.jpg",
-            "./img/p4.jpg",
-            "../img/p4.jpg",
-            "p4.jpg",
-        ];
-
-        for img_path_str in &possible_paths {
-            let img_path = std::path::Path::new(img_path_str);
-
-            if let Ok(img) = image::open(img_path) {
-                let img_buffer = img.to_rgba8();
-                let size = [img_buffer.width() as usize, img_buffer.height() as usize];
-                let pixels = img_buffer.as_flat_samples();
-
-                let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                    size,
-                    pixels.as_slice(),
-                );
-
-                println!("Background image loaded from: {}", img_path_str);
-                return Some(ctx.load_texture(
-                    "background",
-                    color_image,
-                    egui::TextureOptions::LINEAR,
-                ));
+/// Runs a CLI subcommand and returns the process exit code.
+fn run_command(db: &Database, command: Command) -> i32 {
+    let result = match command {
+        Command::Draw { target, count, exclude_previous, mode, seed, dry_run } => {
+            let seed = seed.unwrap_or_else(|| rand::thread_rng().gen_range(0..=i64::MAX as u64));
+            if mode == WinnerMode::WeightedRandom {
+                eprintln!("Seed: {}", seed);
             }
+            let targets = vec![target; count];
+            let winners = if dry_run {
+                db.preview_winners(&targets, exclude_previous, mode, seed).map(|ranked| {
+                    ranked.into_iter()
+                        .map(|w| User { winner: true, prize_index: Some(w.prize_index as i32), ..w.user })
+                        .collect()
+                })
+            } else {
+                db.calculate_winners(&targets, exclude_previous, mode, seed)
+                    .and_then(|_| db.get_sorted_users(target))
+                    .map(|users| users.into_iter().filter(|u| u.winner).collect::<Vec<User>>())
+            };
+            winners
+                .map(|winners| println!("{}", serde_json::to_string_pretty(&winners).unwrap()))
+                .map_err(|e| format!("Draw failed: {}", e))
         }
-
-        eprintln!("Warning: Could not load background image.");
-        None
-    }
-
-    fn export_to_excel(&self) -> Result<String, String>
-    {
-        let db = self.database.lock().unwrap();
-        let users = db.get_all_users()
-            .map_err(|e| format!("Database error: {}", e))?;
-
-        if users.is_empty()
-        {
-            return Err("No data to export!".to_string());
+        Command::Export { format, out } => {
+            // the columns picked in the dev window
+            let saved = db.dev_settings().unwrap_or_default().export_columns.unwrap_or_default();
+            let columns = enabled_columns(&column_spec(&saved));
+            db.get_all_users()
+                .and_then(|users| Ok((users, db.get_prizes()?)))
+                .map_err(|e| format!("Export failed: {}", e))
+                .and_then(|(users, prizes)| export_users(&users, &prizes, format, &columns, &out.to_string_lossy()))
+                .map(|message| {
+                    log::info!("{}", message);
+                    eprintln!("{}", message);
+                })
         }
-
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let filename = format!("registrations_{}.xlsx", timestamp);
-
-        let mut workbook = Workbook::create(&filename);
-        let mut sheet = workbook.create_sheet("Registrations");
-
-        sheet.add_column(Column { width: 8.0 });
-        sheet.add_column(Column { width: 15.0 });
-        sheet.add_column(Column { width: 15.0 });
-        sheet.add_column(Column { width: 25.0 });
-        sheet.add_column(Column { width: 12.0 });
-        sheet.add_column(Column { width: 10.0 });
-
-        workbook.write_sheet(&mut sheet, |sheet_writer|
-            {
-                let sw = sheet_writer;
-
-                sw.append_row
-                (
-                    row!
-                    [
-                        "ID",
-                        "First Name",
-                        "Surname",
-                        "Email",
-                        "Number",
-                        "Winner"
-                    ]
-                )?;
-
-            for user in users.iter()
-            {
-                sw.append_row
-                (
-                    row!
-                    [
-                    user.id.to_string(),
-                    user.first_name.clone(),
-                    user.surname.clone(),
-                    user.email.clone(),
-                    user.number.to_string(),
-                    if user.winner { "YES" } else { "NO" }
-                ])?;
-            }
-
-            Ok(())
-        }).map_err(|e| format!("Write error: {:?}", e))?;
-
-        workbook.close().map_err(|e| format!("Save error: {:?}", e))?;
-
-        Ok(format!("Exported {} users to {}", users.len(), filename))
-    }
-}
-
-impl eframe::App for MyApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Update snowflakes
-        // down movment
-        for flake in &mut self.snowflakes {
-            flake.y += flake.speed;
-            if flake.y > 1.1 {
-                flake.y = -0.1;
-                flake.x = rand::thread_rng().gen_range(0.0..1.0);
-            }
-        }
-        // side Movment
-        for flake in &mut self.snowflakes
-        {
-            flake.x -= flake.side_toside_speed;
-            if flake.y > 1.1
-            {
-                flake.y = -0.1;
-                flake.x =rand::thread_rng().gen_range(0.0..1.0)
-            }
-        }
-
-
-        ctx.request_repaint();
-
-        // Dev window toggle mit Ctrl+Shift+D
-        if ctx.input(|i| i.key_pressed(egui::Key::D) && i.modifiers.ctrl && i.modifiers.shift) {
-            self.dev_window.open = !self.dev_window.open;
+        Command::Stats => {
+            let stats = || -> rusqlite::Result<serde_json::Value> {
+                let db_stats = db.stats()?;
+                let numbers = db.number_stats()?;
+                Ok(serde_json::json!({
+                    "registrations": db_stats.registrations,
+                    "winners": db_stats.winners,
+                    "distinct_emails": db_stats.distinct_emails,
+                    "registration_open": db.registration_open()?,
+                    "numbers": {
+                        "min": numbers.min,
+                        "max": numbers.max,
+                        "mean": numbers.mean,
+                        "median": numbers.median,
+                    },
+                }))
+            };
+            stats()
+                .map(|json| println!("{}", serde_json::to_string_pretty(&json).unwrap()))
+                .map_err(|e| format!("Stats failed: {}", e))
         }
+        Command::SetPassword { .. } => unreachable!("handled before the database is opened"),
+    };
 
-        // Table window toggle mit Ctrl+Windows+L
-        if ctx.input(|i| i.key_pressed(egui::Key::L) && i.modifiers.ctrl && i.modifiers.command) {
-            self.table_window.open = !self.table_window.open;
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            log::error!("{}", e);
+            eprintln!("{}", e);
+            1
         }
+    }
+}
 
-        // Developer window
-        if self.dev_window.open {
-            let mut dev_open = self.dev_window.open;
-            egui::Window::new("Developer Settings")
-                .open(&mut dev_open)
-                .default_width(400.0)
-                .show(ctx, |ui| {
-                    ui.label("Max Number (Zielzahl):");
-                    ui.text_edit_singleline(&mut self.dev_window.max_number);
-
-                    ui.add_space(10.0);
-
-                    if ui.button("Calculate Winners (Top 5 closest)").clicked() {
-                        if let Ok(max_num) = self.dev_window.max_number.parse::<i32>() {
-                            let db = self.database.lock().unwrap();
-                            match db.calculate_winners(max_num) {
-                                Ok(_) => self.export_message = "Winners calculated successfully!".to_string(),
-                                Err(e) => self.export_message = format!("Error: {}", e),
-                            }
-                        } else {
-                            self.export_message = "Invalid max number!".to_string();
-                        }
-                    }
-
-                    ui.add_space(10.0);
-                    ui.separator();
-
-                    if ui.button("Export All Data to Excel").clicked() {
-                        match self.export_to_excel() {
-                            Ok(msg) => self.export_message = msg,
-                            Err(e) => self.export_message = format!("Error: {}", e),
-                        }
-                    }
-
-                    if !self.export_message.is_empty() {
-                        ui.add_space(5.0);
-                        ui.colored_label(
-                            if self.export_message.contains("success") || self.export_message.contains("Exported") {
-                                egui::Color32::GREEN
-                            } else {
-                                egui::Color32::RED
-                            },
-                            &self.export_message,
-                        );
-                    }
+fn main() -> Result<(), eframe::Error> {
+    let cli = Cli::parse();
+    // without a log the booth still runs, the console keeps the errors;
+    // subcommands print their own messages, so only the GUI echoes the log
+    if let Err(e) = logging::init(&logging::default_dir(), cli.verbose, cli.command.is_none()) {
+        eprintln!("Logging disabled: {}", e);
+    }
 
-                    ui.add_space(10.0);
-                    ui.separator();
-                    ui.label("Shortcuts:");
-                    ui.small("Ctrl+Shift+D - Dev Settings");
-                    ui.small("Ctrl+Win+L - Table View");
-                    ui.add_space(5.0);
-                    ui.label("Developed by Pierre Maurice Hesse");
-                });
-            self.dev_window.open = dev_open;
+    let config_path = cli.config.clone().unwrap_or_else(AppConfig::default_path);
+    log::info!("Starting v{} ({}), config {}", env!("CARGO_PKG_VERSION"), env!("GIT_HASH"), config_path.display());
+    let mut config = match AppConfig::load(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!("Invalid config: {}", e);
+            eprintln!("Invalid config: {}", e);
+            std::process::exit(1);
         }
+    };
+    config.kiosk |= cli.kiosk;
+    crash::install_hook(logging::default_dir(), &config, &cli.db);
+    if let Some(Command::SetPassword { operator }) = cli.command {
+        std::process::exit(set_password(config, &config_path, operator));
+    }
+    let database = Database::open(&cli.db);
+    match &database {
+        Ok(_) => log::info!("Opened database {}", cli.db.display()),
+        Err(e) => log::error!("Could not open database {}: {}", cli.db.display(), e),
+    }
 
-        // Table window
-        if self.table_window.open {
-            let mut table_open = self.table_window.open;
-            egui::Window::new("Registrations Table")
-                .open(&mut table_open)
-                .default_width(700.0)
-                .default_height(500.0)
-                .show(ctx, |ui| {
-                    let db = self.database.lock().unwrap();
-                    let max_num = self.dev_window.max_number.parse::<i32>().unwrap_or(300);
-
-                    match db.get_sorted_users(max_num) {
-                        Ok(users) => {
-                            if users.is_empty() {
-                                ui.label("No registrations yet.");
-                            } else {
-                                ui.label(format!("Total registrations: {} | Target number: {}", users.len(), max_num));
-                                ui.add_space(5.0);
-
-                                egui::ScrollArea::vertical().show(ui, |ui| {
-                                    ui.heading("Winners (Top 5 closest)");
-                                    ui.separator();
-
-                                    for (idx, user) in users.iter().enumerate() {
-                                        let distance = (user.number - max_num).abs();
-                                        let bg_color = if user.winner {
-                                            egui::Color32::from_rgb(50, 100, 50)
-                                        } else if idx % 2 == 0 {
-                                            egui::Color32::from_rgb(30, 30, 35)
-                                        } else {
-                                            egui::Color32::from_rgb(25, 25, 30)
-                                        };
-
-                                        ui.horizontal(|ui| {
-                                            let frame = egui::Frame::none().fill(bg_color).inner_margin(5.0);
-                                            frame.show(ui, |ui| {
-                                                ui.set_min_width(650.0);
-
-                                                if user.winner {
-                                                    ui.label(egui::RichText::new("[WINNER]").color(egui::Color32::GOLD).size(14.0));
-                                                }
+    if let Some(command) = cli.command {
+        let database = database.unwrap_or_else(|e| {
+            eprintln!("Could not open database {}: {}", cli.db.display(), e);
+            std::process::exit(1);
+        });
+        std::process::exit(run_command(&database, command));
+    }
 
-                                                ui.label(format!("ID: {}", user.id));
-                                                ui.separator();
-                                                ui.label(&user.first_name);
-                                                ui.label(&user.surname);
-                                                ui.separator();
-                                                ui.label(&user.email);
-                                                ui.separator();
-                                                ui.label(format!("Number: {}", user.number));
-                                                ui.separator();
-                                                ui.colored_label(
-                                                    if distance < 10 {
-                                                        egui::Color32::GREEN
-                                                    } else if distance < 50 {
-                                                        egui::Color32::YELLOW
-                                                    } else {
-                                                        egui::Color32::GRAY
-                                                    },
-                                                    format!("Distance: {}", distance)
-                                                );
-                                            });
-                                        });
-                                        ui.add_space(2.0);
-                                    }
-                                });
-                            }
-                        }
-                        Err(e) => {
-                            ui.colored_label(egui::Color32::RED, format!("Error: {}", e));
-                        }
-                    }
-                });
-            self.table_window.open = table_open;
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size([config.window_width, config.window_height])
+        .with_min_inner_size(MIN_WINDOW_SIZE)
+        .with_decorations(true); // Fensterrahmen bleiben
+    if config.kiosk {
+        if config.admin_password_hash.is_none() {
+            log::warn!("Kiosk mode without an admin password, Ctrl+Shift+Alt+Q quits right away");
         }
-
-        // Main panel - OHNE RAHMEN UND PADDING
-        egui::CentralPanel::default()
-            .frame(egui::Frame::none()) // Entfernt alle Rahmen und Padding
-            .show(ctx, |ui| {
-                let painter = ui.painter();
-                let rect = ui.max_rect();
-
-                // Hintergrundbild über den gesamten Bildschirm
-                if let Some(texture) = &self.background_texture {
-                    painter.image(
-                        texture.id(),
-                        rect,
-                        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
-                        egui::Color32::WHITE,
-                    );
-                } else {
-                    // Fallback, falls das Bild nicht geladen werden kann
-                    painter.rect_filled(
-                        rect,
-                        0.0,
-                        egui::Color32::from_rgb(15, 20, 35),
-                    );
-                }
-
-                // Schneeflocken über dem Hintergrund
-                for flake in &self.snowflakes {
-                    painter.circle_filled(
-                        egui::pos2(
-                            rect.left() + flake.x * rect.width(),
-                            rect.top() + flake.y * rect.height(),
-                        ),
-                        flake.size,
-                        egui::Color32::from_rgba_unmultiplied(255, 255, 255, 200),
-                    );
-                }
-
-                let form_width = (rect.width() * 0.35).clamp(280.0, 400.0);
-                let form_height = (rect.height() * 0.5).clamp(280.0, 350.0);
-
-                // Registrierungsformular muss Transparenz komisches Vireck invordergrung
-                egui::Window::new("Winter Registration")
-                    .fixed_pos(egui::pos2(
-                        rect.center().x - form_width / 2.0,
-                        rect.center().y - form_height / 2.0,
-                    ))
-                    .fixed_size(egui::vec2(form_width, form_height))
-                    .collapsible(false)
-                    .frame(egui::Frame {
-                        fill: egui::Color32::from_rgba_unmultiplied(30, 30, 35, 50), // Hier kann die  Transparenz geändert werden (0-255)
-                        rounding: egui::Rounding::same(10.0),
-                       // stroke: egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(255, 255, 0, 255)),// eine Umrandung wenn die gewolt wird
-                        inner_margin: egui::Margin::same(15.0),
-                        ..Default::default()
-                    })
-                    .show(ctx, |ui| {
-                        ui.vertical_centered(|ui| {
-                            ui.heading("Register");
-                            ui.add_space(10.0);
-                        });
-
-                        ui.label("First Name:");
-                        ui.text_edit_singleline(&mut self.first_name);
-
-                        ui.label("Surname:");
-                        ui.text_edit_singleline(&mut self.surname);
-
-                        ui.label("Email:");
-                        ui.text_edit_singleline(&mut self.email);
-
-                        ui.label("Number (1 to ∞):");
-                        ui.text_edit_singleline(&mut self.number);
-
-                        ui.add_space(10.0);
-
-                        if ui.button("Submit").clicked() {
-                            if self.first_name.is_empty() || self.surname.is_empty() ||
-                                self.email.is_empty() || self.number.is_empty() {
-                                self.message = "Please fill all fields!".to_string();
-                            } else if let Ok(num) = self.number.parse::<i32>() {
-                                if num >= 1 {
-                                    let db = self.database.lock().unwrap();
-                                    match db.insert_user(&self.first_name, &self.surname, &self.email, num) {
-                                        Ok(_) => {
-                                            self.message = "Registration successful!".to_string();
-                                            self.first_name.clear();
-                                            self.surname.clear();
-                                            self.email.clear();
-                                            self.number.clear();
-                                        }
-                                        Err(e) => self.message = format!("Error: {}", e),
-                                    }
-                                } else {
-                                    self.message = "Number must be >= 1".to_string();
-                                }
-                            } else {
-                                self.message = "Invalid number format!".to_string();
-                            }
-                        }
-
-                        if !self.message.is_empty() {
-                            ui.add_space(5.0);
-                            ui.colored_label(
-                                if self.message.contains("successful") {
-                                    egui::Color32::GREEN
-                                } else {
-                                    egui::Color32::RED
-                                },
-                                &self.message,
-                            );
-                        }
-
-                        ui.add_space(5.0);
-                        ui.separator();
-                        ui.vertical_centered(|ui| {
-                            ui.small("Developed by Pierre Maurice Hesse");
-                        });
-                    });
-            });
+        viewport = viewport.with_fullscreen(true).with_decorations(false);
+    } else if let Some(g) = database.as_ref().ok().and_then(|db| db.window_geometry().ok().flatten()) {
+        // wo das Fenster beim letzten Schließen war; liegt das nicht mehr auf
+        // dem Bildschirm, holt MyApp::keep_window_on_screen es zurück
+        viewport = viewport
+            .with_inner_size([g.width.max(MIN_WINDOW_SIZE[0]), g.height.max(MIN_WINDOW_SIZE[1])])
+            .with_position([g.x, g.y]);
     }
-}
-
-fn main() -> Result<(), eframe::Error> {
-    let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([800.0, 600.0])
-            .with_min_inner_size([640.0, 480.0])
-            .with_decorations(true), // Fensterrahmen bleiben
-        ..Default::default()
-    };
+    let options = eframe::NativeOptions { viewport, ..Default::default() };
 
     eframe::run_native(
-        "Snow Drift Registration - by Pierre Maurice Hesse",
+        APP_TITLE,
         options,
-        Box::new(|cc| Box::new(MyApp::new(cc))),
+        Box::new(|cc| Box::new(Booth::new(&cc.egui_ctx, cli.db, database, cli.events, (config, config_path), cli.serve))),
     )
-}
\ No newline at end of file
+}
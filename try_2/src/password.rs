@@ -0,0 +1,181 @@
+//! Admin password for the dev and table windows.
+//!
+//! New hashes are Argon2id in the PHC string format (`$argon2id$v=19$...`).
+//! Configs written by earlier versions hold
+//! `pbkdf2-sha256$<iterations>$<salt hex>$<hash hex>`, which still verifies.
+
+use std::time::{Duration, Instant};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+
+const LEGACY_PREFIX: &str = "pbkdf2-sha256";
+
+/// New salted hash for the config file.
+pub fn hash_password(password: &str) -> String {
+    hash_with(&Argon2::default(), password)
+}
+
+fn hash_with(argon2: &Argon2, password: &str) -> String {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let salt = SaltString::encode_b64(&salt).expect("16 bytes is a valid salt length");
+    argon2
+        .hash_password(password.as_bytes(), &salt)
+        .expect("the default Argon2 parameters are valid")
+        .to_string()
+}
+
+/// False for a wrong password and for anything that is not one of our hashes.
+/// Takes a noticeable moment on purpose, keep it off the UI thread.
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    let stored = stored.trim();
+    if let Some((iterations, salt, hash)) = parse_legacy(stored) {
+        let mut computed = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, iterations, &mut computed);
+        // same time for every wrong byte
+        return computed.len() == hash.len() && computed.iter().zip(&hash).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0;
+    }
+    PasswordHash::new(stored).is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+/// Whether `stored` looks like something `hash_password`, or an earlier
+/// version of it, wrote.
+pub fn is_password_hash(stored: &str) -> bool {
+    parse_legacy(stored).is_some()
+        || PasswordHash::new(stored).is_ok_and(|hash| hash.algorithm.as_str().starts_with("argon2"))
+}
+
+/// (iterations, salt, hash) of a `pbkdf2-sha256$...` entry.
+fn parse_legacy(stored: &str) -> Option<(u32, Vec<u8>, Vec<u8>)> {
+    let parts: Vec<&str> = stored.split('$').collect();
+    let [LEGACY_PREFIX, iterations, salt, hash] = parts[..] else {
+        return None;
+    };
+    Some((iterations.parse().ok()?, unhex(salt)?, unhex(hash)?))
+}
+
+/// Keyed SHA-256 of `text` in hex; the same `salt` gives the same hash on
+/// every run, a different one gives unrelated hashes.
+pub fn salted_hash(text: &str, salt: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(salt.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(text.as_bytes());
+    hex(&mac.finalize().into_bytes())
+}
+
+/// Random salt for `salted_hash`.
+pub fn new_salt() -> String {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    hex(&salt)
+}
+
+/// Refuses further attempts for a while after too many wrong passwords.
+pub struct Lockout {
+    pub max_attempts: u32,
+    pub duration: Duration,
+    failures: u32,
+    until: Option<Instant>,
+}
+
+impl Lockout {
+    pub fn new(max_attempts: u32, duration: Duration) -> Self {
+        Lockout { max_attempts, duration, failures: 0, until: None }
+    }
+
+    /// Time left until the next attempt is allowed.
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.until.and_then(|until| until.checked_duration_since(now)).filter(|d| !d.is_zero())
+    }
+
+    /// Counts an attempt; the `max_attempts`-th failure in a row locks.
+    pub fn record(&mut self, success: bool, now: Instant) {
+        if success {
+            self.failures = 0;
+            return;
+        }
+        self.failures += 1;
+        if self.failures >= self.max_attempts.max(1) {
+            self.failures = 0;
+            self.until = Some(now + self.duration);
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if text.is_empty() || !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_published_test_vectors() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            salted_hash("what do ya want for nothing?", "Jefe"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // RFC 6070 with SHA-256, as an earlier version stored it
+        let legacy = "pbkdf2-sha256$4096$73616c74$c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a";
+        assert!(is_password_hash(legacy));
+        assert!(verify_password("password", legacy));
+        assert!(!verify_password("Password", legacy));
+    }
+
+    #[test]
+    fn salted_hashes_are_stable_per_salt() {
+        let first = salted_hash("anna@example.com", "abc");
+        assert_eq!(first, salted_hash("anna@example.com", "abc"));
+        assert_ne!(first, salted_hash("anna@example.com", "abd"));
+        assert_eq!(first.len(), 64);
+        assert_ne!(new_salt(), new_salt());
+    }
+
+    #[test]
+    fn hashes_are_salted_and_verify() {
+        // the real cost is slow in debug builds; it is stored in the hash anyway
+        let cheap = Argon2::new(
+            argon2::Algorithm::Argon2id,
+            argon2::Version::V0x13,
+            argon2::Params::new(1024, 1, 1, None).unwrap(),
+        );
+        let first = hash_with(&cheap, "schneeflocke");
+        assert!(first.starts_with("$argon2id$"), "{}", first);
+        assert!(is_password_hash(&first));
+        assert_ne!(first, hash_with(&cheap, "schneeflocke"));
+        assert!(verify_password("schneeflocke", &first));
+        assert!(!verify_password("Schneeflocke", &first));
+        // a plaintext entry in the config never unlocks anything
+        assert!(!verify_password("schneeflocke", "schneeflocke"));
+        assert!(!is_password_hash("schneeflocke"));
+    }
+
+    #[test]
+    fn locks_after_too_many_failures() {
+        let start = Instant::now();
+        let mut lockout = Lockout::new(3, Duration::from_secs(60));
+        lockout.record(false, start);
+        lockout.record(false, start);
+        assert_eq!(lockout.remaining(start), None);
+        lockout.record(false, start);
+        assert_eq!(lockout.remaining(start), Some(Duration::from_secs(60)));
+        assert_eq!(lockout.remaining(start + Duration::from_secs(61)), None);
+
+        let later = start + Duration::from_secs(61);
+        lockout.record(false, later);
+        lockout.record(true, later);
+        lockout.record(false, later);
+        assert_eq!(lockout.remaining(later), None);
+    }
+}
@@ -0,0 +1,57 @@
+//! Fake registrations for trying the app out.
+
+use rand::Rng;
+use crate::db::NewUser;
+
+/// Shape of the guesses the test-data generator produces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestDistribution {
+    Uniform,
+    /// bell curve centred on the range, most guesses near the middle
+    Normal,
+}
+
+const TEST_FIRST_NAMES: &[&str] = &[
+    "Anna", "Lukas", "Mia", "Jonas", "Emma", "Leon", "Sophie", "Felix", "Hannah", "Paul",
+    "Lena", "Maximilian", "Marie", "Elias", "Laura", "Noah", "Clara", "Finn", "Lea", "Jürgen",
+];
+const TEST_SURNAMES: &[&str] = &[
+    "Müller", "Schmidt", "Schneider", "Fischer", "Weber", "Meyer", "Wagner", "Becker", "Schulz", "Hoffmann",
+    "Koch", "Richter", "Klein", "Wolf", "Schröder", "Neumann", "Schwarz", "Braun", "Zimmermann", "Hesse",
+];
+
+/// Random but plausible registrations with guesses in `range`.
+/// `first_index` keeps the emails unique across several runs.
+pub fn generate_test_users(
+    rng: &mut impl Rng,
+    count: usize,
+    first_index: i64,
+    distribution: TestDistribution,
+    range: std::ops::RangeInclusive<i32>,
+) -> Vec<NewUser> {
+    let (low, high) = (*range.start(), *range.end());
+    (0..count)
+        .map(|i| {
+            let first_name = TEST_FIRST_NAMES[rng.gen_range(0..TEST_FIRST_NAMES.len())];
+            let surname = TEST_SURNAMES[rng.gen_range(0..TEST_SURNAMES.len())];
+            let number = match distribution {
+                TestDistribution::Uniform => rng.gen_range(low..=high),
+                TestDistribution::Normal => {
+                    // Box-Muller, ±3 sigma spans the range
+                    let (u1, u2): (f64, f64) = (rng.gen_range(f64::EPSILON..1.0), rng.gen_range(0.0..1.0));
+                    let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+                    let mean = (f64::from(low) + f64::from(high)) / 2.0;
+                    let sigma = (f64::from(high) - f64::from(low)) / 6.0;
+                    ((mean + z * sigma).round() as i32).clamp(low, high)
+                }
+            };
+            NewUser {
+                first_name: first_name.to_string(),
+                surname: surname.to_string(),
+                email: format!("test+{}@example.invalid", first_index + i as i64),
+                number,
+                is_test: true,
+            }
+        })
+        .collect()
+}
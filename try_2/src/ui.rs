@@ -0,0 +1,5226 @@
+//! The egui front end.
+
+use eframe::egui;
+use rusqlite::Result as SqlResult;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::config::{AppConfig, ThemeColors, parse_shortcut};
+use crate::crash;
+use crate::db::{
+    BatchReport, DEFAULT_SUBMIT_INTERVAL_SECS, Database, DbStats, DevSettings, NewUser, NotifyStatus, RowOutcome, ToolWindow, NumberStats, Prize,
+    User, UserStore,
+    WindowGeometry, db_error_message, lock_database, take_poison_notice, unix_now,
+};
+use crate::error::AppError;
+use crate::draw::{RankedWinner, WinnerMode, parse_targets, prize_name, rank_by_distance};
+use crate::export::{
+    ExportColumn, anonymize_user, column_spec, enabled_columns, file_prefix, initials, unique_path, winner_emails, write_excel,
+};
+use crate::form::{
+    FieldError, FormField, MAX_EMAIL_LEN, MAX_NAME_LEN, RateLimiter, count_numbers, find_duplicates,
+    fuzzy_search, guess_range_label, normalize_imported, normalize_registration, trim_form_fields, truncate_chars, validate_form,
+};
+use crate::http::{HttpServer, local_ip};
+use crate::i18n::{Lang, set_lang, tr};
+use crate::import::{import_from_csv, import_from_xlsx};
+use crate::logging;
+use crate::password::{Lockout, new_salt, verify_password};
+use crate::testdata::{TestDistribution, generate_test_users};
+use crate::tr;
+use crate::webhook::Webhook;
+
+#[derive(Clone)]
+struct Snowflake {
+    x: f32,
+    y: f32,
+    /// 0 = far, 1 = near
+    depth: f32,
+    speed: f32,
+    side_toside_speed: f32,
+    size: f32,
+    /// radians, advanced by `spin` every frame
+    rotation: f32,
+    spin: f32,
+    /// seconds left for a burst flake, None for the snow that never ends
+    life: Option<f32>,
+}
+
+impl Snowflake {
+    /// Neue Flocke auf Höhe `y`, nahe Flocken sind größer, schneller und weniger durchsichtig
+    fn spawn(rng: &mut impl Rng, backdrop: &Backdrop, y: f32) -> Self {
+        let depth = rng.gen_range(0.0..1.0);
+        let between = |(far, near): (f32, f32)| far + (near - far) * depth;
+        Snowflake {
+            x: rng.gen_range(0.0..1.0),
+            y,
+            depth,
+            speed: between(backdrop.speed) + rng.gen_range(0.0..0.0003),
+            size: between(backdrop.size) + rng.gen_range(0.0..1.0),
+            side_toside_speed: rng.gen_range(-0.0025..0.0025) * (0.5 + depth),
+            rotation: rng.gen_range(0.0..std::f32::consts::TAU),
+            spin: rng.gen_range(-0.02..0.02),
+            life: None,
+        }
+    }
+
+    /// Thrown outwards from (`x`, `y`) for the registration burst, then
+    /// pulled down by `BURST_GRAVITY` until its life runs out.
+    fn burst(rng: &mut impl Rng, x: f32, y: f32) -> Self {
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let force = rng.gen_range(0.004..0.012);
+        Snowflake {
+            x,
+            y,
+            depth: 1.0,
+            speed: angle.sin() * force,
+            // the window is wider than high, so a little less sideways
+            side_toside_speed: angle.cos() * force * 0.6,
+            size: rng.gen_range(4.0..9.0),
+            rotation: rng.gen_range(0.0..std::f32::consts::TAU),
+            spin: rng.gen_range(-0.1..0.1),
+            life: Some(CELEBRATION_SECS * rng.gen_range(0.6..1.0)),
+        }
+    }
+
+    fn alpha(&self) -> u8 {
+        match self.life {
+            // burst flakes fade out over their last half second
+            Some(life) => (255.0 * (life / 0.5).clamp(0.0, 1.0)) as u8,
+            None => (80.0 + self.depth * 175.0) as u8,
+        }
+    }
+
+    /// Sechs Zacken: drei Striche durch die Mitte, gedreht um `rotation`.
+    fn paint_star(&self, painter: &egui::Painter, center: egui::Pos2, color: egui::Color32) {
+        let stroke = egui::Stroke::new((self.size * 0.25).max(1.0), color);
+        for arm in 0..3 {
+            let angle = self.rotation + arm as f32 * std::f32::consts::FRAC_PI_3;
+            let tip = egui::vec2(angle.cos(), angle.sin()) * self.size;
+            painter.line_segment([center - tip, center + tip], stroke);
+        }
+    }
+}
+
+/// A fresh snowfall, still above the window and about to fall in.
+fn snowfall(backdrop: &Backdrop) -> Vec<Snowflake> {
+    let mut rng = rand::thread_rng();
+    let mut snowflakes: Vec<Snowflake> = (0..backdrop.count)
+        .map(|_| {
+            let y = rng.gen_range(-0.8..0.0);
+            Snowflake::spawn(&mut rng, backdrop, y)
+        })
+        .collect();
+    snowflakes.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+    snowflakes
+}
+
+/// Snow and what it falls in front of; a `ThemePreset` sets all of it,
+/// the dev window can change each value afterwards.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Backdrop {
+    /// flakes falling at once
+    count: usize,
+    /// (far, near) flakes' fall speed in window heights per frame
+    speed: (f32, f32),
+    /// (far, near) flakes' size in points
+    size: (f32, f32),
+    /// behind the snow when there is no background picture
+    background: egui::Color32,
+    /// the color even if there is a picture
+    flat: bool,
+}
+
+impl Default for Backdrop {
+    fn default() -> Self {
+        Backdrop {
+            count: 500, // einstelung der Geschwindikeit menge und Gröze der Flocken
+            speed: (0.0008, 0.0028),
+            size: (2.0, 10.0),
+            background: egui::Color32::from_rgb(15, 20, 35),
+            flat: false,
+        }
+    }
+}
+
+/// One-click looks for the season; the config's `[theme]` colors still win.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ThemePreset {
+    Christmas,
+    NewYear,
+    /// corporate: no snow, no picture
+    Neutral,
+}
+
+impl ThemePreset {
+    const ALL: [ThemePreset; 3] = [ThemePreset::Christmas, ThemePreset::NewYear, ThemePreset::Neutral];
+
+    /// Name stored in the dev settings.
+    fn code(self) -> &'static str {
+        match self {
+            ThemePreset::Christmas => "christmas",
+            ThemePreset::NewYear => "new_year",
+            ThemePreset::Neutral => "neutral",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        ThemePreset::ALL.into_iter().find(|p| p.code() == value)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ThemePreset::Christmas => tr("Christmas"),
+            ThemePreset::NewYear => tr("New Year"),
+            ThemePreset::Neutral => tr("Neutral"),
+        }
+    }
+
+    fn colors(self) -> ThemeColors {
+        let rgb = |r, g, b| Some(egui::Color32::from_rgb(r, g, b));
+        match self {
+            ThemePreset::Christmas => ThemeColors {
+                primary: rgb(180, 30, 45),
+                winner_highlight: rgb(30, 110, 50),
+                success: rgb(30, 120, 55),
+                ..ThemeColors::default()
+            },
+            ThemePreset::NewYear => ThemeColors {
+                primary: rgb(200, 160, 40),
+                winner_highlight: rgb(130, 100, 20),
+                form_background: Some(egui::Color32::from_rgba_unmultiplied(10, 20, 60, 120)),
+                ..ThemeColors::default()
+            },
+            ThemePreset::Neutral => ThemeColors {
+                primary: rgb(70, 110, 150),
+                winner_highlight: rgb(60, 90, 120),
+                form_background: Some(egui::Color32::from_rgba_unmultiplied(40, 42, 48, 230)),
+                ..ThemeColors::default()
+            },
+        }
+    }
+
+    fn backdrop(self) -> Backdrop {
+        match self {
+            ThemePreset::Christmas => Backdrop {
+                count: 900,
+                speed: (0.0010, 0.0030),
+                background: egui::Color32::from_rgb(15, 35, 25),
+                ..Backdrop::default()
+            },
+            ThemePreset::NewYear => Backdrop {
+                count: 200,
+                speed: (0.0004, 0.0012),
+                size: (1.5, 6.0),
+                background: egui::Color32::from_rgb(10, 15, 45),
+                flat: false,
+            },
+            ThemePreset::Neutral => Backdrop {
+                count: 0,
+                background: egui::Color32::from_rgb(40, 42, 48),
+                flat: true,
+                ..Backdrop::default()
+            },
+        }
+    }
+}
+
+const CELEBRATION_SECS: f32 = 2.0;
+const BURST_FLAKES: usize = 80;
+/// added to a burst flake's fall speed every frame
+const BURST_GRAVITY: f32 = 0.0004;
+
+/// Snow burst and "✔ Registered!" after a registration. Only for show:
+/// the form is cleared already and typing the next entry ends it.
+struct Celebration {
+    /// seconds since the registration
+    age: f32,
+    flakes: Vec<Snowflake>,
+}
+
+impl Celebration {
+    /// Bursts from the middle of the window, where the form sits.
+    fn new(rng: &mut impl Rng) -> Self {
+        let flakes = (0..BURST_FLAKES).map(|_| Snowflake::burst(rng, 0.5, 0.5)).collect();
+        Celebration { age: 0.0, flakes }
+    }
+
+    fn tick(&mut self, dt: f32) {
+        self.age += dt;
+        for flake in &mut self.flakes {
+            flake.x -= flake.side_toside_speed;
+            flake.y += flake.speed;
+            flake.speed += BURST_GRAVITY;
+            flake.rotation += flake.spin;
+            flake.life = flake.life.map(|life| life - dt);
+        }
+        self.flakes.retain(|f| f.life.is_some_and(|life| life > 0.0));
+    }
+
+    fn done(&self) -> bool {
+        self.age >= CELEBRATION_SECS
+    }
+
+    fn draw(&self, painter: &egui::Painter, rect: egui::Rect, simple: bool) {
+        for flake in &self.flakes {
+            let center = egui::pos2(rect.left() + flake.x * rect.width(), rect.top() + flake.y * rect.height());
+            let color = egui::Color32::from_rgba_unmultiplied(255, 255, 255, flake.alpha());
+            if simple {
+                painter.circle_filled(center, flake.size, color);
+            } else {
+                flake.paint_star(painter, center, color);
+            }
+        }
+        // full for the first half, then fading
+        let alpha = ((CELEBRATION_SECS - self.age) / (CELEBRATION_SECS / 2.0)).clamp(0.0, 1.0);
+        let text_pos = rect.center() - egui::vec2(0.0, rect.height() * 0.3);
+        painter.text(
+            text_pos + egui::vec2(3.0, 3.0),
+            egui::Align2::CENTER_CENTER,
+            tr("✔ Registered!"),
+            egui::FontId::proportional(64.0),
+            egui::Color32::BLACK.gamma_multiply(0.6 * alpha),
+        );
+        painter.text(
+            text_pos,
+            egui::Align2::CENTER_CENTER,
+            tr("✔ Registered!"),
+            egui::FontId::proportional(64.0),
+            egui::Color32::from_rgb(120, 230, 140).gamma_multiply(alpha),
+        );
+    }
+}
+
+/// Title of the booth window; the unlocked role is appended.
+pub const APP_TITLE: &str = "Snow Drift Registration - by Pierre Maurice Hesse";
+
+const SNOW_BUCKETS: usize = 120;
+/// Highest the drift may grow, as a fraction of the window height.
+const SNOW_PILE_MAX: f32 = 0.12;
+/// Fraction of the window height that melts away per second.
+const SNOW_MELT_RATE: f32 = 0.002;
+
+/// Adds a landed flake to the drift, spilling a bit into the neighbours so
+/// the pile grows as a smooth hill instead of single spikes.
+fn pile_snow(pile: &mut [f32], bucket: usize, flake_size: f32) {
+    let amount = flake_size * 0.001;
+    let mut add = |idx: usize, share: f32| {
+        if let Some(h) = pile.get_mut(idx) {
+            *h = (*h + amount * share).min(SNOW_PILE_MAX);
+        }
+    };
+    add(bucket, 1.0);
+    if bucket > 0 {
+        add(bucket - 1, 0.5);
+    }
+    add(bucket + 1, 0.5);
+}
+
+/// how many of the booth's own registrations can be undone in a row
+const UNDO_DEPTH: usize = 5;
+const DELETE_UNDO_DEPTH: usize = 10;
+
+/// how often the GUI looks for writes made by the CLI or another window
+const AUTO_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+/// the mouse cursor disappears in kiosk mode after this long without moving
+const KIOSK_CURSOR_HIDE: Duration = Duration::from_secs(5);
+/// table window width below which rows switch to two lines
+const COMPACT_TABLE_WIDTH: f32 = 900.0;
+/// the table's "Reveal winners" shows one more winner this often
+const TABLE_REVEAL_STEP: Duration = Duration::from_secs(2);
+/// changed dev window inputs are saved at most this often, and on exit
+const SETTINGS_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// a half-filled form is saved this often while someone types
+const DRAFT_SAVE_INTERVAL: Duration = Duration::from_secs(3);
+/// a CSV import reports its progress every this many rows
+const IMPORT_PROGRESS_STEP: usize = 100;
+/// a field being typed into is checked once typing pauses this long
+const VALIDATE_DELAY: Duration = Duration::from_millis(700);
+/// how long a first Esc or "Clear" waits for the second one
+const CLEAR_CONFIRM_WINDOW: Duration = Duration::from_secs(3);
+/// the dev window looks up the LAN address again this often, a cable may
+/// have been plugged in
+const LAN_IP_REFRESH: Duration = Duration::from_secs(10);
+
+/// Field label, red while the field has an error.
+fn form_label(ui: &mut egui::Ui, text: &str, errors: &[FieldError], field: FormField) {
+    if errors.iter().any(|e| e.field == field) {
+        ui.colored_label(egui::Color32::RED, text);
+    } else {
+        ui.label(text);
+    }
+}
+
+fn form_error(ui: &mut egui::Ui, errors: &[FieldError], field: FormField) {
+    for e in errors.iter().filter(|e| e.field == field) {
+        ui.small(egui::RichText::new(e.message.as_str()).color(egui::Color32::RED));
+    }
+}
+
+/// Where Tab goes in the registration form, in this order.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum FormStop {
+    Field(FormField),
+    Submit,
+}
+
+const TAB_ORDER: [FormStop; 5] = [
+    FormStop::Field(FormField::FirstName),
+    FormStop::Field(FormField::Surname),
+    FormStop::Field(FormField::Email),
+    FormStop::Field(FormField::Number),
+    FormStop::Submit,
+];
+
+/// The stop after `stop`, or before it with Shift; wraps around.
+fn tab_step(stop: FormStop, back: bool) -> FormStop {
+    let i = TAB_ORDER.iter().position(|s| *s == stop).unwrap_or(0);
+    let step = if back { TAB_ORDER.len() - 1 } else { 1 };
+    TAB_ORDER[(i + step) % TAB_ORDER.len()]
+}
+
+/// A key of the on-screen keypad or keyboard.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum KeyInput {
+    Char(char),
+    Backspace,
+    Clear,
+}
+
+fn apply_key(text: &mut String, key: KeyInput) {
+    match key {
+        KeyInput::Char(c) => text.push(c),
+        KeyInput::Backspace => {
+            text.pop();
+        }
+        KeyInput::Clear => text.clear(),
+    }
+}
+
+/// Buttons big enough for a finger.
+fn key_button(ui: &mut egui::Ui, label: &str, size: egui::Vec2) -> bool {
+    ui.add(egui::Button::new(egui::RichText::new(label).size(size.y * 0.5)).min_size(size)).clicked()
+}
+
+/// Calculator layout, 7-8-9 on top.
+fn numeric_keypad(ui: &mut egui::Ui) -> Option<KeyInput> {
+    let size = egui::vec2(64.0, 52.0);
+    let mut pressed = None;
+    egui::Grid::new("numeric_keypad").spacing([6.0, 6.0]).show(ui, |ui| {
+        for row in [['7', '8', '9'], ['4', '5', '6'], ['1', '2', '3']] {
+            for digit in row {
+                if key_button(ui, &digit.to_string(), size) {
+                    pressed = Some(KeyInput::Char(digit));
+                }
+            }
+            ui.end_row();
+        }
+        if key_button(ui, "C", size) {
+            pressed = Some(KeyInput::Clear);
+        }
+        if key_button(ui, "0", size) {
+            pressed = Some(KeyInput::Char('0'));
+        }
+        if key_button(ui, "⌫", size) {
+            pressed = Some(KeyInput::Backspace);
+        }
+        ui.end_row();
+    });
+    pressed
+}
+
+/// German layout, with the characters email addresses need.
+fn qwertz_keyboard(ui: &mut egui::Ui, shift: &mut bool) -> Option<KeyInput> {
+    const ROWS: [&str; 4] = ["1234567890", "qwertzuiopü", "asdfghjklöä", "yxcvbnm@.-_"];
+    let size = egui::vec2(34.0, 40.0);
+    let mut pressed = None;
+    for row in ROWS {
+        ui.horizontal(|ui| {
+            for c in row.chars() {
+                let c = if *shift { c.to_uppercase().next().unwrap_or(c) } else { c };
+                if key_button(ui, &c.to_string(), size) {
+                    pressed = Some(KeyInput::Char(c));
+                }
+            }
+        });
+    }
+    ui.horizontal(|ui| {
+        if ui.add(egui::SelectableLabel::new(*shift, egui::RichText::new("⇧").size(20.0))).clicked() {
+            *shift = !*shift;
+        }
+        if key_button(ui, tr("Space"), egui::vec2(180.0, 40.0)) {
+            pressed = Some(KeyInput::Char(' '));
+        }
+        if key_button(ui, "⌫", size) {
+            pressed = Some(KeyInput::Backspace);
+        }
+    });
+    // one capital letter, like a phone keyboard
+    if matches!(pressed, Some(KeyInput::Char(c)) if c.is_alphabetic()) {
+        *shift = false;
+    }
+    pressed
+}
+
+/// Bigger text and spacing for the form on a touch monitor.
+fn touch_style(style: &mut egui::Style) {
+    for (text_style, font) in style.text_styles.iter_mut() {
+        font.size = match text_style {
+            egui::TextStyle::Heading => 32.0,
+            egui::TextStyle::Small => 15.0,
+            _ => 22.0,
+        };
+    }
+    style.spacing.item_spacing = egui::vec2(10.0, 10.0);
+    style.spacing.button_padding = egui::vec2(14.0, 10.0);
+    style.spacing.interact_size.y = 40.0;
+}
+
+/// Colors the table uses for distances and winners.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Palette {
+    Standard,
+    /// blue to orange, which red-green color blindness keeps apart
+    ColorBlind,
+}
+
+impl Palette {
+    const ALL: [Palette; 2] = [Palette::Standard, Palette::ColorBlind];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Palette::Standard => tr("Standard"),
+            Palette::ColorBlind => tr("Color-blind safe"),
+        }
+    }
+
+    /// Perfect guess at one end of the scale, `max_distance` at the other.
+    fn distance_color(self, distance: i64, max_distance: i64) -> egui::Color32 {
+        let t = distance_fraction(distance, max_distance);
+        match self {
+            // hue 1/3 is green, 0 is red
+            Palette::Standard => egui::ecolor::Hsva::new((1.0 - t) / 3.0, 1.0, 1.0, 1.0).into(),
+            Palette::ColorBlind => {
+                let (near, far) = ([86.0, 180.0, 233.0], [230.0, 159.0, 0.0]);
+                let mix = |i: usize| (near[i] + (far[i] - near[i]) * t) as u8;
+                egui::Color32::from_rgb(mix(0), mix(1), mix(2))
+            }
+        }
+    }
+
+    fn winner_fill(self, dark: bool) -> egui::Color32 {
+        match (self, dark) {
+            (Palette::Standard, true) => egui::Color32::from_rgb(50, 100, 50),
+            (Palette::Standard, false) => egui::Color32::from_rgb(190, 230, 190),
+            (Palette::ColorBlind, true) => egui::Color32::from_rgb(20, 70, 120),
+            (Palette::ColorBlind, false) => egui::Color32::from_rgb(180, 210, 240),
+        }
+    }
+}
+
+/// Light or dark look of the whole app, picked in the developer window.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Theme {
+    /// whatever the OS reports, dark if it reports nothing
+    System,
+    Dark,
+    Light,
+}
+
+impl Theme {
+    const ALL: [Theme; 3] = [Theme::System, Theme::Dark, Theme::Light];
+
+    /// Name stored in the dev settings.
+    fn code(self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Theme::ALL.into_iter().find(|t| t.code() == value)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Theme::System => tr("System"),
+            Theme::Dark => tr("Dark"),
+            Theme::Light => tr("Light"),
+        }
+    }
+
+    fn is_dark(self, system: Option<eframe::Theme>) -> bool {
+        match self {
+            Theme::System => system != Some(eframe::Theme::Light),
+            Theme::Dark => true,
+            Theme::Light => false,
+        }
+    }
+}
+
+/// egui's light or dark look with the config's primary color on top.
+fn themed_visuals(dark: bool, colors: &ThemeColors) -> egui::Visuals {
+    let mut visuals = if dark { egui::Visuals::dark() } else { egui::Visuals::light() };
+    if let Some(primary) = colors.primary {
+        visuals.selection.bg_fill = primary;
+        visuals.hyperlink_color = primary;
+    }
+    visuals
+}
+
+/// Alternating table row background.
+fn stripe_fill(idx: usize, dark: bool) -> egui::Color32 {
+    match (idx.is_multiple_of(2), dark) {
+        (true, true) => egui::Color32::from_rgb(30, 30, 35),
+        (false, true) => egui::Color32::from_rgb(25, 25, 30),
+        (true, false) => egui::Color32::from_rgb(245, 245, 248),
+        (false, false) => egui::Color32::from_rgb(232, 232, 238),
+    }
+}
+
+/// `GOLD` is unreadable on the light winner rows.
+fn badge_gold(dark: bool) -> egui::Color32 {
+    if dark { egui::Color32::GOLD } else { egui::Color32::from_rgb(150, 105, 0) }
+}
+
+/// Translucent so the background picture shows through the registration form.
+fn form_fill(dark: bool) -> egui::Color32 {
+    if dark {
+        egui::Color32::from_rgba_unmultiplied(30, 30, 35, 50) // Hier kann die  Transparenz geändert werden (0-255)
+    } else {
+        egui::Color32::from_rgba_unmultiplied(245, 245, 250, 190)
+    }
+}
+
+fn distance_fraction(distance: i64, max_distance: i64) -> f32 {
+    if max_distance <= 0 {
+        0.0
+    } else {
+        (distance as f32 / max_distance as f32).clamp(0.0, 1.0)
+    }
+}
+
+/// Ids of the winners the table's reveal still hides `elapsed` after it
+/// started: the last prize shows first, one more every `TABLE_REVEAL_STEP`.
+fn unrevealed_winners(users: &[User], elapsed: Duration) -> Vec<i32> {
+    let mut winners: Vec<&User> = users.iter().filter(|u| u.winner).collect();
+    winners.sort_by_key(|u| u.prize_index.unwrap_or(i32::MAX));
+    let shown = (elapsed.as_secs_f32() / TABLE_REVEAL_STEP.as_secs_f32()) as usize;
+    let hidden = winners.len().saturating_sub(shown);
+    winners[..hidden].iter().map(|u| u.id).collect()
+}
+
+/// Case-insensitive match on name, email, ticket or guess; empty matches all.
+fn matches_filter(user: &User, filter: &str) -> bool {
+    let filter = filter.trim().to_lowercase();
+    filter.is_empty()
+        || format!("{} {}", user.first_name, user.surname).to_lowercase().contains(&filter)
+        || user.email.to_lowercase().contains(&filter)
+        || filter.trim_start_matches('#') == user.ticket_number.to_string()
+        || filter == user.number.to_string()
+}
+
+/// Farther guesses show as ">1,000,000", so a joke entry can't widen every row.
+const DISTANCE_DISPLAY_CAP: i64 = 1_000_000;
+
+/// "12,345"; above `DISTANCE_DISPLAY_CAP` only the cap.
+fn format_distance(d: i64) -> String {
+    if d > DISTANCE_DISPLAY_CAP {
+        return format!(">{}", format_distance(DISTANCE_DISPLAY_CAP));
+    }
+    let digits = d.unsigned_abs().to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3 + 1);
+    if d < 0 {
+        out.push('-');
+    }
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Spelled out next to the color, so it doesn't carry the meaning alone.
+fn distance_label(distance: i64, max_distance: i64) -> &'static str {
+    match distance_fraction(distance, max_distance) {
+        t if t < 1.0 / 3.0 => tr("close"),
+        t if t < 2.0 / 3.0 => tr("medium"),
+        _ => tr("far"),
+    }
+}
+
+fn format_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+/// Opens `path` with the system's default app, a folder in the file manager.
+fn open_path(path: &Path) -> std::io::Result<()> {
+    let program = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(program).arg(path).spawn().map(|_| ())
+}
+
+fn rect_geometry(rect: egui::Rect) -> WindowGeometry {
+    WindowGeometry { x: rect.min.x, y: rect.min.y, width: rect.width(), height: rect.height() }
+}
+
+fn geometry_rect(g: WindowGeometry) -> egui::Rect {
+    egui::Rect::from_min_size(egui::pos2(g.x, g.y), egui::vec2(g.width, g.height))
+}
+
+/// Moves and, if needed, shrinks `rect` into `screen`, e.g. when the booth
+/// window came back smaller than the tool window was saved at.
+fn clamp_to_screen(rect: egui::Rect, screen: egui::Rect) -> egui::Rect {
+    let size = rect.size().min(screen.size());
+    let min = rect.min.clamp(screen.min, screen.max - size);
+    egui::Rect::from_min_size(min, size)
+}
+
+/// The sender for `url`, None when it is empty or doesn't parse.
+fn start_webhook(url: &str) -> Option<Webhook> {
+    match Webhook::start(url) {
+        Ok(webhook) => Some(webhook),
+        Err(_) if url.is_empty() => None,
+        Err(e) => {
+            log::warn!("Webhook disabled: {}", e);
+            None
+        }
+    }
+}
+
+/// Dev window text for a guess range; no upper bound shows as an empty max.
+fn guess_range_inputs(guesses: &RangeInclusive<i32>) -> (String, String) {
+    let max = if *guesses.end() == i32::MAX { String::new() } else { guesses.end().to_string() };
+    (guesses.start().to_string(), max)
+}
+
+/// Accepts "HH:MM" (next occurrence, so 00:30 late at night means tomorrow)
+/// or a full "YYYY-MM-DD HH:MM".
+fn parse_clock_time(input: &str) -> Option<chrono::DateTime<chrono::Local>> {
+    use chrono::TimeZone;
+    let input = input.trim();
+    let now = chrono::Local::now();
+
+    if let Ok(full) = chrono::NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M") {
+        return chrono::Local.from_local_datetime(&full).earliest();
+    }
+
+    let time = chrono::NaiveTime::parse_from_str(input, "%H:%M").ok()?;
+    let today = chrono::Local.from_local_datetime(&now.date_naive().and_time(time)).earliest()?;
+    if today > now {
+        Some(today)
+    } else {
+        Some(today + chrono::Duration::days(1))
+    }
+}
+
+fn format_countdown(remaining_secs: i64) -> String {
+    let secs = remaining_secs.max(0);
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Level {
+    Info,
+    Success,
+    Error,
+}
+
+const TOAST_LIFETIME: Duration = Duration::from_secs(5);
+/// errors stay longer, they usually need reading twice
+const ERROR_TOAST_LIFETIME: Duration = Duration::from_secs(10);
+const TOAST_FADE: Duration = Duration::from_millis(600);
+/// older toasts wait off screen until newer ones expire
+const MAX_SHOWN_TOASTS: usize = 4;
+
+impl Level {
+    fn lifetime(self) -> Duration {
+        match self {
+            Level::Error => ERROR_TOAST_LIFETIME,
+            Level::Info | Level::Success => TOAST_LIFETIME,
+        }
+    }
+}
+
+/// Kleine Benachrichtigungen unten rechts, verschwinden nach ein paar Sekunden
+#[derive(Default)]
+struct Toasts {
+    items: Vec<(String, Instant, Level)>,
+}
+
+impl Toasts {
+    fn push(&mut self, level: Level, text: impl Into<String>) {
+        self.items.push((text.into(), Instant::now(), level));
+    }
+
+    fn info(&mut self, text: impl Into<String>) {
+        self.push(Level::Info, text);
+    }
+
+    fn success(&mut self, text: impl Into<String>) {
+        self.push(Level::Success, text);
+    }
+
+    /// Also goes to the log file, the toast is gone after a few seconds.
+    fn error(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        log::error!("{}", text);
+        self.push(Level::Error, text);
+    }
+
+    /// Drops what has expired at `now`; the newest `MAX_SHOWN_TOASTS` of
+    /// the rest, oldest first.
+    fn visible(&mut self, now: Instant) -> &[(String, Instant, Level)] {
+        self.items.retain(|(_, created, level)| now.duration_since(*created) < level.lifetime());
+        &self.items[self.items.len().saturating_sub(MAX_SHOWN_TOASTS)..]
+    }
+
+    fn show(&mut self, ctx: &egui::Context, colors: &ThemeColors) {
+        let now = Instant::now();
+        let items = self.visible(now);
+        if items.is_empty() {
+            return;
+        }
+
+        egui::Area::new("toasts")
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (text, created, level) in items {
+                    let remaining = level.lifetime().saturating_sub(now.duration_since(*created));
+                    let alpha = (remaining.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0);
+                    let color = match level {
+                        Level::Info => colors.primary.unwrap_or(egui::Color32::from_rgb(60, 90, 140)),
+                        Level::Success => colors.success.unwrap_or(egui::Color32::from_rgb(40, 120, 60)),
+                        Level::Error => colors.error.unwrap_or(egui::Color32::from_rgb(150, 40, 40)),
+                    };
+
+                    // see-through, the form behind stays readable
+                    egui::Frame::none()
+                        .fill(color.gamma_multiply(0.85 * alpha))
+                        .rounding(egui::Rounding::same(6.0))
+                        .inner_margin(egui::Margin::same(8.0))
+                        .show(ui, |ui| {
+                            ui.colored_label(egui::Color32::WHITE.gamma_multiply(alpha), text);
+                        });
+                    ui.add_space(4.0);
+                }
+            });
+    }
+}
+
+const REVEAL_DELAY: f32 = 3.0;
+
+/// What the CSV import thread sends back.
+enum ImportUpdate {
+    Progress(usize),
+    /// None after a cancel
+    Finished(Result<Option<BatchReport>, String>),
+}
+
+/// A CSV import running on its own database connection, so the booth
+/// keeps going meanwhile.
+struct ImportJob {
+    path: PathBuf,
+    /// file line of every row handed to the database, in order
+    lines: Vec<usize>,
+    /// rows refused before the database saw them
+    invalid: Vec<String>,
+    done: usize,
+    cancel: Arc<AtomicBool>,
+    updates: mpsc::Receiver<ImportUpdate>,
+}
+
+/// Siegerehrung: winners are uncovered from last place to first.
+struct Reveal {
+    /// 1st place first, winners set by hand after the drawn ones
+    winners: Vec<User>,
+    prizes: Vec<Prize>,
+    /// how many places (counted from the back) are already visible
+    revealed: usize,
+    /// seconds spent on the current step
+    timer: f32,
+}
+
+impl Reveal {
+    fn finished(&self) -> bool {
+        self.revealed >= self.winners.len()
+    }
+
+    /// Place of the winner at `idx`, 1-based, from the prize they won; None
+    /// for winners set by hand, who have no prize.
+    fn place(&self, idx: usize) -> Option<usize> {
+        self.winners[idx].prize_index.map(|prize| prize as usize + 1)
+    }
+
+    /// Index of the winner that is revealed next.
+    fn next_index(&self) -> usize {
+        self.winners.len() - self.revealed - 1
+    }
+
+    fn advance(&mut self) {
+        if !self.finished() {
+            self.revealed += 1;
+            self.timer = 0.0;
+        }
+    }
+
+    fn draw(&self, painter: &egui::Painter, rect: egui::Rect) {
+        painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(150));
+
+        let center = rect.center();
+        let big = egui::FontId::proportional((rect.height() * 0.09).clamp(32.0, 96.0));
+        let medium = egui::FontId::proportional((rect.height() * 0.045).clamp(18.0, 40.0));
+        let small = egui::FontId::proportional(16.0);
+
+        if self.finished() {
+            painter.text(
+                egui::pos2(center.x, rect.top() + rect.height() * 0.12),
+                egui::Align2::CENTER_CENTER,
+                tr("Congratulations!"),
+                medium.clone(),
+                egui::Color32::GOLD,
+            );
+        } else {
+            // Spannung: Punkte zählen bis zur nächsten Enthüllung hoch
+            let dots = ".".repeat(1 + (self.timer / REVEAL_DELAY * 3.0) as usize);
+            let heading = match self.place(self.next_index()) {
+                Some(place) => tr!("{} place{}", ordinal(place), dots),
+                None => tr!("Next winner{}", dots),
+            };
+            painter.text(
+                egui::pos2(center.x, center.y - rect.height() * 0.25),
+                egui::Align2::CENTER_CENTER,
+                heading,
+                big.clone(),
+                egui::Color32::WHITE,
+            );
+        }
+
+        // revealed places, newest (best) on top
+        let start = self.winners.len() - self.revealed;
+        for (row, (idx, user)) in self.winners.iter().enumerate().skip(start).enumerate() {
+            let font = if row == 0 { big.clone() } else { medium.clone() };
+            let y = center.y - rect.height() * 0.08 + row as f32 * rect.height() * 0.12;
+            let place = self.place(idx);
+            let label = place.map(ordinal).unwrap_or_else(|| tr("Winner").to_string());
+            painter.text(
+                egui::pos2(center.x, y),
+                egui::Align2::CENTER_CENTER,
+                format!("{}: {} {} ({})", label, user.first_name, user.surname, user.number),
+                font,
+                if place == Some(1) { egui::Color32::GOLD } else { egui::Color32::WHITE },
+            );
+            painter.text(
+                egui::pos2(center.x, y + rect.height() * 0.05),
+                egui::Align2::CENTER_CENTER,
+                prize_name(&self.prizes, user.prize_index),
+                small.clone(),
+                egui::Color32::LIGHT_GRAY,
+            );
+        }
+
+        painter.text(
+            egui::pos2(center.x, rect.bottom() - 20.0),
+            egui::Align2::CENTER_CENTER,
+            tr("Space / click: next    Esc: back"),
+            small,
+            egui::Color32::GRAY,
+        );
+    }
+}
+
+/// The Zielzahl at the top center, above the snow and the reveal.
+fn paint_targets(painter: &egui::Painter, rect: egui::Rect, targets: &[i32]) {
+    let text = match targets {
+        [target] => tr!("Target: {}", target),
+        _ => tr!("Targets: {}", targets.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(" · ")),
+    };
+    let font = egui::FontId::proportional((rect.height() * 0.07).clamp(28.0, 64.0));
+    let galley = painter.layout_no_wrap(text, font, egui::Color32::WHITE);
+    let pos = egui::pos2(rect.center().x - galley.size().x / 2.0, rect.top() + 12.0);
+    painter.rect_filled(
+        egui::Rect::from_min_size(pos, galley.size()).expand2(egui::vec2(18.0, 6.0)),
+        12.0,
+        egui::Color32::from_black_alpha(120),
+    );
+    painter.galley(pos, galley);
+}
+
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (1, 11) | (2, 12) | (3, 13) => tr("th"),
+        (1, _) => tr("st"),
+        (2, _) => tr("nd"),
+        (3, _) => tr("rd"),
+        _ => tr("th"),
+    };
+    format!("{}{}", n, suffix)
+}
+
+const COUNTDOWN_FLASH_SECS: i64 = 5;
+
+/// "Draw in 14:59" overlay. Works off the wall clock, so minimizing the
+/// window or dropped frames never make it drift.
+struct Countdown {
+    target: chrono::DateTime<chrono::Local>,
+    /// text is only rebuilt when the second changes
+    label: String,
+    last_second: i64,
+}
+
+impl Countdown {
+    fn new(target: chrono::DateTime<chrono::Local>) -> Self {
+        Countdown { target, label: String::new(), last_second: i64::MIN }
+    }
+
+    /// Seconds left; negative once the target has passed.
+    fn remaining(&self) -> i64 {
+        (self.target - chrono::Local::now()).num_seconds()
+    }
+
+    fn tick(&mut self) {
+        let now = chrono::Local::now().timestamp();
+        if now == self.last_second {
+            return;
+        }
+        self.last_second = now;
+        let remaining = self.remaining();
+        self.label = if remaining > 0 {
+            tr!("Draw in {}", format_countdown(remaining))
+        } else if remaining > -COUNTDOWN_FLASH_SECS {
+            tr!("Draw in {}", "00:00")
+        } else {
+            tr("Draw time!").to_string()
+        };
+    }
+
+    fn draw(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let remaining = self.remaining();
+        let flashing = remaining <= 0 && remaining > -COUNTDOWN_FLASH_SECS;
+        // blinkt zweimal pro Sekunde nach Ablauf
+        let on = chrono::Local::now().timestamp_subsec_millis() < 500;
+        let color = if flashing && on {
+            egui::Color32::from_rgba_unmultiplied(255, 80, 80, 230)
+        } else {
+            egui::Color32::from_rgba_unmultiplied(255, 255, 255, 150)
+        };
+
+        painter.text(
+            egui::pos2(rect.right() - 20.0, rect.top() + 15.0),
+            egui::Align2::RIGHT_TOP,
+            &self.label,
+            egui::FontId::proportional((rect.height() * 0.07).clamp(28.0, 64.0)),
+            color,
+        );
+    }
+}
+
+struct StatsWindow {
+    open: bool,
+    bin_width: String,
+    /// (revision, bin width) the cached numbers belong to
+    cache_key: Option<((i64, i64), i64)>,
+    stats: NumberStats,
+    bins: Vec<(i64, i64)>,
+}
+
+impl StatsWindow {
+    fn bin_width(&self) -> i64 {
+        self.bin_width.trim().parse::<i64>().ok().filter(|w| *w > 0).unwrap_or(10)
+    }
+}
+
+/// A computed but not yet committed draw.
+struct DrawPreview {
+    targets: Vec<i32>,
+    exclude_previous: bool,
+    mode: WinnerMode,
+    seed: u64,
+    revision: (i64, i64),
+    /// `data_revision` at preview time; committing refuses once it moved
+    data_revision: Option<i64>,
+    winners: Vec<RankedWinner>,
+    /// top N guesses around the first target, with their distance
+    nearest: Vec<(User, i64)>,
+}
+
+struct DevWindow
+{
+    open: bool,
+    max_number: String,
+    winner_count: String,
+    exclude_previous: bool,
+    mode: WinnerMode,
+    /// empty picks a fresh seed for every weighted draw
+    seed: String,
+    confirm_overwrite: bool,
+    confirm_unlock: bool,
+    pending_restore: Option<PathBuf>,
+    selected_draw: Option<i64>,
+    preview: Option<DrawPreview>,
+    countdown_input: String,
+    test_user_count: String,
+    test_distribution: TestDistribution,
+    /// guesses are generated within low..=high
+    test_range: (String, String),
+    countdown_closes: bool,
+    close_at_input: String,
+    /// max edits between two full names to still count as the same person
+    duplicate_distance: String,
+    duplicates: Option<Vec<Vec<User>>>,
+    /// must read DELETE before "Delete all registrations" unlocks
+    clear_confirm: String,
+    new_event_name: String,
+    new_event_backup: bool,
+    /// "Start new event" was clicked once
+    confirm_new_event: bool,
+    /// prize list being edited, loaded when the section is first opened
+    prize_edit: Option<Vec<Prize>>,
+    /// registration revision the stats were loaded at, and the result
+    db_stats: Option<((i64, i64), Result<DbStats, String>)>,
+    /// tell participants how crowded the number they type is
+    number_hint: bool,
+    /// pick up changes the CLI or another window wrote to the file
+    auto_refresh: bool,
+    /// plain circles instead of rotating stars, for slow machines
+    simple_snow: bool,
+    /// Zielzahl groß oben auf dem Hauptbildschirm, fürs Publikum
+    show_target: bool,
+    /// large form with an on-screen keypad, for a touch monitor
+    touch_mode: bool,
+    /// on-screen keyboard for the text fields as well
+    touch_keyboard: bool,
+    /// exports carry hashed emails, see `anonymize_user`
+    anonymize_export: bool,
+    /// ...and only the initials of the names
+    export_initials: bool,
+    /// `salted_hash` salt, empty until the first anonymized export
+    export_salt: String,
+    /// starts as `config.language`, then the selector wins
+    lang: Lang,
+    /// (column, exported) in file order, see `column_spec`
+    export_columns: Vec<(ExportColumn, bool)>,
+    theme: Theme,
+    /// None is the built-in look
+    preset: Option<ThemePreset>,
+    backdrop: Backdrop,
+    /// where "Start API" binds the info-screen server
+    serve_addr: String,
+    /// when `local_ip` was last asked, and its answer
+    lan_ip: Option<(Instant, Option<IpAddr>)>,
+    webhook_url: String,
+    /// min and max guess being edited; an empty max means no upper bound
+    guess_range_input: (String, String),
+    /// where the window was last drawn, saved with the settings
+    rect: Option<egui::Rect>,
+}
+
+impl DevWindow {
+    fn winner_count(&self) -> usize {
+        self.winner_count.trim().parse().unwrap_or(5)
+    }
+
+    fn targets(&self) -> Option<Vec<i32>> {
+        parse_targets(&self.max_number, self.winner_count())
+    }
+
+    fn settings(&self) -> DevSettings {
+        DevSettings {
+            targets: Some(self.max_number.clone()),
+            winner_count: Some(self.winner_count.clone()),
+            mode: Some(self.mode.as_str().to_string()),
+            exclude_previous: Some(self.exclude_previous),
+            number_hint: Some(self.number_hint),
+            auto_refresh: Some(self.auto_refresh),
+            simple_snow: Some(self.simple_snow),
+            show_target: Some(self.show_target),
+            touch_mode: Some(self.touch_mode),
+            touch_keyboard: Some(self.touch_keyboard),
+            anonymize_export: Some(self.anonymize_export),
+            export_initials: Some(self.export_initials),
+            export_salt: Some(self.export_salt.clone()).filter(|s| !s.is_empty()),
+            language: Some(self.lang.code().to_string()),
+            export_columns: Some(self.export_columns.clone()),
+            theme: Some(self.theme.code().to_string()),
+            theme_preset: Some(self.preset.map_or("none", ThemePreset::code).to_string()),
+            snow_count: Some(self.backdrop.count),
+            snow_speed: Some(self.backdrop.speed),
+            snow_size: Some(self.backdrop.size),
+            background_color: Some(self.backdrop.background.to_array()),
+            flat_background: Some(self.backdrop.flat),
+            // the window layout is MyApp's business
+            ..DevSettings::default()
+        }
+    }
+
+    /// Takes over what was saved; bad text ends up in the inputs like a typo would.
+    fn apply_settings(&mut self, settings: DevSettings) {
+        self.max_number = settings.targets.unwrap_or(std::mem::take(&mut self.max_number));
+        self.winner_count = settings.winner_count.unwrap_or(std::mem::take(&mut self.winner_count));
+        if let Some(mode) = settings.mode {
+            self.mode = WinnerMode::parse(&mode);
+        }
+        self.exclude_previous = settings.exclude_previous.unwrap_or(self.exclude_previous);
+        self.number_hint = settings.number_hint.unwrap_or(self.number_hint);
+        self.auto_refresh = settings.auto_refresh.unwrap_or(self.auto_refresh);
+        self.simple_snow = settings.simple_snow.unwrap_or(self.simple_snow);
+        self.show_target = settings.show_target.unwrap_or(self.show_target);
+        self.touch_mode = settings.touch_mode.unwrap_or(self.touch_mode);
+        self.touch_keyboard = settings.touch_keyboard.unwrap_or(self.touch_keyboard);
+        self.anonymize_export = settings.anonymize_export.unwrap_or(self.anonymize_export);
+        self.export_initials = settings.export_initials.unwrap_or(self.export_initials);
+        self.export_salt = settings.export_salt.unwrap_or(std::mem::take(&mut self.export_salt));
+        if let Some(lang) = settings.language.as_deref().and_then(Lang::parse) {
+            self.lang = lang;
+        }
+        if let Some(saved) = settings.export_columns {
+            self.export_columns = column_spec(&saved);
+        }
+        if let Some(theme) = settings.theme.as_deref().and_then(Theme::parse) {
+            self.theme = theme;
+        }
+        if let Some(preset) = settings.theme_preset {
+            self.preset = ThemePreset::parse(&preset);
+        }
+        let backdrop = &mut self.backdrop;
+        backdrop.count = settings.snow_count.unwrap_or(backdrop.count);
+        backdrop.speed = settings.snow_speed.unwrap_or(backdrop.speed);
+        backdrop.size = settings.snow_size.unwrap_or(backdrop.size);
+        if let Some([r, g, b, a]) = settings.background_color {
+            backdrop.background = egui::Color32::from_rgba_premultiplied(r, g, b, a);
+        }
+        backdrop.flat = settings.flat_background.unwrap_or(backdrop.flat);
+    }
+}
+
+struct TableWindow
+{
+    open: bool,
+    reroll_reason: String,
+    /// (user id, new winner state, display name) waiting for confirmation
+    pending_toggle: Option<(i32, bool, String)>,
+    /// recycle bin entry whose "Delete permanently" was clicked once
+    pending_purge: Option<i32>,
+    /// (user id, [first name, surname, email, number]) being edited
+    pending_edit: Option<(i32, [String; 4])>,
+    palette: Palette,
+    /// two-line rows; None picks them below `COMPACT_TABLE_WIDTH`
+    compact: Option<bool>,
+    /// only rows matching this are listed and exported by "Export view"
+    filter: String,
+    /// typo-tolerant filter, best matches first
+    fuzzy: bool,
+    /// when "Reveal winners" was clicked, None once all are shown
+    reveal_started: Option<Instant>,
+    rect: Option<egui::Rect>,
+}
+
+/// Who unlocked the staff windows: operators fix entries at the booth,
+/// admins run the event.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Role {
+    Operator,
+    Admin,
+}
+
+/// What a role may do, always asked through `MyApp::may`.
+#[derive(Clone, Copy, PartialEq)]
+enum Capability {
+    /// table window, search, edit, recycle bin
+    EditEntries,
+    DevSettings,
+    /// closing the kiosk
+    Quit,
+    DrawWinners,
+    Export,
+    /// anything that can't be undone from the recycle bin
+    DeleteData,
+}
+
+impl Role {
+    fn as_str(self) -> &'static str {
+        match self {
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Role::Operator => tr("Operator"),
+            Role::Admin => tr("Admin"),
+        }
+    }
+
+    fn allows(self, capability: Capability) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Operator => capability == Capability::EditEntries,
+        }
+    }
+}
+
+/// Windows behind the admin password.
+#[derive(Clone, Copy, PartialEq)]
+enum Guarded {
+    Dev,
+    Table,
+    /// leaving kiosk mode
+    Quit,
+}
+
+struct PasswordPrompt {
+    /// opened once the password is right
+    window: Guarded,
+    input: String,
+    keep_unlocked: bool,
+    /// the hash check running on its own thread, see `try_unlock`
+    checking: Option<mpsc::Receiver<Option<Role>>>,
+}
+
+struct AuditWindow {
+    open: bool,
+    /// None shows every action
+    action_filter: Option<String>,
+    user_filter: String,
+}
+
+/// One event's store, e.g. the kids' raffle next to the adults'. The app
+/// runs on SQLite files; tests can hand in any other `UserStore`.
+pub struct NamedDatabase {
+    /// the event name, the file name until the event has one
+    name: String,
+    path: PathBuf,
+    store: Arc<Mutex<dyn UserStore + Send>>,
+}
+
+impl NamedDatabase {
+    pub fn new(path: &Path, store: impl UserStore + Send + 'static) -> Self {
+        let name = store.event_name().ok().filter(|n| !n.is_empty()).unwrap_or_else(|| {
+            path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+        });
+        NamedDatabase { name, path: path.to_path_buf(), store: Arc::new(Mutex::new(store)) }
+    }
+}
+
+/// The registration booth: form, snow and the staff windows.
+pub struct MyApp
+{
+    first_name: String,
+    surname: String,
+    email: String,
+    number: String,
+    /// shown under the fields: the checked ones' problems plus `save_errors`
+    form_errors: Vec<FieldError>,
+    /// checked live: left once, or typed into and then paused
+    checked_fields: Vec<FormField>,
+    last_edit: Option<(FormField, Instant)>,
+    /// refused by the database on submit, until that field is edited
+    save_errors: Vec<FieldError>,
+    /// (first name, surname, email, number) waiting for the participant's OK
+    pending_registration: Option<(String, String, String, i32)>,
+    /// ids of the last few registrations from this booth, newest last
+    recent_inserts: Vec<i32>,
+    /// registrations as they were before "🗑", newest last
+    recent_deletes: Vec<User>,
+    snowflakes: Vec<Snowflake>,
+    snow_pile: Vec<f32>,
+    celebration: Option<Celebration>,
+    reveal: Option<Reveal>,
+    countdown: Option<Countdown>,
+    /// every event open side by side; the form and windows use `active`
+    databases: Vec<NamedDatabase>,
+    active: usize,
+    dev_window: DevWindow,
+    table_window: TableWindow,
+    stats_window: StatsWindow,
+    audit_window: AuditWindow,
+    toasts: Toasts,
+    background_texture: Option<egui::TextureHandle>,
+    export_job: Option<mpsc::Receiver<Result<String, String>>>,
+    import_job: Option<ImportJob>,
+    rng: StdRng,
+    /// cached from the settings table, written through by `set_registration_open`
+    registration_open: bool,
+    registration_close_at: Option<i64>,
+    /// numbers the form accepts, from the min_guess/max_guess settings
+    guess_range: RangeInclusive<i32>,
+    /// registration revision the counts were built at, and guesses per number
+    number_counts: Option<((i64, i64), HashMap<i32, usize>)>,
+    /// `data_revision` the cached state above was read at
+    seen_revision: Option<i64>,
+    last_poll: Instant,
+    /// JSON API for the info screens, shut down when dropped
+    http_server: Option<HttpServer>,
+    /// against double clicks on Submit; interval cached from the settings
+    submit_limiter: RateLimiter<()>,
+    /// None while no URL is set, so nothing runs in the background
+    webhook: Option<Webhook>,
+    config: AppConfig,
+    /// `config.theme` parsed, see "Reload theme"
+    colors: ThemeColors,
+    /// where "Save settings to config" writes
+    config_path: PathBuf,
+    /// dev window inputs as last written, to skip saves without changes
+    saved_settings: DevSettings,
+    last_settings_save: Instant,
+    /// form fields as last autosaved
+    saved_draft: [String; 4],
+    last_draft_save: Instant,
+    /// draft left over from the last run, until restored or discarded
+    offered_draft: Option<[String; 4]>,
+    /// where the on-screen keys type, the field focused last
+    key_target: FormField,
+    keyboard_shift: bool,
+    /// form widget with keyboard focus last frame, and the one to focus next
+    focused_stop: Option<FormStop>,
+    focus_request: Option<FormStop>,
+    /// first Esc or "Clear" press, the form is wiped on a second one soon after
+    clear_armed: Option<Instant>,
+    /// report of the last crash, offered until dismissed
+    crash_report: Option<PathBuf>,
+    /// role whose password was entered; without "keep unlocked" only until
+    /// the guarded windows are closed again
+    role: Option<Role>,
+    keep_unlocked: bool,
+    /// from the settings table, in the title bar and export file names
+    event_name: String,
+    /// window title as last sent
+    title: String,
+    lockout: Lockout,
+    password_prompt: Option<PasswordPrompt>,
+    /// last click or key in the dev or table window, for the idle lock
+    last_admin_input: Instant,
+    /// the last click landed in one of them, so keys typed now count too
+    admin_focused: bool,
+    /// kiosk mode only closes once this is set
+    quit_allowed: bool,
+    /// for hiding the cursor in kiosk mode
+    last_pointer_move: Instant,
+    /// the restored window position was checked against the monitor
+    window_on_screen: bool,
+    /// stays on top until dismissed, unlike a toast
+    error_banner: Option<String>,
+}
+
+impl MyApp
+{
+    /// The only place the UI touches the database; the lock is released
+    /// as soon as `f` returns, so never hold on to it across widgets. A
+    /// lock poisoned by a crash is recovered, see `take_poison_notice`.
+    fn with_db<F, R>(&self, f: F) -> Result<R, AppError>
+    where
+        F: FnOnce(&mut dyn UserStore) -> SqlResult<R>,
+    {
+        Ok(f(&mut *lock_database(&self.databases[self.active].store))?)
+    }
+
+    /// Toasts a failed call and keeps it in the log, where the booth
+    /// staff can look it up after the toast is gone.
+    fn report_error(&mut self, e: &AppError) {
+        log::error!("{}", e);
+        self.toasts.error(e.message());
+    }
+
+    /// Loads the settings from `event` and spawns the snow.
+    pub fn new(
+        ctx: &egui::Context,
+        event: NamedDatabase,
+        config: AppConfig,
+        config_path: PathBuf,
+    ) -> Self {
+        let database = lock_database(&event.store);
+        let seen_revision = database.data_revision().ok();
+        let registration_open = database.registration_open().unwrap_or(true);
+        let registration_close_at = database.registration_close_at().unwrap_or(None);
+        let submit_interval = database.submit_interval_secs().unwrap_or(DEFAULT_SUBMIT_INTERVAL_SECS);
+        let guess_range = database.guess_range().unwrap_or(1..=i32::MAX);
+        let guess_range_input = guess_range_inputs(&guess_range);
+        let saved_settings = database.dev_settings().unwrap_or_default();
+        let offered_draft = database.form_draft().ok().flatten();
+        let webhook_url = database.webhook_url().unwrap_or_default();
+        let event_name = database.event_name().unwrap_or_default();
+        drop(database);
+        let webhook = start_webhook(&webhook_url);
+
+        let background_texture = Self::load_background_image(ctx, config.background_image.as_deref());
+        let lockout = Lockout::new(config.unlock_attempts, Duration::from_secs(config.lockout_secs));
+        let (max_number, winner_count) = (config.target_number.to_string(), config.winner_count.to_string());
+        let lang = Lang::parse(&config.language).unwrap_or(Lang::En);
+
+        let mut app = Self {
+            first_name: String::new(),
+            surname: String::new(),
+            email: String::new(),
+            number: String::new(),
+            form_errors: Vec::new(),
+            checked_fields: Vec::new(),
+            last_edit: None,
+            save_errors: Vec::new(),
+            pending_registration: None,
+            recent_inserts: Vec::new(),
+            recent_deletes: Vec::new(),
+            snowflakes: Vec::new(),
+            snow_pile: vec![0.0; SNOW_BUCKETS],
+            celebration: None,
+            reveal: None,
+            countdown: None,
+            export_job: None,
+            import_job: None,
+            rng: StdRng::from_entropy(),
+            registration_open,
+            registration_close_at,
+            guess_range,
+            number_counts: None,
+            seen_revision,
+            last_poll: Instant::now(),
+            http_server: None,
+            submit_limiter: RateLimiter::new(Duration::from_secs(submit_interval)),
+            webhook,
+            config,
+            // both follow the preset, see `apply_settings`
+            colors: ThemeColors::default(),
+            config_path,
+            databases: vec![event],
+            active: 0,
+            dev_window: DevWindow {
+                open: false,
+                max_number,
+                winner_count,
+                exclude_previous: false,
+                mode: WinnerMode::Closest,
+                seed: String::new(),
+                confirm_overwrite: false,
+                confirm_unlock: false,
+                pending_restore: None,
+                selected_draw: None,
+                preview: None,
+                countdown_input: String::new(),
+                test_user_count: "100".to_string(),
+                test_distribution: TestDistribution::Uniform,
+                test_range: ("1".to_string(), "1000".to_string()),
+                countdown_closes: false,
+                close_at_input: String::new(),
+                duplicate_distance: "2".to_string(),
+                duplicates: None,
+                clear_confirm: String::new(),
+                new_event_name: String::new(),
+                new_event_backup: true,
+                confirm_new_event: false,
+                prize_edit: None,
+                db_stats: None,
+                number_hint: true,
+                auto_refresh: true,
+                simple_snow: false,
+                show_target: false,
+                touch_mode: false,
+                touch_keyboard: false,
+                anonymize_export: false,
+                export_initials: false,
+                export_salt: String::new(),
+                lang,
+                export_columns: column_spec(&[]),
+                theme: Theme::System,
+                preset: None,
+                backdrop: Backdrop::default(),
+                serve_addr: "0.0.0.0:8080".to_string(),
+                lan_ip: None,
+                webhook_url,
+                guess_range_input,
+                rect: None,
+            },
+            table_window: TableWindow {
+                open: false,
+                reroll_reason: String::new(),
+                pending_toggle: None,
+                pending_purge: None,
+                pending_edit: None,
+                palette: Palette::Standard,
+                compact: None,
+                filter: String::new(),
+                fuzzy: false,
+                reveal_started: None,
+                rect: None,
+            },
+            audit_window: AuditWindow {
+                open: false,
+                action_filter: None,
+                user_filter: String::new(),
+            },
+            stats_window: StatsWindow {
+                open: false,
+                bin_width: "10".to_string(),
+                cache_key: None,
+                stats: NumberStats::default(),
+                bins: Vec::new(),
+            },
+            toasts: Toasts::default(),
+            background_texture,
+            saved_settings: DevSettings::default(),
+            last_settings_save: Instant::now(),
+            saved_draft: offered_draft.clone().unwrap_or_default(),
+            last_draft_save: Instant::now(),
+            offered_draft,
+            key_target: FormField::Number,
+            keyboard_shift: true,
+            focused_stop: None,
+            focus_request: Some(FormStop::Field(FormField::FirstName)),
+            clear_armed: None,
+            crash_report: crash::unseen_report(&logging::default_dir()),
+            role: None,
+            keep_unlocked: false,
+            event_name,
+            title: String::new(),
+            lockout,
+            password_prompt: None,
+            last_admin_input: Instant::now(),
+            admin_focused: false,
+            quit_allowed: false,
+            last_pointer_move: Instant::now(),
+            window_on_screen: false,
+            error_banner: None,
+        };
+        app.apply_settings(saved_settings);
+        app.saved_settings = app.current_settings();
+        app
+    }
+
+    fn load_background_image(ctx: &egui::Context, configured: Option<&Path>) -> Option<egui::TextureHandle> {
+        // why wont it ucking Load
+        // fixed it
+        let possible_paths = configured.into_iter().chain(
+            ["src/img/p4.jpg", "img/p4.jpg", "./img/p4.jpg", "../img/p4.jpg", "p4.jpg"].map(Path::new),
+        );
+
+        for img_path in possible_paths {
+            let img_path_str = img_path.display();
+
+            if let Ok(img) = image::open(img_path) {
+                let img_buffer = img.to_rgba8();
+                let size = [img_buffer.width() as usize, img_buffer.height() as usize];
+                let pixels = img_buffer.as_flat_samples();
+
+                let color_image = egui::ColorImage::from_rgba_unmultiplied(
+                    size,
+                    pixels.as_slice(),
+                );
+
+                log::info!("Background image loaded from: {}", img_path_str);
+                return Some(ctx.load_texture(
+                    "background",
+                    color_image,
+                    egui::TextureOptions::LINEAR,
+                ));
+            }
+        }
+
+        log::warn!("Could not load background image.");
+        None
+    }
+
+    /// The seed typed into the dev window, or a new one from the shared RNG.
+    fn draw_seed(&mut self) -> Option<u64> {
+        let input = self.dev_window.seed.trim();
+        if input.is_empty() {
+            return Some(self.rng.gen_range(0..=i64::MAX as u64));
+        }
+        let seed = input.parse().ok();
+        if seed.is_none() {
+            self.toasts.error(tr("Invalid seed"));
+        }
+        seed
+    }
+
+    fn run_calculation(&mut self) {
+        let Some(targets) = self.dev_window.targets() else {
+            self.toasts.error(tr("Invalid max number!"));
+            return;
+        };
+        if let Some(seed) = self.draw_seed() {
+            self.commit_draw(&targets, self.dev_window.exclude_previous, self.dev_window.mode, seed, None);
+        }
+    }
+
+    /// With `revision` from a preview, nothing is written if another booth
+    /// changed the data in the meantime; the preview is redone instead.
+    fn commit_draw(&mut self, targets: &[i32], exclude_previous: bool, mode: WinnerMode, seed: u64, revision: Option<i64>) {
+        self.dev_window.preview = None;
+        if self.with_db(|db| db.winners_locked()).unwrap_or(false) {
+            self.toasts.error(tr("Winners are locked - unlock them first"));
+            return;
+        }
+        let result = self.with_db(|db| match revision {
+            Some(revision) => db.calculate_winners_if_unchanged(revision, targets, exclude_previous, mode, seed),
+            None => db.calculate_winners(targets, exclude_previous, mode, seed).map(|_| true),
+        });
+        match result {
+            Ok(true) => self.toasts.success(tr("Winners calculated successfully!")),
+            Ok(false) => {
+                self.toasts.error(tr("The data changed since the preview - check the new preview"));
+                self.preview_draw();
+            }
+            Err(e) => self.report_error(&e),
+        }
+    }
+
+    fn preview_draw(&mut self) {
+        let Some(targets) = self.dev_window.targets() else {
+            self.toasts.error(tr("Invalid max number!"));
+            return;
+        };
+        let Some(seed) = self.draw_seed() else {
+            return;
+        };
+        let (exclude_previous, mode) = (self.dev_window.exclude_previous, self.dev_window.mode);
+        let count = self.dev_window.winner_count();
+        let result = self.with_db(|db| {
+            let data_revision = db.data_revision().ok();
+            let previous = if exclude_previous { db.get_previous_winners()? } else { HashMap::new() };
+            let users: Vec<User> = db.get_all_users()?
+                .into_iter()
+                .filter(|u| !previous.contains_key(&u.id))
+                .collect();
+            let mut nearest = rank_by_distance(&users, targets[0], mode);
+            nearest.truncate(count);
+            db.registration_revision()
+                .and_then(|rev| Ok((rev, data_revision, db.preview_winners(&targets, exclude_previous, mode, seed)?, nearest)))
+        });
+        match result {
+            Ok((revision, data_revision, winners, nearest)) => {
+                self.dev_window.preview = Some(DrawPreview {
+                    targets, exclude_previous, mode, seed, revision, data_revision, winners, nearest,
+                });
+            }
+            Err(e) => self.toasts.error(tr!("Error: {}", e)),
+        }
+    }
+
+    /// Reloads the statistics once registrations or the bin width changed.
+    fn refresh_stats(&mut self) -> Result<(), AppError> {
+        let key = (self.with_db(|db| db.registration_revision())?, self.stats_window.bin_width());
+        if self.stats_window.cache_key != Some(key) {
+            self.stats_window.stats = self.with_db(|db| db.number_stats())?;
+            self.stats_window.bins = self.with_db(|db| db.number_histogram(key.1))?;
+            self.stats_window.cache_key = Some(key);
+        }
+        Ok(())
+    }
+
+    fn start_reveal(&mut self) {
+        let max_num = self.dev_window.targets().map(|t| t[0]).unwrap_or(300);
+        let data = self.with_db(|db| {
+            db.get_sorted_users(max_num).and_then(|users| Ok((users, db.get_prizes()?)))
+        });
+        match data {
+            Ok((users, prizes)) => {
+                let winners: Vec<User> = users.into_iter().filter(|u| u.winner).collect();
+                if winners.is_empty() {
+                    self.toasts.error(tr("No winners to reveal - calculate winners first"));
+                } else {
+                    self.reveal = Some(Reveal { winners, prizes, revealed: 0, timer: 0.0 });
+                }
+            }
+            Err(e) => self.toasts.error(tr!("Error: {}", e)),
+        }
+    }
+
+    fn update_reveal(&mut self, ctx: &egui::Context) {
+        let Some(reveal) = &mut self.reveal else {
+            return;
+        };
+        let (dt, skip, abort) = ctx.input(|i| (
+            i.stable_dt.min(0.1),
+            i.key_pressed(egui::Key::Space) || i.pointer.primary_clicked(),
+            i.key_pressed(egui::Key::Escape),
+        ));
+
+        if abort {
+            self.reveal = None;
+            return;
+        }
+        if reveal.finished() {
+            return;
+        }
+
+        reveal.timer += dt;
+        if skip || reveal.timer >= REVEAL_DELAY {
+            reveal.advance();
+        }
+    }
+
+    fn set_registration_open(&mut self, open: bool) {
+        let result = self.with_db(|db| {
+            db.set_registration_open(open).and_then(|_| {
+                // manually reopening cancels a pending schedule
+                if open { db.set_registration_close_at(None) } else { Ok(()) }
+            })
+        });
+        match result {
+            Ok(_) => {
+                self.registration_open = open;
+                if open {
+                    self.registration_close_at = None;
+                }
+                self.toasts.info(if open { tr("Registration opened") } else { tr("Registration closed") });
+            }
+            Err(e) => self.toasts.error(tr!("Error: {}", e)),
+        }
+    }
+
+    fn schedule_close(&mut self, close_at: Option<i64>) {
+        match self.with_db(|db| db.set_registration_close_at(close_at)) {
+            Ok(_) => self.registration_close_at = close_at,
+            Err(e) => self.toasts.error(tr!("Error: {}", e)),
+        }
+    }
+
+    /// Closes registration once the scheduled time or the countdown runs out.
+    fn check_registration_deadline(&mut self) {
+        if !self.registration_open {
+            return;
+        }
+        let scheduled = self.registration_close_at.is_some_and(|ts| unix_now() >= ts);
+        let countdown_done = self.dev_window.countdown_closes
+            && self.countdown.as_ref().is_some_and(|c| c.remaining() <= 0);
+        if scheduled || countdown_done {
+            self.set_registration_open(false);
+            if scheduled {
+                self.schedule_close(None);
+            }
+            // fire once, so reopening by hand afterwards sticks
+            self.dev_window.countdown_closes = false;
+        }
+    }
+
+    /// Cheap check for writes from outside, at most every `AUTO_REFRESH_INTERVAL`.
+    fn poll_changes(&mut self) {
+        if !self.dev_window.auto_refresh || self.last_poll.elapsed() < AUTO_REFRESH_INTERVAL {
+            return;
+        }
+        self.last_poll = Instant::now();
+        let revision = self.with_db(|db| db.data_revision()).ok();
+        if revision.is_some() && revision != self.seen_revision {
+            log::debug!("Data revision {:?} -> {:?}, reloading", self.seen_revision, revision);
+            self.seen_revision = revision;
+            self.reload_cached();
+        }
+    }
+
+    /// Throws away everything cached from the database, including the
+    /// open prize edit and duplicate search.
+    fn refresh_from_disk(&mut self) {
+        self.seen_revision = self.with_db(|db| db.data_revision()).ok();
+        self.reload_cached();
+        self.dev_window.prize_edit = None;
+        self.dev_window.duplicates = None;
+        self.toasts.info(tr("Reloaded from disk"));
+    }
+
+    fn reload_cached(&mut self) {
+        // each value falls back on its own, so the lookup itself can't fail
+        let Ok((open, close_at, interval, guesses, event_name)) = self.with_db(|db| {
+            Ok((db.registration_open(), db.registration_close_at(), db.submit_interval_secs(), db.guess_range(), db.event_name()))
+        }) else {
+            return;
+        };
+        self.event_name = event_name.unwrap_or(std::mem::take(&mut self.event_name));
+        if !self.event_name.is_empty() {
+            self.databases[self.active].name = self.event_name.clone();
+        }
+        if let Ok(guesses) = guesses {
+            self.dev_window.guess_range_input = guess_range_inputs(&guesses);
+            self.guess_range = guesses;
+        }
+        self.registration_open = open.unwrap_or(self.registration_open);
+        self.registration_close_at = close_at.unwrap_or(self.registration_close_at);
+        if let Ok(secs) = interval {
+            self.submit_limiter.interval = Duration::from_secs(secs);
+        }
+        self.number_counts = None;
+        self.stats_window.cache_key = None;
+        self.dev_window.db_stats = None;
+    }
+
+    /// Opens another event's database next to the open ones and returns
+    /// its index; a file that is already open is not opened twice.
+    fn add_event(&mut self, path: &Path) -> Option<usize> {
+        if let Some(index) = self.databases.iter().position(|event| event.path == path) {
+            return Some(index);
+        }
+        match Database::open(path) {
+            Ok(database) => {
+                log::info!("Opened event database {}", path.display());
+                let event = NamedDatabase::new(path, database);
+                lock_database(&event.store).set_actor(self.role.map(Role::as_str));
+                self.databases.push(event);
+                Some(self.databases.len() - 1)
+            }
+            Err(e) => {
+                log::error!("Could not open event database {}: {}", path.display(), e);
+                self.toasts.error(tr!("Could not open {}: {}", path.display(), e));
+                None
+            }
+        }
+    }
+
+    /// Makes `index` the event the form submits to and the staff windows
+    /// show. The typed form stays, anything half done in the old event goes.
+    fn switch_event(&mut self, index: usize) {
+        if index == self.active || index >= self.databases.len() {
+            return;
+        }
+        self.save_dev_settings(true);
+        self.save_draft(true);
+        self.active = index;
+        log::info!("Switched to event \"{}\"", self.databases[index].name);
+
+        let (settings, webhook_url) = self.with_db(|db| Ok((db.dev_settings().unwrap_or_default(), db.webhook_url().unwrap_or_default())))
+            .unwrap_or_default();
+        // the window layout belongs to the screen, not the event
+        self.dev_window.apply_settings(settings);
+        self.saved_settings = self.current_settings();
+        self.dev_window.webhook_url = webhook_url.clone();
+        self.webhook = start_webhook(&webhook_url);
+        self.saved_draft = Default::default();
+        self.offered_draft = None;
+
+        self.pending_registration = None;
+        self.reset_validation();
+        self.recent_inserts.clear();
+        self.recent_deletes.clear();
+        self.reveal = None;
+        self.dev_window.preview = None;
+        self.dev_window.selected_draw = None;
+        self.dev_window.prize_edit = None;
+        self.dev_window.duplicates = None;
+        self.table_window.pending_toggle = None;
+        self.table_window.pending_purge = None;
+        self.table_window.pending_edit = None;
+        self.seen_revision = self.with_db(|db| db.data_revision()).ok();
+        self.reload_cached();
+        if let Some(addr) = self.http_server.as_ref().map(|server| server.addr().to_string()) {
+            self.start_server(&addr);
+        }
+    }
+
+    /// Starts the info-screen API on `addr`, replacing a running one.
+    pub fn start_server(&mut self, addr: &str) {
+        self.http_server = None;
+        match HttpServer::start(addr, Arc::clone(&self.databases[self.active].store)) {
+            Ok(server) => {
+                self.toasts.success(tr!("API listening on http://{}", server.addr()));
+                self.http_server = Some(server);
+            }
+            Err(e) => self.toasts.error(tr!("Could not start the API on {}: {}", addr, e)),
+        }
+    }
+
+    /// Writes the dev window's target and winner count, and the window
+    /// size, back to the config file.
+    fn save_config(&mut self, ctx: &egui::Context) {
+        let mut config = self.config.clone();
+        if let Some(targets) = self.dev_window.targets() {
+            config.target_number = targets[0];
+        }
+        config.winner_count = self.dev_window.winner_count();
+        config.language = self.dev_window.lang.code().to_string();
+        if let Some(inner) = ctx.input(|i| i.viewport().inner_rect) {
+            config.window_width = inner.width();
+            config.window_height = inner.height();
+        }
+        match config.validate().and_then(|_| config.save(&self.config_path)) {
+            Ok(()) => {
+                self.toasts.success(tr!("Saved {}", self.config_path.display()));
+                self.config = config;
+            }
+            Err(e) => self.toasts.error(e),
+        }
+    }
+
+    /// Takes the `[theme]` colors from the config file, leaving the rest
+    /// of the running config alone.
+    fn reload_theme(&mut self) {
+        match AppConfig::load(&self.config_path) {
+            Ok(config) => {
+                self.config.theme = config.theme;
+                self.refresh_colors();
+                self.toasts.success(tr("Theme reloaded"));
+            }
+            Err(e) => self.toasts.error(e),
+        }
+    }
+
+    /// An admin password is set and no password has been entered.
+    fn locked(&self) -> bool {
+        self.config.admin_password_hash.is_some() && self.role.is_none()
+    }
+
+    /// Without an admin password there are no roles and everyone is admin.
+    fn current_role(&self) -> Option<Role> {
+        match self.config.admin_password_hash {
+            None => Some(Role::Admin),
+            Some(_) => self.role,
+        }
+    }
+
+    /// The one place role checks go through.
+    fn may(&self, capability: Capability) -> bool {
+        self.current_role().is_some_and(|role| role.allows(capability))
+    }
+
+    /// Also tells the database, so the audit log records who changed what.
+    fn set_role(&mut self, role: Option<Role>) {
+        if role != self.role {
+            log::info!("Staff windows {}", role.map_or("locked".to_string(), |r| format!("unlocked as {}", r.as_str())));
+        }
+        self.role = role;
+        let _ = self.with_db(|db| {
+            db.set_actor(role.map(Role::as_str));
+            Ok(())
+        });
+    }
+
+    /// Operators only get the table window, even from Ctrl+Shift+D.
+    fn open_guarded(&mut self, window: Guarded) {
+        if window == Guarded::Dev && !self.may(Capability::DevSettings) {
+            self.toasts.info(tr("Operators only have the registrations table"));
+            self.table_window.open = true;
+        } else if window == Guarded::Quit && !self.may(Capability::Quit) {
+            self.toasts.error(tr("Only an admin can close the kiosk"));
+        } else {
+            *self.guarded_open(window) = true;
+        }
+    }
+
+    /// Kiosk exit: the admin password, then the window closes.
+    fn request_quit(&mut self) {
+        if self.locked() {
+            self.password_prompt = Some(PasswordPrompt { window: Guarded::Quit, input: String::new(), keep_unlocked: false, checking: None });
+        } else {
+            self.open_guarded(Guarded::Quit);
+        }
+    }
+
+    fn guarded_open(&mut self, window: Guarded) -> &mut bool {
+        match window {
+            Guarded::Dev => &mut self.dev_window.open,
+            Guarded::Table => &mut self.table_window.open,
+            Guarded::Quit => &mut self.quit_allowed,
+        }
+    }
+
+    /// Shortcut handling for the dev and table windows: closing is always
+    /// allowed, opening asks for the password first while locked.
+    fn toggle_guarded(&mut self, window: Guarded) {
+        if *self.guarded_open(window) {
+            *self.guarded_open(window) = false;
+        } else if self.locked() {
+            self.password_prompt = Some(PasswordPrompt { window, input: String::new(), keep_unlocked: self.keep_unlocked, checking: None });
+        } else {
+            self.open_guarded(window);
+        }
+    }
+
+    /// Checks the typed password on a thread, hashing takes long enough to
+    /// freeze a frame; `finish_unlock` picks up the answer.
+    fn try_unlock(&mut self) {
+        let (Some(prompt), Some(admin)) = (&mut self.password_prompt, &self.config.admin_password_hash) else {
+            return;
+        };
+        if prompt.checking.is_some() || self.lockout.remaining(Instant::now()).is_some() {
+            return;
+        }
+        let input = std::mem::take(&mut prompt.input);
+        let (admin, operator) = (admin.clone(), self.config.operator_password_hash.clone());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let role = if verify_password(&input, &admin) {
+                Some(Role::Admin)
+            } else if operator.is_some_and(|operator| verify_password(&input, &operator)) {
+                Some(Role::Operator)
+            } else {
+                None
+            };
+            let _ = sender.send(role);
+        });
+        prompt.checking = Some(receiver);
+    }
+
+    /// Unlocks or counts a failure once the check from `try_unlock` is done.
+    fn finish_unlock(&mut self) {
+        let Some(checking) = self.password_prompt.as_ref().and_then(|p| p.checking.as_ref()) else {
+            return;
+        };
+        let role = match checking.try_recv() {
+            Ok(role) => role,
+            Err(mpsc::TryRecvError::Empty) => return,
+            // the thread died, which counts as a wrong password
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        };
+        if let Some(prompt) = &mut self.password_prompt {
+            prompt.checking = None;
+        }
+        let now = Instant::now();
+        self.lockout.record(role.is_some(), now);
+        if let Some(role) = role {
+            let prompt = self.password_prompt.take().unwrap();
+            self.set_role(Some(role));
+            self.keep_unlocked = prompt.keep_unlocked;
+            self.last_admin_input = Instant::now();
+            self.open_guarded(prompt.window);
+        } else if let Some(wait) = self.lockout.remaining(now) {
+            log::warn!("Too many wrong admin passwords, locked for {}s", wait.as_secs());
+            self.toasts.error(tr!("Too many wrong passwords, try again in {}s", wait.as_secs()));
+        } else {
+            log::warn!("Wrong admin password");
+            self.toasts.error(tr("Wrong password"));
+        }
+    }
+
+    /// Time until the idle lock, while it applies.
+    fn admin_idle_left(&self) -> Option<Duration> {
+        if self.role.is_none() || self.config.admin_password_hash.is_none() || self.config.admin_idle_secs == 0 {
+            return None;
+        }
+        Some(Duration::from_secs(self.config.admin_idle_secs).saturating_sub(self.last_admin_input.elapsed()))
+    }
+
+    /// Clicks and keys inside the dev and table windows keep them unlocked;
+    /// after `admin_idle_secs` without any they close and lock.
+    fn track_admin_input(&mut self, ctx: &egui::Context) {
+        let windows = [
+            self.dev_window.rect.filter(|_| self.dev_window.open),
+            self.table_window.rect.filter(|_| self.table_window.open),
+        ];
+        let (pressed_at, typed) = ctx.input(|i| (
+            i.pointer.any_pressed().then(|| i.pointer.interact_pos()).flatten(),
+            i.events.iter().any(|e| matches!(e, egui::Event::Key { pressed: true, .. } | egui::Event::Text(_))),
+        ));
+        if let Some(pos) = pressed_at {
+            self.admin_focused = windows.iter().flatten().any(|rect| rect.contains(pos));
+        }
+        if self.admin_focused && (pressed_at.is_some() || typed) {
+            self.last_admin_input = Instant::now();
+        }
+
+        if self.admin_idle_left().is_some_and(|left| left.is_zero()) {
+            log::info!("Admin windows locked after {}s without input", self.config.admin_idle_secs);
+            if self.dev_window.open || self.table_window.open {
+                self.toasts.info(tr("Admin windows locked after inactivity"));
+            }
+            self.dev_window.open = false;
+            self.table_window.open = false;
+            self.set_role(None);
+            self.keep_unlocked = false;
+            self.admin_focused = false;
+        }
+    }
+
+    fn password_dialog(&mut self, ctx: &egui::Context) {
+        self.finish_unlock();
+        let Some(prompt) = &mut self.password_prompt else {
+            return;
+        };
+        let checking = prompt.checking.is_some();
+        if checking {
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+        let title = match prompt.window {
+            Guarded::Dev => tr("Developer Settings"),
+            Guarded::Table => tr("Registrations Table"),
+            Guarded::Quit => tr("Close kiosk"),
+        };
+        let hint = if self.config.operator_password_hash.is_some() { tr("Admin or operator password") } else { tr("Admin password") };
+        let wait = self.lockout.remaining(Instant::now());
+        let (mut unlock, mut cancel) = (false, false);
+        egui::Window::new(tr!("{} - Password", title))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(wait.is_none() && !checking, |ui| {
+                    let field = ui.add(
+                        egui::TextEdit::singleline(&mut prompt.input).password(true).hint_text(hint),
+                    );
+                    if wait.is_none() && !checking && !field.has_focus() && ctx.memory(|m| m.focus().is_none()) {
+                        field.request_focus();
+                    }
+                    unlock = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    ui.checkbox(&mut prompt.keep_unlocked, tr("Keep unlocked until the app closes"));
+                });
+                if let Some(wait) = wait {
+                    ui.colored_label(
+                        egui::Color32::LIGHT_RED,
+                        tr!("Too many wrong passwords, try again in {}s", wait.as_secs() + 1),
+                    );
+                }
+                ui.horizontal(|ui| {
+                    unlock |= ui.add_enabled(wait.is_none() && !checking, egui::Button::new(tr("Unlock"))).clicked();
+                    if checking {
+                        ui.spinner();
+                    }
+                    cancel = ui.button(tr("Cancel")).clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape));
+                });
+            });
+        if cancel {
+            self.password_prompt = None;
+        } else if unlock {
+            self.try_unlock();
+        }
+    }
+
+    /// Dev window inputs plus which tool windows are open and where.
+    fn current_settings(&self) -> DevSettings {
+        let layout = |open, rect: Option<egui::Rect>| Some(ToolWindow { open, rect: rect.map(rect_geometry) });
+        DevSettings {
+            dev_window: layout(self.dev_window.open, self.dev_window.rect),
+            table_window: layout(self.table_window.open, self.table_window.rect),
+            ..self.dev_window.settings()
+        }
+    }
+
+    /// A password protected layout is restored closed.
+    fn apply_settings(&mut self, settings: DevSettings) {
+        let may_open = !self.locked();
+        if let Some(layout) = settings.dev_window {
+            self.dev_window.open = layout.open && may_open;
+            self.dev_window.rect = layout.rect.map(geometry_rect);
+        }
+        if let Some(layout) = settings.table_window {
+            self.table_window.open = layout.open && may_open;
+            self.table_window.rect = layout.rect.map(geometry_rect);
+        }
+        self.dev_window.apply_settings(settings);
+        self.snowflakes = snowfall(&self.dev_window.backdrop);
+        self.refresh_colors();
+    }
+
+    /// Starts over with the preset's snow and background and takes its colors.
+    fn apply_preset(&mut self, preset: Option<ThemePreset>) {
+        self.dev_window.preset = preset;
+        self.dev_window.backdrop = preset.map_or_else(Backdrop::default, ThemePreset::backdrop);
+        self.snowflakes = snowfall(&self.dev_window.backdrop);
+        self.refresh_colors();
+    }
+
+    /// The config's `[theme]` colors over the preset's.
+    fn refresh_colors(&mut self) {
+        let preset = self.dev_window.preset.map(ThemePreset::colors).unwrap_or_default();
+        self.colors = self.config.theme.colors().unwrap_or_default().or(preset);
+    }
+
+    /// Writes changed dev window inputs, at most every `SETTINGS_SAVE_INTERVAL`
+    /// unless `now` is set for a clean exit.
+    fn save_dev_settings(&mut self, now: bool) {
+        if !now && self.last_settings_save.elapsed() < SETTINGS_SAVE_INTERVAL {
+            return;
+        }
+        self.last_settings_save = Instant::now();
+        let settings = self.current_settings();
+        if settings == self.saved_settings {
+            return;
+        }
+        match self.with_db(|db| db.set_dev_settings(&settings)) {
+            Ok(()) => self.saved_settings = settings,
+            Err(e) => log::warn!("Could not save the developer settings: {}", e),
+        }
+    }
+
+    /// Types an on-screen key into the field focused last.
+    fn type_key(&mut self, key: KeyInput) {
+        let (text, max_len) = match self.key_target {
+            FormField::FirstName => (&mut self.first_name, MAX_NAME_LEN),
+            FormField::Surname => (&mut self.surname, MAX_NAME_LEN),
+            FormField::Email => (&mut self.email, MAX_EMAIL_LEN),
+            FormField::Number => (&mut self.number, i32::MAX.to_string().len()),
+        };
+        apply_key(text, key);
+        truncate_chars(text, max_len);
+        self.field_edited(self.key_target, Instant::now());
+    }
+
+    /// The field is checked again only once typing pauses.
+    fn field_edited(&mut self, field: FormField, now: Instant) {
+        self.checked_fields.retain(|f| *f != field);
+        self.save_errors.retain(|e| e.field != field);
+        self.last_edit = Some((field, now));
+        self.clear_armed = None;
+    }
+
+    /// Errors of the checked fields as they are now, plus `save_errors`.
+    fn update_form_errors(&mut self, now: Instant) {
+        if let Some((field, at)) = self.last_edit
+            && now.duration_since(at) >= VALIDATE_DELAY
+        {
+            self.last_edit = None;
+            self.check_field(field);
+        }
+        let fields = [&self.first_name, &self.surname, &self.email, &self.number].map(String::as_str);
+        let errors: Vec<FieldError> = validate_form(fields, &self.guess_range)
+            .into_iter()
+            .filter(|e| self.checked_fields.contains(&e.field))
+            .chain(self.save_errors.iter().cloned())
+            .collect();
+        self.form_errors = errors;
+    }
+
+    fn check_field(&mut self, field: FormField) {
+        if !self.checked_fields.contains(&field) {
+            self.checked_fields.push(field);
+        }
+    }
+
+    /// For a fresh participant, nothing is checked yet.
+    fn reset_validation(&mut self) {
+        self.form_errors.clear();
+        self.checked_fields.clear();
+        self.last_edit = None;
+        self.save_errors.clear();
+    }
+
+    fn clear_pending(&self, now: Instant) -> bool {
+        self.clear_armed.is_some_and(|at| now.duration_since(at) < CLEAR_CONFIRM_WINDOW)
+    }
+
+    /// Esc or "Clear": the first press only asks, a second one within
+    /// `CLEAR_CONFIRM_WINDOW` empties the form. True once it was cleared.
+    fn press_clear(&mut self, now: Instant) -> bool {
+        if !self.clear_pending(now) {
+            let typed = self.form_fields().iter().any(|f| !f.is_empty()) || !self.form_errors.is_empty();
+            self.clear_armed = typed.then_some(now);
+            return false;
+        }
+        self.clear_armed = None;
+        self.first_name.clear();
+        self.surname.clear();
+        self.email.clear();
+        self.number.clear();
+        self.reset_validation();
+        self.focus_request = Some(FormStop::Field(FormField::FirstName));
+        log::debug!("Form cleared by the participant");
+        true
+    }
+
+    fn form_fields(&self) -> [String; 4] {
+        [self.first_name.clone(), self.surname.clone(), self.email.clone(), self.number.clone()]
+    }
+
+    /// Autosaves the form when it changed, at most every `DRAFT_SAVE_INTERVAL`
+    /// unless `now` is set.
+    fn save_draft(&mut self, now: bool) {
+        if !now && self.last_draft_save.elapsed() < DRAFT_SAVE_INTERVAL {
+            return;
+        }
+        self.last_draft_save = Instant::now();
+        let fields = self.form_fields();
+        // an empty form must not wipe the draft still waiting to be restored
+        let keep_offered = self.offered_draft.is_some() && fields.iter().all(|f| f.trim().is_empty());
+        if fields == self.saved_draft || keep_offered {
+            return;
+        }
+        match self.with_db(|db| db.set_form_draft(&fields)) {
+            Ok(()) => self.saved_draft = fields,
+            Err(e) => log::warn!("Could not save the form draft: {}", e),
+        }
+    }
+
+    /// Pulls the window back onto the monitor once its size is known, e.g.
+    /// when it was saved on a second screen that is gone now.
+    fn keep_window_on_screen(&mut self, ctx: &egui::Context) {
+        let (monitor, outer, inner) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (viewport.monitor_size, viewport.outer_rect, viewport.inner_rect)
+        });
+        // the first frames may not know them yet
+        let (Some(monitor), Some(outer), Some(inner)) = (monitor, outer, inner) else {
+            return;
+        };
+        self.window_on_screen = true;
+        let clamped = clamp_to_screen(outer, egui::Rect::from_min_size(egui::Pos2::ZERO, monitor));
+        if clamped.size() != outer.size() {
+            let frame = outer.size() - inner.size();
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(clamped.size() - frame));
+        }
+        if clamped.min != outer.min {
+            log::info!("Window was off screen at {:?}, moved to {:?}", outer.min, clamped.min);
+            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(clamped.min));
+        }
+    }
+
+    /// Remembers where the window is, so the next start puts it back there.
+    fn save_window_geometry(&self, ctx: &egui::Context) {
+        let (outer, inner, stretched) = ctx.input(|i| {
+            let viewport = i.viewport();
+            let stretched = viewport.maximized == Some(true) || viewport.fullscreen == Some(true);
+            (viewport.outer_rect, viewport.inner_rect, stretched)
+        });
+        // a maximized window would come back at that size, but not maximized
+        let (Some(outer), Some(inner), false) = (outer, inner, stretched) else {
+            return;
+        };
+        let geometry = WindowGeometry { x: outer.min.x, y: outer.min.y, width: inner.width(), height: inner.height() };
+        if let Err(e) = self.with_db(|db| db.set_window_geometry(geometry)) {
+            log::warn!("Could not save the window position: {}", e);
+        }
+    }
+
+    /// Saves the webhook URL and restarts the sender; "" turns it off.
+    fn set_webhook(&mut self, url: &str) {
+        let webhook = if url.is_empty() {
+            None
+        } else {
+            match Webhook::start(url) {
+                Ok(webhook) => Some(webhook),
+                Err(e) => {
+                    self.toasts.error(e);
+                    return;
+                }
+            }
+        };
+        match self.with_db(|db| db.set_webhook_url(url)) {
+            Ok(()) => {
+                self.toasts.success(if webhook.is_some() { tr("Webhook saved") } else { tr("Webhook removed") });
+                self.webhook = webhook;
+            }
+            Err(e) => self.report_error(&e),
+        }
+    }
+
+    fn save_guess_range(&mut self) {
+        let (min, max) = &self.dev_window.guess_range_input;
+        let min = min.trim().parse::<i32>();
+        let max = if max.trim().is_empty() { Ok(i32::MAX) } else { max.trim().parse::<i32>() };
+        let (Ok(min), Ok(max)) = (min, max) else {
+            self.toasts.error(tr("Min and max guess must be whole numbers"));
+            return;
+        };
+        if min < 1 || min > max {
+            self.toasts.error(tr("Min guess must be at least 1 and not above max"));
+            return;
+        }
+        match self.with_db(|db| db.set_guess_range(min, max)) {
+            Ok(()) => {
+                self.guess_range = min..=max;
+                self.toasts.success(tr!("Guesses from {}", guess_range_label(&self.guess_range)));
+            }
+            Err(e) => self.report_error(&e),
+        }
+    }
+
+    /// Reads an edited export back in; see `UserStore::upsert_users`.
+    fn import_corrections(&mut self, path: &Path) {
+        let mut rows = match import_from_xlsx(path) {
+            Ok(rows) => rows,
+            Err(e) => {
+                self.report_error(&e);
+                return;
+            }
+        };
+        normalize_imported(&mut rows, self.config.title_case_names);
+        match self.with_db(|db| db.upsert_users(&rows)) {
+            Ok(report) => {
+                let summary = tr!(
+                    "Imported {}: {} updated, {} added, {} skipped",
+                    path.display(), report.updated(), report.inserted(), report.rejected()
+                );
+                log::info!("{}", summary);
+                // the first few problems by sheet row, the log has them all
+                let problems: Vec<String> = rows.iter().zip(&report.rows)
+                    .filter_map(|(row, outcome)| match outcome {
+                        RowOutcome::Skipped(why) | RowOutcome::Failed(why) => Some(tr!("row {}: {}", row.row, why)),
+                        _ => None,
+                    })
+                    .collect();
+                for problem in &problems {
+                    log::warn!("Import {}", problem);
+                }
+                if problems.is_empty() {
+                    self.toasts.success(summary);
+                } else {
+                    let shown: Vec<&str> = problems.iter().take(3).map(String::as_str).collect();
+                    self.toasts.error(format!("{} ({})", summary, shown.join("; ")));
+                }
+                self.reload_cached();
+            }
+            Err(e) => self.report_error(&e),
+        }
+    }
+
+    /// Adds the rows of a CSV as new registrations on a worker thread;
+    /// `poll_import` shows the progress and the result.
+    fn start_csv_import(&mut self, path: &Path) {
+        if self.import_job.is_some() {
+            return;
+        }
+        let mut rows = match import_from_csv(path) {
+            Ok(rows) => rows,
+            Err(e) => {
+                self.report_error(&e);
+                return;
+            }
+        };
+        normalize_imported(&mut rows, self.config.title_case_names);
+        let (mut users, mut lines, mut invalid) = (Vec::new(), Vec::new(), Vec::new());
+        for row in rows {
+            match row.number.parse::<i32>() {
+                Ok(number) => {
+                    users.push(NewUser {
+                        first_name: row.first_name,
+                        surname: row.surname,
+                        email: row.email,
+                        number,
+                        is_test: false,
+                    });
+                    lines.push(row.row);
+                }
+                Err(_) => invalid.push(tr!("row {}: Number \"{}\" is not a whole number", row.row, row.number)),
+            }
+        }
+        log::info!("Importing {} rows from {}", users.len() + invalid.len(), path.display());
+
+        // a second connection, the UI's stays free for the booth
+        let db_path = self.databases[self.active].path.clone();
+        let actor = self.role.map(Role::as_str);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::clone(&cancel);
+        std::thread::spawn(move || {
+            let result = Database::open(&db_path).and_then(|mut db| {
+                db.set_actor(actor);
+                db.insert_users_batch_with(&users, &mut |done| {
+                    if done.is_multiple_of(IMPORT_PROGRESS_STEP) {
+                        let _ = tx.send(ImportUpdate::Progress(done));
+                    }
+                    !stop.load(Ordering::Relaxed)
+                })
+            });
+            let _ = tx.send(ImportUpdate::Finished(result.map_err(|e| db_error_message(&e))));
+        });
+        self.import_job = Some(ImportJob { path: path.to_path_buf(), lines, invalid, done: 0, cancel, updates: rx });
+    }
+
+    fn poll_import(&mut self) {
+        let Some(job) = &mut self.import_job else {
+            return;
+        };
+        let finished = loop {
+            match job.updates.try_recv() {
+                Ok(ImportUpdate::Progress(done)) => job.done = done,
+                Ok(ImportUpdate::Finished(result)) => break result,
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => break Err(tr("Import thread stopped unexpectedly").to_string()),
+            }
+        };
+        let job = self.import_job.take().unwrap();
+        let report = match finished {
+            Ok(Some(report)) => report,
+            Ok(None) => {
+                self.toasts.info(tr("Import cancelled, nothing was added"));
+                return;
+            }
+            Err(e) => {
+                log::error!("Import of {} failed: {}", job.path.display(), e);
+                self.toasts.error(tr!("Import failed: {}", e));
+                return;
+            }
+        };
+
+        let total = job.lines.len() + job.invalid.len();
+        let mut problems = job.invalid;
+        problems.extend(job.lines.iter().zip(&report.rows).filter_map(|(line, outcome)| match outcome {
+            RowOutcome::Skipped(why) | RowOutcome::Failed(why) => Some(tr!("row {}: {}", line, why)),
+            _ => None,
+        }));
+        let summary = tr!(
+            "Imported {}: {} of {} rows added, {} skipped or invalid",
+            job.path.display(), report.inserted(), total, problems.len()
+        );
+        log::info!("{}", summary);
+        for problem in &problems {
+            log::warn!("Import {}", problem);
+        }
+        if problems.is_empty() {
+            self.toasts.success(summary);
+        } else {
+            let shown: Vec<&str> = problems.iter().take(3).map(String::as_str).collect();
+            self.toasts.error(format!("{} ({})", summary, shown.join("; ")));
+        }
+        self.seen_revision = self.with_db(|db| db.data_revision()).ok();
+        self.reload_cached();
+    }
+
+    fn generate_test_data(&mut self) {
+        let Ok(count) = self.dev_window.test_user_count.trim().parse::<usize>() else {
+            self.toasts.error(tr("Invalid number of test users"));
+            return;
+        };
+        let (low, high) = &self.dev_window.test_range;
+        let (Ok(low), Ok(high)) = (low.trim().parse::<i32>(), high.trim().parse::<i32>()) else {
+            self.toasts.error(tr("Invalid number range"));
+            return;
+        };
+        if low < 1 || low > high {
+            self.toasts.error(tr("Range must satisfy 1 <= low <= high"));
+            return;
+        }
+        let distribution = self.dev_window.test_distribution;
+        let result = self.with_db(|db| db.registration_revision()).and_then(|(_, max_id)| {
+            let users = generate_test_users(&mut self.rng, count, max_id + 1, distribution, low..=high);
+            self.with_db(|db| db.insert_users_batch(&users))
+        });
+        match result {
+            Ok(report) if report.rejected() > 0 => self.toasts.error(tr!(
+                "Generated {} test users, {} rows were rejected",
+                report.inserted(),
+                report.rejected(),
+            )),
+            Ok(report) => {
+                log::info!("Generated {} test users", report.inserted());
+                self.toasts.success(tr!("Generated {} test users", report.inserted()));
+            }
+            Err(e) => self.toasts.error(tr!("Error: {}", e)),
+        }
+    }
+
+    /// Wipes everything after taking a backup, so a mistake can be undone.
+    fn clear_all_data(&mut self) {
+        let backup = match self.backup_database() {
+            Ok(msg) => msg,
+            Err(e) => {
+                self.toasts.error(tr!("{} - nothing was deleted", e));
+                return;
+            }
+        };
+        match self.with_db(|db| db.clear_all()) {
+            Ok(n) => {
+                self.dev_window.preview = None;
+                self.dev_window.selected_draw = None;
+                self.toasts.success(tr!("Deleted {} registrations. {}", n, backup));
+            }
+            Err(e) => self.toasts.error(tr!("Error: {}", e)),
+        }
+    }
+
+    /// Backs up (if asked), wipes the registrations and draw state and names
+    /// the new event, then says what happened.
+    fn start_new_event(&mut self) {
+        let name = self.dev_window.new_event_name.trim().to_string();
+        let mut summary = Vec::new();
+        if self.dev_window.new_event_backup {
+            match self.backup_database() {
+                Ok(msg) => summary.push(msg),
+                Err(e) => {
+                    self.toasts.error(tr!("{} - nothing was changed", e));
+                    return;
+                }
+            }
+        }
+        match self.with_db(|db| db.start_new_event(&name)) {
+            Ok(removed) => {
+                summary.push(tr!("{} registrations and all draws removed", removed));
+                summary.push(tr("winners unlocked, registration open").to_string());
+                let summary = tr!("New event \"{}\": {}", name, summary.join(", "));
+                log::info!("{}", summary);
+                self.toasts.success(summary);
+            }
+            Err(e) => {
+                self.toasts.error(tr!("Error: {}", e));
+                return;
+            }
+        }
+        self.dev_window.preview = None;
+        self.dev_window.selected_draw = None;
+        self.dev_window.new_event_name.clear();
+        self.recent_inserts.clear();
+        self.recent_deletes.clear();
+        self.seen_revision = self.with_db(|db| db.data_revision()).ok();
+        self.reload_cached();
+    }
+
+    /// The newest registration from this booth, if it may still be undone.
+    fn undo_candidate(&self) -> Option<i32> {
+        let id = *self.recent_inserts.last()?;
+        self.with_db(|db| db.undoable(id)).unwrap_or(false).then_some(id)
+    }
+
+    /// Removes the last registration again and puts its values back into
+    /// the form for correction.
+    fn undo_last_registration(&mut self) {
+        let Some(id) = self.undo_candidate() else {
+            return;
+        };
+        self.recent_inserts.pop();
+        match self.with_db(|db| db.undo_registration(id)) {
+            Ok(Some(user)) => {
+                self.first_name = user.first_name;
+                self.surname = user.surname;
+                self.email = user.email;
+                self.number = user.number.to_string();
+                self.reset_validation();
+                self.toasts.info(tr("Last registration undone"));
+            }
+            Ok(None) => self.toasts.error(tr("That registration was changed meanwhile")),
+            Err(e) => self.toasts.error(tr!("Error: {}", e)),
+        }
+    }
+
+    fn delete_registration(&mut self, user: &User) {
+        match self.with_db(|db| db.delete_user(user.id)) {
+            Ok(_) => {
+                self.recent_deletes.push(user.clone());
+                if self.recent_deletes.len() > DELETE_UNDO_DEPTH {
+                    self.recent_deletes.remove(0);
+                }
+                self.toasts.info(tr!("{} {} moved to the recycle bin", user.first_name, user.surname));
+            }
+            Err(e) => self.toasts.error(tr!("Error: {}", e)),
+        }
+    }
+
+    fn undo_last_delete(&mut self) {
+        let Some(user) = self.recent_deletes.pop() else {
+            return;
+        };
+        match self.with_db(|db| db.undelete_user(&user)) {
+            Ok(true) => self.toasts.success(tr!("{} {} is back", user.first_name, user.surname)),
+            Ok(false) => self.toasts.error(tr!("{} is already used by a live registration", user.email)),
+            Err(e) => {
+                self.toasts.error(tr!("Error: {}", e));
+                self.recent_deletes.push(user);
+            }
+        }
+    }
+
+    /// How many live registrations already guessed `number`. The map is
+    /// rebuilt only when registrations were added or removed.
+    fn number_count(&mut self, number: i32) -> usize {
+        let revision = self.with_db(|db| db.registration_revision()).unwrap_or_default();
+        if self.number_counts.as_ref().map(|(key, _)| *key) != Some(revision) {
+            let counts = self.with_db(|db| db.get_all_users())
+                .map(|users| count_numbers(&users))
+                .unwrap_or_default();
+            self.number_counts = Some((revision, counts));
+        }
+        self.number_counts.as_ref()
+            .and_then(|(_, counts)| counts.get(&number).copied())
+            .unwrap_or(0)
+    }
+
+    /// Hands out focus requested through `focus_request` and notes which
+    /// form widget has it, so Tab can move on from there.
+    fn track_focus(&mut self, response: &egui::Response, stop: FormStop) {
+        if self.focus_request == Some(stop) {
+            self.focus_request = None;
+            response.request_focus();
+        }
+        if response.has_focus() {
+            self.focused_stop = Some(stop);
+            if let FormStop::Field(field) = stop {
+                self.key_target = field;
+            }
+            // buttons let egui move focus on Tab unless told otherwise
+            let filter = egui::EventFilter { tab: true, ..Default::default() };
+            response.ctx.memory_mut(|m| m.set_focus_lock_filter(response.id, filter));
+        }
+    }
+
+    /// A field is checked once it is left; with an error it gets a red frame.
+    fn outline_field(&mut self, ui: &egui::Ui, response: &egui::Response, field: FormField) {
+        if response.lost_focus() {
+            self.check_field(field);
+        }
+        if self.form_errors.iter().any(|e| e.field == field) {
+            let rounding = ui.visuals().widgets.inactive.rounding;
+            ui.painter().rect_stroke(response.rect.expand(1.0), rounding, egui::Stroke::new(1.5, egui::Color32::RED));
+        }
+    }
+
+    /// Stores the registration the participant just confirmed.
+    fn submit_registration(&mut self, (first_name, surname, email, number): (String, String, String, i32)) {
+        match self.with_db(|db| db.insert_user(&first_name, &surname, &email, number)) {
+            Ok((id, ticket)) => {
+                self.recent_inserts.push(id);
+                if self.recent_inserts.len() > UNDO_DEPTH {
+                    self.recent_inserts.remove(0);
+                }
+                // no names or emails in the log
+                log::info!("Registered id {} as entry #{}", id, ticket);
+                self.toasts.success(tr!("Registration successful! You are entry #{}", ticket));
+                self.celebration = Some(Celebration::new(&mut rand::thread_rng()));
+                if let Some(webhook) = &self.webhook {
+                    let total = self.with_db(|db| db.registration_revision()).map(|(count, _)| count).unwrap_or(0);
+                    webhook.send(serde_json::json!({ "first_name": first_name, "number": number, "total": total }));
+                }
+                self.first_name.clear();
+                self.surname.clear();
+                self.email.clear();
+                self.number.clear();
+                // a stored draft of this entry must not be offered again
+                self.save_draft(true);
+                self.reset_validation();
+                self.focus_request = Some(FormStop::Field(FormField::FirstName));
+            }
+            Err(e) if e.is_constraint_violation() => {
+                self.save_errors.push(FieldError {
+                    field: FormField::Email,
+                    message: tr("This email is already registered").to_string(),
+                });
+            }
+            Err(e) => self.report_error(&e),
+        }
+    }
+
+    fn backup_database(&self) -> Result<String, String> {
+        let filename = format!("backup_{}.db", unix_now());
+        self.with_db(|db| db.backup_to(Path::new(&filename)))
+            .map_err(|e| tr!("Backup failed: {}", e))?;
+        Ok(tr!("Database saved to {}", filename))
+    }
+
+    fn restore_database(&self, path: &Path) -> Result<String, String> {
+        Database::check_backup_schema(path)?;
+        self.with_db(|db| db.restore_from(path))
+            .map_err(|e| tr!("Restore failed: {}", e))?;
+        Ok(tr!("Restored from {}", path.display()))
+    }
+
+    /// Kicks off the Excel export on a worker thread; the result arrives
+    /// through `export_job` and is picked up in `poll_export`.
+    fn start_export(&mut self)
+    {
+        if self.export_job.is_some()
+        {
+            return;
+        }
+
+        let data = self.with_db(|db| {
+            db.get_all_users().and_then(|users| Ok((users, db.get_prizes()?)))
+        });
+        let (users, prizes) = match data
+        {
+            Ok(data) => data,
+            Err(e) => {
+                self.toasts.error(tr!("Database error: {}", e));
+                return;
+            }
+        };
+
+        self.export_users(users, prizes, "registrations");
+    }
+
+    /// Exports exactly `users`, e.g. the table's filtered view, as
+    /// `<event>_<prefix>_<timestamp>.xlsx`.
+    fn export_users(&mut self, users: Vec<User>, prizes: Vec<Prize>, prefix: &'static str)
+    {
+        if self.export_job.is_some()
+        {
+            return;
+        }
+        if users.is_empty()
+        {
+            self.toasts.error(tr("No data to export!"));
+            return;
+        }
+        let columns = enabled_columns(&self.dev_window.export_columns);
+        if columns.is_empty() {
+            self.toasts.error(tr("No columns selected for the export"));
+            return;
+        }
+
+        let users = if self.dev_window.anonymize_export {
+            if self.dev_window.export_salt.is_empty() {
+                self.dev_window.export_salt = new_salt();
+            }
+            let salt = &self.dev_window.export_salt;
+            users.iter().map(|u| {
+                let mut u = anonymize_user(u, salt);
+                if self.dev_window.export_initials {
+                    (u.first_name, u.surname) = (initials(&u.first_name), initials(&u.surname));
+                }
+                u
+            }).collect()
+        } else {
+            users
+        };
+
+        let folder = self.config.export_folder.clone().unwrap_or_default();
+        // side by side events need telling apart even before they are named
+        let event = if self.databases.len() > 1 { &self.databases[self.active].name } else { &self.event_name };
+        let prefix = file_prefix(event, prefix);
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let filename = unique_path(&folder.join(format!("{}_{}.xlsx", prefix, unix_now())));
+            // receiver may be gone if the app closed meanwhile
+            let _ = tx.send(write_excel(&users, &prizes, &columns, &filename.to_string_lossy()));
+        });
+        self.export_job = Some(rx);
+    }
+
+    fn exporting(&self) -> bool
+    {
+        self.export_job.is_some()
+    }
+
+    fn poll_export(&mut self)
+    {
+        let Some(rx) = &self.export_job else {
+            return;
+        };
+        match rx.try_recv()
+        {
+            Ok(Ok(msg)) => {
+                log::info!("{}", msg);
+                self.toasts.success(msg);
+            }
+            Ok(Err(e)) => self.toasts.error(tr!("Error: {}", e)),
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => self.toasts.error(tr("Export thread stopped unexpectedly")),
+        }
+        self.export_job = None;
+    }
+}
+
+impl eframe::App for MyApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        set_lang(self.dev_window.lang);
+        let dark = self.dev_window.theme.is_dark(frame.info().system_theme);
+        let visuals = themed_visuals(dark, &self.colors);
+        if ctx.style().visuals != visuals {
+            ctx.set_visuals(visuals);
+        }
+        let mut closing = ctx.input(|i| i.viewport().close_requested());
+        // Alt+F4 and friends do nothing in kiosk mode
+        if closing && self.config.kiosk && !self.quit_allowed {
+            log::info!("Close request ignored in kiosk mode");
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            closing = false;
+        }
+        if closing {
+            self.save_window_geometry(ctx);
+        }
+        if !self.window_on_screen && !self.config.kiosk {
+            self.keep_window_on_screen(ctx);
+        }
+        self.save_dev_settings(closing);
+        self.save_draft(closing);
+
+        if take_poison_notice() {
+            self.error_banner = Some(tr("An operation crashed while using the database - check the last change").to_string());
+        }
+        if let Some(message) = &self.error_banner {
+            let mut dismissed = false;
+            egui::TopBottomPanel::top("error_banner").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::RED, message);
+                    dismissed = ui.button(tr("Dismiss")).clicked();
+                });
+            });
+            if dismissed {
+                self.error_banner = None;
+            }
+        }
+
+        if self.databases.len() > 1 {
+            egui::TopBottomPanel::top("events").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Event:"));
+                    let mut selected = self.active;
+                    for (index, event) in self.databases.iter().enumerate() {
+                        ui.selectable_value(&mut selected, index, &event.name);
+                    }
+                    self.switch_event(selected);
+                });
+            });
+        }
+
+        if let Some(report) = self.crash_report.clone() {
+            egui::TopBottomPanel::top("crash_banner").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::LIGHT_RED, tr("The app crashed last time, a report was saved."));
+                    if ui.button(tr("Open report")).clicked()
+                        && let Err(e) = open_path(&report)
+                    {
+                        self.toasts.error(tr!("Could not open {}: {}", report.display(), e));
+                    }
+                    if ui.button(tr("Dismiss")).clicked() {
+                        if let Err(e) = crash::mark_seen(&report) {
+                            log::warn!("Could not mark {} as seen: {}", report.display(), e);
+                        }
+                        self.crash_report = None;
+                    }
+                });
+            });
+        }
+
+        // Update snowflakes
+        let backdrop = self.dev_window.backdrop;
+        // the count may have been changed in the dev window
+        self.snowflakes.truncate(backdrop.count);
+        while self.snowflakes.len() < backdrop.count {
+            self.snowflakes.push(Snowflake::spawn(&mut rand::thread_rng(), &backdrop, -0.1));
+        }
+        // down movment
+        for flake in &mut self.snowflakes {
+            flake.y += flake.speed;
+            flake.rotation += flake.spin;
+            if flake.y > 1.1 {
+                *flake = Snowflake::spawn(&mut rand::thread_rng(), &backdrop, -0.1);
+            }
+        }
+        // side Movment
+        for flake in &mut self.snowflakes
+        {
+            flake.x -= flake.side_toside_speed;
+            if flake.y > 1.1
+            {
+                *flake = Snowflake::spawn(&mut rand::thread_rng(), &backdrop, -0.1);
+            }
+        }
+        // Schnee bleibt unten liegen und taut langsam wieder weg
+        let dt = ctx.input(|i| i.stable_dt).min(0.1);
+        for height in &mut self.snow_pile {
+            *height = (*height - SNOW_MELT_RATE * dt).max(0.0);
+        }
+        for flake in &mut self.snowflakes {
+            if !(0.0..1.0).contains(&flake.x) {
+                continue;
+            }
+            let bucket = (flake.x * SNOW_BUCKETS as f32) as usize;
+            if flake.y >= 1.0 - self.snow_pile[bucket] {
+                pile_snow(&mut self.snow_pile, bucket, flake.size);
+                *flake = Snowflake::spawn(&mut rand::thread_rng(), &backdrop, -0.1);
+            }
+        }
+        // neu erzeugte Flocken haben eine neue Tiefe, nahe werden zuletzt gemalt
+        self.snowflakes.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+
+        if let Some(celebration) = &mut self.celebration {
+            celebration.tick(dt);
+        }
+        // the next participant starting to type ends it early
+        let typing = [&self.first_name, &self.surname, &self.email, &self.number].iter().any(|f| !f.is_empty());
+        if typing || self.celebration.as_ref().is_some_and(Celebration::done) {
+            self.celebration = None;
+        }
+
+        ctx.request_repaint();
+
+        self.update_reveal(ctx);
+        self.poll_export();
+        self.poll_import();
+        if let Some(countdown) = &mut self.countdown {
+            countdown.tick();
+        }
+        self.check_registration_deadline();
+        self.poll_changes();
+        if let Some(server) = &self.http_server {
+            server.set_target(self.dev_window.targets().map(|t| t[0]));
+        }
+
+        // Dev window toggle mit Ctrl+Shift+D, mit Admin-Passwort erst nach der Abfrage
+        if ctx.input(|i| i.key_pressed(egui::Key::D) && i.modifiers.ctrl && i.modifiers.shift) {
+            self.toggle_guarded(Guarded::Dev);
+        }
+
+        // Table window toggle mit Ctrl+Windows+L
+        if ctx.input(|i| i.key_pressed(egui::Key::L) && i.modifiers.ctrl && i.modifiers.command) {
+            self.toggle_guarded(Guarded::Table);
+        }
+        if self.config.kiosk {
+            // the hidden way out
+            if ctx.input(|i| i.key_pressed(egui::Key::Q) && i.modifiers.ctrl && i.modifiers.shift && i.modifiers.alt) {
+                self.request_quit();
+            }
+            if ctx.input(|i| i.pointer.is_moving() || i.pointer.any_down()) {
+                self.last_pointer_move = Instant::now();
+            } else if self.last_pointer_move.elapsed() > KIOSK_CURSOR_HIDE {
+                ctx.set_cursor_icon(egui::CursorIcon::None);
+            }
+        }
+        self.password_dialog(ctx);
+        if self.quit_allowed {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
+        // Ctrl+Z nimmt die letzte Anmeldung zurück, solange kein Textfeld aktiv ist
+        if ctx.input(|i| i.key_pressed(egui::Key::Z) && i.modifiers.command && !i.modifiers.shift)
+            && ctx.memory(|m| m.focus().is_none())
+        {
+            self.undo_last_registration();
+        }
+
+        // Statistik mit Ctrl+Shift+S
+        if ctx.input(|i| i.key_pressed(egui::Key::S) && i.modifiers.ctrl && i.modifiers.shift) {
+            self.stats_window.open = !self.stats_window.open;
+        }
+
+        // Schnell-Export (Standard Ctrl+E), nicht während man in ein Textfeld tippt
+        if let Some(shortcut) = parse_shortcut(&self.config.export_shortcut)
+            && self.may(Capability::Export)
+            && ctx.memory(|m| m.focus().is_none())
+            && ctx.input_mut(|i| i.consume_shortcut(&shortcut))
+        {
+            if self.exporting() {
+                self.toasts.info(tr("An export is already running"));
+            } else {
+                self.start_export();
+            }
+        }
+
+        // Developer window
+        if self.dev_window.open && self.reveal.is_none() && self.may(Capability::DevSettings) {
+            let mut dev_open = self.dev_window.open;
+            let mut window = egui::Window::new(tr("Developer Settings")).id(egui::Id::new("dev_window")).open(&mut dev_open).default_width(400.0);
+            if let Some(rect) = self.dev_window.rect {
+                window = window.default_rect(clamp_to_screen(rect, ctx.screen_rect()));
+            }
+            let shown = window.show(ctx, |ui| {
+                    if let Some(left) = self.admin_idle_left() {
+                        ui.small(tr!("Locks in {} without input", format!("{}:{:02}", left.as_secs() / 60, left.as_secs() % 60)));
+                    }
+                    if self.with_db(|db| Ok(db.is_in_memory())).unwrap_or(false) {
+                        ui.colored_label(egui::Color32::RED, tr("Storage: IN-MEMORY (data will be lost!)"));
+                    } else {
+                        let path = &self.databases[self.active].path;
+                        let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+                        ui.label(tr!("Storage: {}", name)).on_hover_text(path.display().to_string());
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Language:"));
+                        egui::ComboBox::from_id_source("language")
+                            .selected_text(self.dev_window.lang.name())
+                            .show_ui(ui, |ui| {
+                                for lang in Lang::ALL {
+                                    ui.selectable_value(&mut self.dev_window.lang, lang, lang.name());
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Theme:"));
+                        egui::ComboBox::from_id_source("theme")
+                            .selected_text(self.dev_window.theme.label())
+                            .show_ui(ui, |ui| {
+                                for theme in Theme::ALL {
+                                    ui.selectable_value(&mut self.dev_window.theme, theme, theme.label());
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Preset:"));
+                        let mut preset = self.dev_window.preset;
+                        egui::ComboBox::from_id_source("theme_preset")
+                            .selected_text(preset.map_or(tr("Standard"), ThemePreset::label))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut preset, None, tr("Standard"));
+                                for p in ThemePreset::ALL {
+                                    ui.selectable_value(&mut preset, Some(p), p.label());
+                                }
+                            });
+                        if preset != self.dev_window.preset {
+                            self.apply_preset(preset);
+                        }
+                    });
+                    egui::CollapsingHeader::new(tr("Snow and background")).id_source("backdrop").show(ui, |ui| {
+                        let backdrop = &mut self.dev_window.backdrop;
+                        ui.horizontal(|ui| {
+                            ui.label(tr("Flakes:"));
+                            ui.add(egui::DragValue::new(&mut backdrop.count).clamp_range(0..=5000));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(tr("Speed (far, near):"));
+                            for speed in [&mut backdrop.speed.0, &mut backdrop.speed.1] {
+                                ui.add(egui::DragValue::new(speed).speed(0.0001).clamp_range(0.0..=0.02).fixed_decimals(4));
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(tr("Size (far, near):"));
+                            for size in [&mut backdrop.size.0, &mut backdrop.size.1] {
+                                ui.add(egui::DragValue::new(size).speed(0.1).clamp_range(0.5..=40.0));
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(tr("Background:"));
+                            ui.color_edit_button_srgba(&mut backdrop.background);
+                            ui.checkbox(&mut backdrop.flat, tr("instead of the picture"));
+                        });
+                    });
+                    ui.label(tr("Max Number (Zielzahl), comma separated for one prize each:"));
+                    ui.text_edit_singleline(&mut self.dev_window.max_number);
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Winners:"));
+                        ui.add(egui::TextEdit::singleline(&mut self.dev_window.winner_count).desired_width(40.0));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.dev_window.exclude_previous, tr("Exclude previous winners"));
+                        if ui.button(tr("Reset winner history")).clicked() {
+                            match self.with_db(|db| db.reset_winner_history()) {
+                                Ok(()) => self.toasts.success(tr("Earlier winners can win again")),
+                                Err(e) => self.report_error(&e),
+                            }
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Mode:"));
+                        ui.radio_value(&mut self.dev_window.mode, WinnerMode::Closest, tr("Closest"));
+                        ui.radio_value(&mut self.dev_window.mode, WinnerMode::WeightedRandom, tr("Weighted random"));
+                    });
+                    if self.dev_window.mode == WinnerMode::WeightedRandom {
+                        ui.horizontal(|ui| {
+                            ui.label(tr("Seed:"));
+                            ui.add(egui::TextEdit::singleline(&mut self.dev_window.seed)
+                                .hint_text(tr("random"))
+                                .desired_width(160.0));
+                        });
+                    }
+
+                    ui.add_space(10.0);
+
+                    let locked = self.with_db(|db| db.winners_locked()).unwrap_or(false);
+                    ui.horizontal(|ui| {
+                        if locked {
+                            ui.colored_label(egui::Color32::GOLD, tr("🔒 Winners locked"));
+                            if ui.button(tr("Unlock...")).clicked() {
+                                self.dev_window.confirm_unlock = true;
+                            }
+                        } else if ui.button(tr("🔓 Lock winners")).clicked() {
+                            match self.with_db(|db| db.set_winners_locked(true)) {
+                                Ok(_) => self.toasts.info(tr("Winners locked")),
+                                Err(e) => self.toasts.error(tr!("Error: {}", e)),
+                            }
+                        }
+                    });
+
+                    if self.dev_window.confirm_unlock {
+                        ui.colored_label(egui::Color32::YELLOW, tr("Unlocking allows the winners to change again."));
+                        ui.horizontal(|ui| {
+                            if ui.button(tr("Unlock winners")).clicked() {
+                                self.dev_window.confirm_unlock = false;
+                                match self.with_db(|db| db.set_winners_locked(false)) {
+                                    Ok(_) => self.toasts.info(tr("Winners unlocked")),
+                                    Err(e) => self.toasts.error(tr!("Error: {}", e)),
+                                }
+                            }
+                            if ui.button(tr("Cancel")).clicked() {
+                                self.dev_window.confirm_unlock = false;
+                            }
+                        });
+                    }
+
+                    ui.add_space(10.0);
+
+                    let summary = self.with_db(|db| db.notification_summary());
+                    if let Ok((confirmed, total)) = summary && total > 0 {
+                        ui.label(tr!("{} of {} winners confirmed", confirmed, total));
+                    }
+
+                    let calculate_label = match self.dev_window.mode {
+                        WinnerMode::Closest => tr!("Calculate Winners (Top {} closest)", self.dev_window.winner_count()),
+                        WinnerMode::WeightedRandom => tr!("Draw {} Winners (weighted)", self.dev_window.winner_count()),
+                    };
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!locked, egui::Button::new(calculate_label)).clicked() {
+                            let has_overrides = self.with_db(|db| db.has_manual_overrides()).unwrap_or(false);
+                            if has_overrides {
+                                self.dev_window.confirm_overwrite = true;
+                            } else {
+                                self.run_calculation();
+                            }
+                        }
+                        if ui.button(tr("Preview winners")).clicked() {
+                            self.preview_draw();
+                        }
+                        if ui.button(tr("Start reveal")).clicked() {
+                            self.start_reveal();
+                        }
+                        if ui.button(tr("Statistics")).clicked() {
+                            self.stats_window.open = true;
+                        }
+                        if ui.button(tr("Audit log")).clicked() {
+                            self.audit_window.open = true;
+                        }
+                    });
+
+                    // Vorschau verwerfen, sobald sich die Anmeldungen geändert haben
+                    if let Some(preview) = &self.dev_window.preview {
+                        let current = self.with_db(|db| db.registration_revision()).ok();
+                        if current != Some(preview.revision) {
+                            self.dev_window.preview = None;
+                            self.toasts.info(tr("Registrations changed, preview discarded"));
+                        }
+                    }
+
+                    // eine offene Vorschau folgt den Eingaben für Ziel, Anzahl, Modus und Seed
+                    if let Some(preview) = &self.dev_window.preview {
+                        let dev = &self.dev_window;
+                        let inputs_changed = dev.targets().is_some_and(|t| t != preview.targets)
+                            || dev.exclude_previous != preview.exclude_previous
+                            || dev.mode != preview.mode
+                            || dev.seed.trim().parse::<u64>().is_ok_and(|s| s != preview.seed);
+                        if inputs_changed {
+                            self.preview_draw();
+                        }
+                    }
+
+                    if let Some(preview) = &self.dev_window.preview {
+                        ui.group(|ui| {
+                            ui.label(tr!("Preview ({} winners):", preview.winners.len()));
+                            if preview.mode == WinnerMode::WeightedRandom {
+                                ui.small(tr!("seed {}", preview.seed));
+                            }
+                            for w in &preview.winners {
+                                ui.label(tr!(
+                                    "{}. {} {} - number {}, distance {}",
+                                    w.prize_index + 1, w.user.first_name, w.user.surname, w.user.number, w.distance
+                                ));
+                            }
+                            // bei einem einzelnen Ziel im Modus "closest" sind das genau die Gewinner
+                            let same_as_winners = preview.nearest.len() == preview.winners.len()
+                                && preview.nearest.iter().zip(&preview.winners).all(|((u, _), w)| u.id == w.user.id);
+                            if !same_as_winners {
+                                ui.separator();
+                                ui.label(tr!("Closest to {}:", preview.targets[0]));
+                                for (pos, (user, distance)) in preview.nearest.iter().enumerate() {
+                                    ui.label(tr!(
+                                        "{}. {} {} - number {}, distance {}",
+                                        pos + 1, user.first_name, user.surname, user.number, distance
+                                    ));
+                                }
+                            }
+                        });
+                        let (targets, exclude_previous) = (preview.targets.clone(), preview.exclude_previous);
+                        let (mode, seed, revision) = (preview.mode, preview.seed, preview.data_revision);
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(!locked, egui::Button::new(tr("Commit this draw"))).clicked() {
+                                self.commit_draw(&targets, exclude_previous, mode, seed, revision);
+                            }
+                            if ui.button(tr("Discard")).clicked() {
+                                self.dev_window.preview = None;
+                            }
+                        });
+                    }
+
+                    if self.dev_window.confirm_overwrite {
+                        ui.colored_label(egui::Color32::YELLOW, tr("Manual winner overrides exist and will be replaced."));
+                        ui.horizontal(|ui| {
+                            if ui.button(tr("Calculate anyway")).clicked() {
+                                self.dev_window.confirm_overwrite = false;
+                                self.run_calculation();
+                            }
+                            if ui.button(tr("Cancel")).clicked() {
+                                self.dev_window.confirm_overwrite = false;
+                            }
+                        });
+                    }
+
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("Save current settings to config")).clicked() {
+                            self.save_config(ctx);
+                        }
+                        if ui.button(tr("Reload theme")).on_hover_text(tr("Re-read the [theme] colors from the config file")).clicked() {
+                            self.reload_theme();
+                        }
+                    });
+
+                    ui.add_space(10.0);
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!self.exporting(), egui::Button::new(tr("Export All Data to Excel"))).clicked() {
+                            self.start_export();
+                        }
+                        if ui.button(tr("Import corrections...")).clicked()
+                            && let Some(path) = rfd::FileDialog::new().add_filter("Excel export", &["xlsx"]).pick_file()
+                        {
+                            self.import_corrections(&path);
+                        }
+                        if ui.add_enabled(self.import_job.is_none(), egui::Button::new(tr("Import CSV..."))).clicked()
+                            && let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv", "txt"]).pick_file()
+                        {
+                            self.start_csv_import(&path);
+                        }
+                        if self.exporting() {
+                            ui.add(egui::Spinner::new());
+                            ui.label(tr("Exporting..."));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.dev_window.anonymize_export, tr("Anonymize"))
+                            .on_hover_text(tr("Emails become a hash, the same for the same person in every export"));
+                        ui.add_enabled(
+                            self.dev_window.anonymize_export,
+                            egui::Checkbox::new(&mut self.dev_window.export_initials, tr("Initials only")),
+                        );
+                    });
+                    egui::CollapsingHeader::new(tr("Export columns")).id_source("export_columns").show(ui, |ui| {
+                        ui.small(tr("Drag ☰ to change the order"));
+                        let mut rows = Vec::new();
+                        let mut dragged = None;
+                        for (index, (column, on)) in self.dev_window.export_columns.iter_mut().enumerate() {
+                            let row = ui.horizontal(|ui| {
+                                let handle = ui.label("☰");
+                                // keyed by column, so the drag survives the row moving
+                                let handle = ui.interact(handle.rect, egui::Id::new(("export_column", *column)), egui::Sense::drag());
+                                if handle.on_hover_cursor(egui::CursorIcon::Grab).dragged() {
+                                    dragged = Some(index);
+                                }
+                                ui.checkbox(on, tr(column.header()));
+                            });
+                            rows.push(row.response.rect);
+                        }
+                        if let (Some(from), Some(pointer)) = (dragged, ui.ctx().pointer_interact_pos()) {
+                            ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+                            let to = rows.iter().enumerate().filter(|(i, r)| *i != from && r.center().y < pointer.y).count();
+                            let column = self.dev_window.export_columns.remove(from);
+                            self.dev_window.export_columns.insert(to, column);
+                        }
+                        if ui.small_button(tr("Reset")).clicked() {
+                            self.dev_window.export_columns = column_spec(&[]);
+                        }
+                    });
+                    if let Some(job) = &self.import_job {
+                        let total = job.lines.len().max(1);
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::ProgressBar::new(job.done as f32 / total as f32)
+                                    .desired_width(200.0)
+                                    .text(tr!("{} / {} rows", job.done, job.lines.len()))
+                            );
+                            ui.label(format!("{}%", job.done * 100 / total));
+                            if ui.button(tr("Cancel")).clicked() {
+                                job.cancel.store(true, Ordering::Relaxed);
+                            }
+                        });
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Countdown to (HH:MM):"));
+                        ui.add(egui::TextEdit::singleline(&mut self.dev_window.countdown_input).desired_width(120.0));
+                        if ui.button(tr("Start")).clicked() {
+                            match parse_clock_time(&self.dev_window.countdown_input) {
+                                Some(target) => self.countdown = Some(Countdown::new(target)),
+                                None => self.toasts.error(tr("Invalid time, use HH:MM or YYYY-MM-DD HH:MM")),
+                            }
+                        }
+                        if self.countdown.is_some() && ui.button(tr("Clear")).clicked() {
+                            self.countdown = None;
+                        }
+                    });
+                    ui.checkbox(&mut self.dev_window.countdown_closes, tr("Close registration when the countdown ends"));
+
+                    ui.add_space(5.0);
+                    let mut open = self.registration_open;
+                    if ui.checkbox(&mut open, tr("Registration open")).changed() {
+                        self.set_registration_open(open);
+                    }
+                    ui.checkbox(&mut self.dev_window.number_hint, tr("Show how many picked the typed number"));
+                    ui.checkbox(&mut self.dev_window.simple_snow, tr("Simple snow (circles, for slow machines)"));
+                    ui.checkbox(&mut self.dev_window.show_target, tr("Show the target number on the main screen"));
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.dev_window.touch_mode, tr("Touch mode"));
+                        ui.add_enabled(
+                            self.dev_window.touch_mode,
+                            egui::Checkbox::new(&mut self.dev_window.touch_keyboard, tr("with on-screen keyboard")),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Seconds between submissions:"));
+                        let mut secs = self.submit_limiter.interval.as_secs();
+                        let response = ui.add(egui::DragValue::new(&mut secs).clamp_range(0..=60));
+                        if response.changed() {
+                            self.submit_limiter.interval = Duration::from_secs(secs);
+                        }
+                        // saved when the drag ends, not on every step
+                        if (response.drag_released() || response.lost_focus())
+                            && let Err(e) = self.with_db(|db| db.set_submit_interval_secs(secs))
+                        {
+                            self.report_error(&e);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Guesses from"));
+                        ui.add(egui::TextEdit::singleline(&mut self.dev_window.guess_range_input.0).desired_width(60.0));
+                        ui.label(tr("to"));
+                        ui.add(egui::TextEdit::singleline(&mut self.dev_window.guess_range_input.1)
+                            .desired_width(60.0)
+                            .hint_text("∞"));
+                        if ui.button(tr("Save")).clicked() {
+                            self.save_guess_range();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("Refresh from disk")).clicked() {
+                            self.refresh_from_disk();
+                        }
+                        ui.checkbox(&mut self.dev_window.auto_refresh, tr("Check for changes every 2 s"));
+                    });
+                    if ui.button(tr("Open log folder")).clicked()
+                        && let Err(e) = open_path(&logging::default_dir())
+                    {
+                        self.toasts.error(tr!("Could not open the log folder: {}", e));
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Close automatically at:"));
+                        ui.add(egui::TextEdit::singleline(&mut self.dev_window.close_at_input).desired_width(120.0));
+                        if ui.button(tr("Schedule")).clicked() {
+                            match parse_clock_time(&self.dev_window.close_at_input) {
+                                Some(at) => self.schedule_close(Some(at.timestamp())),
+                                None => self.toasts.error(tr("Invalid time, use HH:MM or YYYY-MM-DD HH:MM")),
+                            }
+                        }
+                    });
+                    if let Some(ts) = self.registration_close_at {
+                        ui.horizontal(|ui| {
+                            ui.small(tr!("Closes at {}", format_timestamp(ts)));
+                            if ui.small_button(tr("Cancel")).clicked() {
+                                self.schedule_close(None);
+                            }
+                        });
+                    }
+
+                    let now = Instant::now();
+                    if self.dev_window.lan_ip.is_none_or(|(checked, _)| now.duration_since(checked) >= LAN_IP_REFRESH) {
+                        let ip = local_ip();
+                        if ip.is_none() && !matches!(self.dev_window.lan_ip, Some((_, None))) {
+                            log::warn!("No network found, other devices cannot reach this machine");
+                        }
+                        self.dev_window.lan_ip = Some((now, ip));
+                    }
+                    let lan_ip = self.dev_window.lan_ip.and_then(|(_, ip)| ip);
+                    let this_machine = lan_ip.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("This machine: {}", this_machine));
+                        if lan_ip.is_none() {
+                            ui.colored_label(egui::Color32::YELLOW, tr("⚠ No network, other devices cannot connect"));
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Info-screen API:"));
+                        if let Some(server) = &self.http_server {
+                            // "0.0.0.0" is no address to type into a phone
+                            let mut addr = server.addr();
+                            if addr.ip().is_unspecified() {
+                                addr.set_ip(this_machine);
+                            }
+                            ui.label(format!("http://{}", addr));
+                            if ui.button(tr("Stop")).clicked() {
+                                self.http_server = None;
+                            }
+                        } else {
+                            ui.add(egui::TextEdit::singleline(&mut self.dev_window.serve_addr).desired_width(120.0));
+                            if ui.button(tr("Start")).clicked() {
+                                let addr = self.dev_window.serve_addr.trim().to_string();
+                                self.start_server(&addr);
+                            }
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Webhook URL:"));
+                        ui.add(egui::TextEdit::singleline(&mut self.dev_window.webhook_url).hint_text("https://...").desired_width(200.0));
+                        let url = self.dev_window.webhook_url.trim().to_string();
+                        let saved = self.webhook.as_ref().map(Webhook::url).unwrap_or("");
+                        if ui.add_enabled(url != saved, egui::Button::new(tr("Save"))).clicked() {
+                            self.set_webhook(&url);
+                        }
+                    });
+                    if let Some(failures) = self.webhook.as_ref().map(Webhook::failures).filter(|&n| n > 0) {
+                        ui.colored_label(egui::Color32::YELLOW, tr!("⚠ {} webhook messages could not be delivered", failures));
+                    }
+
+                    egui::CollapsingHeader::new(tr("Database")).id_source("database").show(ui, |ui| {
+                        let mut refresh = ui.small_button(tr("Refresh")).clicked();
+                        let revision = self.with_db(|db| db.registration_revision()).unwrap_or_default();
+                        refresh |= !matches!(&self.dev_window.db_stats, Some((rev, _)) if *rev == revision);
+                        if refresh {
+                            let stats = self.with_db(|db| db.stats()).map_err(|e| e.to_string());
+                            self.dev_window.db_stats = Some((revision, stats));
+                        }
+                        match &self.dev_window.db_stats {
+                            Some((_, Ok(stats))) => {
+                                let time = |ts: Option<i64>| ts.map(format_timestamp).unwrap_or_else(|| "-".to_string());
+                                ui.label(tr!("Registrations: {}", stats.registrations));
+                                ui.label(tr!("Winners: {}", stats.winners));
+                                ui.label(tr!("Distinct emails: {}", stats.distinct_emails));
+                                ui.label(tr!("First registration: {}", time(stats.first_created)));
+                                ui.label(tr!("Last registration: {}", time(stats.last_created)));
+                                ui.label(tr!("File: {}", stats.path.display()));
+                                match stats.file_size {
+                                    Some(size) => ui.label(tr!("Size: {} KiB", format!("{:.1}", size as f64 / 1024.0))),
+                                    None => ui.label(tr("Size: in memory")),
+                                };
+                                ui.small(format!(
+                                    "journal {} | synchronous {} | foreign keys {} | busy timeout {} ms",
+                                    stats.journal_mode,
+                                    stats.synchronous,
+                                    if stats.foreign_keys { "on" } else { "off" },
+                                    stats.busy_timeout_ms,
+                                ));
+                            }
+                            Some((_, Err(e))) => {
+                                ui.colored_label(egui::Color32::RED, tr!("Error: {}", e));
+                            }
+                            None => {}
+                        }
+                    });
+
+                    egui::CollapsingHeader::new(tr("Prizes")).id_source("prizes").show(ui, |ui| {
+                        if self.dev_window.prize_edit.is_none() {
+                            match self.with_db(|db| db.get_prizes()) {
+                                Ok(prizes) => self.dev_window.prize_edit = Some(prizes),
+                                Err(e) => {
+                                    ui.colored_label(egui::Color32::RED, tr!("Error: {}", e));
+                                    return;
+                                }
+                            }
+                        }
+                        let Some(prizes) = &mut self.dev_window.prize_edit else {
+                            return;
+                        };
+
+                        let mut remove = None;
+                        for (idx, prize) in prizes.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{}.", idx + 1));
+                                ui.add(egui::TextEdit::singleline(&mut prize.name).hint_text(tr("Name")).desired_width(120.0));
+                                ui.add(egui::TextEdit::singleline(&mut prize.description).hint_text(tr("Description")).desired_width(160.0));
+                                if ui.small_button("✖").clicked() {
+                                    remove = Some(idx);
+                                }
+                            });
+                        }
+                        if let Some(idx) = remove {
+                            prizes.remove(idx);
+                        }
+
+                        let mut save = false;
+                        ui.horizontal(|ui| {
+                            if ui.button(tr("Add prize")).clicked() {
+                                prizes.push(Prize::default());
+                            }
+                            save = ui.button(tr("Save prizes")).clicked();
+                        });
+                        if save {
+                            let prizes = prizes.clone();
+                            match self.with_db(|db| db.save_prizes(&prizes)) {
+                                Ok(_) => self.toasts.success(tr("Prizes saved")),
+                                Err(e) => self.toasts.error(tr!("Error: {}", e)),
+                            }
+                        }
+                    });
+
+                    egui::CollapsingHeader::new(tr("Duplicates")).id_source("duplicates").show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(tr("Max name distance:"));
+                            ui.add(egui::TextEdit::singleline(&mut self.dev_window.duplicate_distance).desired_width(30.0));
+                            if ui.button(tr("Find duplicates")).clicked() {
+                                let distance = self.dev_window.duplicate_distance.trim().parse().unwrap_or(2);
+                                match self.with_db(|db| db.get_all_users()) {
+                                    Ok(users) => self.dev_window.duplicates = Some(find_duplicates(&users, distance)),
+                                    Err(e) => self.toasts.error(tr!("Error: {}", e)),
+                                }
+                            }
+                        });
+                        let Some(groups) = self.dev_window.duplicates.clone() else {
+                            return;
+                        };
+                        if groups.is_empty() {
+                            ui.label(tr("No suspected duplicates."));
+                        }
+                        let mut merged = false;
+                        egui::ScrollArea::vertical().id_source("duplicates").max_height(200.0).show(ui, |ui| {
+                            for group in &groups {
+                                ui.group(|ui| {
+                                    for user in group {
+                                        ui.horizontal(|ui| {
+                                            ui.label(format!(
+                                                "ID {} | {} {} | {} | {}",
+                                                user.id, user.first_name, user.surname, user.email, user.number
+                                            ));
+                                            if ui.small_button(tr("Merge, keep this")).clicked() {
+                                                let others: Vec<i32> = group.iter().map(|u| u.id).filter(|&id| id != user.id).collect();
+                                                match self.with_db(|db| db.merge_users(user.id, &others)) {
+                                                    Ok(_) => self.toasts.success(tr!("Merged {} duplicates into ID {}", others.len(), user.id)),
+                                                    Err(e) => self.toasts.error(tr!("Error: {}", e)),
+                                                }
+                                                merged = true;
+                                            }
+                                        });
+                                    }
+                                });
+                            }
+                        });
+                        if merged {
+                            // search again next time, the old groups are stale
+                            self.dev_window.duplicates = None;
+                        }
+                    });
+
+                    egui::CollapsingHeader::new(tr("Draw history")).id_source("draw_history").show(ui, |ui| {
+                        let draws = match self.with_db(|db| db.get_draws()) {
+                            Ok(draws) => draws,
+                            Err(e) => {
+                                ui.colored_label(egui::Color32::RED, tr!("Error: {}", e));
+                                return;
+                            }
+                        };
+                        if draws.is_empty() {
+                            ui.label(tr("No draws yet."));
+                        }
+
+                        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                            for draw in &draws {
+                                ui.horizontal(|ui| {
+                                    ui.label(tr!(
+                                        "#{} {} | {} | target {} | {} of {} won",
+                                        draw.round,
+                                        format_timestamp(draw.created_at),
+                                        draw.mode,
+                                        draw.targets,
+                                        draw.winner_count,
+                                        draw.participant_count,
+                                    ));
+                                    if let Some(seed) = draw.seed {
+                                        ui.small(tr!("seed {}", seed));
+                                    }
+                                    if ui.small_button(tr("View")).clicked() {
+                                        self.dev_window.selected_draw = Some(draw.id);
+                                    }
+                                    if ui.small_button(tr("Restore flags")).clicked() {
+                                        match self.with_db(|db| db.restore_draw(draw.id)) {
+                                            Ok(_) => self.toasts.success(tr!("Winners of draw #{} restored", draw.round)),
+                                            Err(e) => self.toasts.error(tr!("Error: {}", e)),
+                                        }
+                                    }
+                                });
+                            }
+                        });
+
+                        if let Some(draw_id) = self.dev_window.selected_draw {
+                            ui.separator();
+                            let round = draws.iter().find(|d| d.id == draw_id).map_or(draw_id, |d| d.round);
+                            ui.label(tr!("Winners of draw #{}:", round));
+                            match self.with_db(|db| db.get_draw_winners(draw_id)) {
+                                Ok(winners) => {
+                                    for w in winners {
+                                        let line = tr!(
+                                            "{}. {} (ID {}) - number {}, distance {}",
+                                            w.rank, w.name, w.user_id, w.number, w.distance
+                                        );
+                                        match w.replaced_reason {
+                                            Some(reason) => ui.weak(tr!("{} - re-rolled: {}", line, reason)),
+                                            None => ui.label(line),
+                                        };
+                                    }
+                                }
+                                Err(e) => {
+                                    ui.colored_label(egui::Color32::RED, tr!("Error: {}", e));
+                                }
+                            }
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Numbers:"));
+                        ui.add(egui::TextEdit::singleline(&mut self.dev_window.test_range.0).desired_width(50.0));
+                        ui.label(tr("to"));
+                        ui.add(egui::TextEdit::singleline(&mut self.dev_window.test_range.1).desired_width(50.0));
+                        ui.radio_value(&mut self.dev_window.test_distribution, TestDistribution::Uniform, tr("Uniform"));
+                        ui.radio_value(&mut self.dev_window.test_distribution, TestDistribution::Normal, tr("Normal"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut self.dev_window.test_user_count).desired_width(60.0));
+                        if ui.button(tr("Generate test users")).clicked() {
+                            self.generate_test_data();
+                        }
+                        if ui.button(tr("Delete test data")).clicked() {
+                            match self.with_db(|db| db.delete_test_users()) {
+                                Ok(n) => self.toasts.success(tr!("Deleted {} test users", n)),
+                                Err(e) => self.toasts.error(tr!("Error: {}", e)),
+                            }
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("Backup database")).clicked() {
+                            match self.backup_database() {
+                                Ok(msg) => self.toasts.success(msg),
+                                Err(e) => self.toasts.error(e),
+                            }
+                        }
+                        if ui.button(tr("Restore from backup...")).clicked() {
+                            self.dev_window.pending_restore = rfd::FileDialog::new()
+                                .add_filter("SQLite database", &["db"])
+                                .pick_file();
+                        }
+                    });
+
+                    if let Some(path) = self.dev_window.pending_restore.clone() {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            tr!("Restoring {} overwrites ALL current data.", path.display()),
+                        );
+                        ui.horizontal(|ui| {
+                            if ui.button(tr("Restore")).clicked() {
+                                self.dev_window.pending_restore = None;
+                                match self.restore_database(&path) {
+                                    Ok(msg) => self.toasts.success(msg),
+                                    Err(e) => self.toasts.error(e),
+                                }
+                            }
+                            if ui.button(tr("Cancel")).clicked() {
+                                self.dev_window.pending_restore = None;
+                            }
+                        });
+                    }
+
+                    egui::CollapsingHeader::new(tr("Start new event"))
+                        .id_source("new_event")
+                        .show(ui, |ui| {
+                            ui.label(tr!(
+                                "Current event: {}",
+                                if self.event_name.is_empty() { tr("(unnamed)") } else { &self.event_name }
+                            ));
+                            ui.horizontal(|ui| {
+                                ui.label(tr("New event name:"));
+                                ui.text_edit_singleline(&mut self.dev_window.new_event_name);
+                            });
+                            ui.checkbox(&mut self.dev_window.new_event_backup, tr("Back up the current database first"));
+                            ui.small(tr("Removes all registrations and draws, unlocks the winners and opens the registration."));
+                            let named = !self.dev_window.new_event_name.trim().is_empty();
+                            if self.dev_window.confirm_new_event {
+                                ui.horizontal(|ui| {
+                                    let button = egui::Button::new(tr("Really start new event")).fill(egui::Color32::DARK_RED);
+                                    if ui.add(button).clicked() {
+                                        self.dev_window.confirm_new_event = false;
+                                        self.start_new_event();
+                                    }
+                                    if ui.button(tr("Cancel")).clicked() {
+                                        self.dev_window.confirm_new_event = false;
+                                    }
+                                });
+                            } else if ui.add_enabled(named, egui::Button::new(tr("Start new event"))).clicked() {
+                                self.dev_window.confirm_new_event = true;
+                            }
+                        });
+
+                    egui::CollapsingHeader::new(tr("Events side by side"))
+                        .id_source("events")
+                        .show(ui, |ui| {
+                            ui.small(tr("Every event has its own file with its own registrations, targets and draws."));
+                            for (index, event) in self.databases.iter().enumerate() {
+                                let line = format!("{} - {}", event.name, event.path.display());
+                                if index == self.active {
+                                    ui.strong(line);
+                                } else {
+                                    ui.label(line);
+                                }
+                            }
+                            let dialog = || rfd::FileDialog::new().add_filter("SQLite database", &["db"]);
+                            let mut opened = None;
+                            ui.horizontal(|ui| {
+                                if ui.button(tr("Open event...")).clicked()
+                                    && let Some(path) = dialog().pick_file()
+                                {
+                                    opened = self.add_event(&path);
+                                }
+                                if ui.button(tr("New event file...")).clicked()
+                                    && let Some(path) = dialog().set_file_name("event.db").save_file()
+                                {
+                                    opened = self.add_event(&path);
+                                }
+                            });
+                            if let Some(index) = opened {
+                                self.switch_event(index);
+                            }
+                        });
+
+                    egui::CollapsingHeader::new(egui::RichText::new(tr("Danger zone")).color(egui::Color32::RED))
+                        .id_source("danger_zone")
+                        .show(ui, |ui| {
+                            ui.label(tr("Deletes all registrations, draw history and audit entries. A backup is saved first."));
+                            ui.horizontal(|ui| {
+                                ui.label(tr("Type DELETE:"));
+                                ui.add(egui::TextEdit::singleline(&mut self.dev_window.clear_confirm).desired_width(80.0));
+                            });
+                            let confirmed = self.dev_window.clear_confirm == "DELETE";
+                            let button = egui::Button::new(tr("Delete all registrations")).fill(egui::Color32::DARK_RED);
+                            if ui.add_enabled(confirmed, button).clicked() {
+                                self.dev_window.clear_confirm.clear();
+                                self.clear_all_data();
+                            }
+                        });
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.label(tr("Shortcuts:"));
+                    ui.small(tr("Ctrl+Shift+D - Dev Settings"));
+                    ui.small(tr("Ctrl+Win+L - Table View"));
+                    ui.small(tr("Ctrl+Shift+S - Statistics"));
+                    ui.small(tr("Ctrl+Z - Undo last registration"));
+                    ui.add_space(5.0);
+                    ui.label(tr("Developed by Pierre Maurice Hesse"));
+                    ui.small(format!(
+                        "v{} ({}) - {}",
+                        env!("CARGO_PKG_VERSION"),
+                        env!("GIT_HASH"),
+                        env!("RUSTC_VERSION"),
+                    ));
+                });
+            self.dev_window.open = dev_open;
+            if let Some(shown) = shown {
+                self.dev_window.rect = Some(shown.response.rect);
+            }
+        }
+
+        // Table window
+        if self.table_window.open && self.reveal.is_none() {
+            let mut table_open = self.table_window.open;
+            let mut window = egui::Window::new(tr("Registrations Table"))
+                .id(egui::Id::new("table_window"))
+                .open(&mut table_open)
+                .default_width(700.0)
+                .default_height(500.0);
+            if let Some(rect) = self.table_window.rect {
+                window = window.default_rect(clamp_to_screen(rect, ctx.screen_rect()));
+            }
+            let shown = window.show(ctx, |ui| {
+                    let targets = self.dev_window.targets().unwrap_or_else(|| vec![300]);
+                    let max_num = targets[0];
+                    // operators only see searching, editing and the recycle bin
+                    let may_draw = self.may(Capability::DrawWinners);
+                    let may_export = self.may(Capability::Export);
+                    let may_delete = self.may(Capability::DeleteData);
+
+                    let Ok((previous, locked, prizes, sorted, deleted)) = self.with_db(|db| Ok((
+                        db.get_previous_winners().unwrap_or_default(),
+                        db.winners_locked().unwrap_or(false),
+                        db.get_prizes().unwrap_or_default(),
+                        db.get_sorted_users(max_num),
+                        db.get_deleted_users().unwrap_or_default(),
+                    ))) else {
+                        return;
+                    };
+
+                    if let Some(last) = self.recent_deletes.last() {
+                        let label = tr!("↩ Undo delete ({} {})", last.first_name, last.surname);
+                        if ui.button(label).clicked() {
+                            self.undo_last_delete();
+                        }
+                    }
+                    egui::CollapsingHeader::new(tr!("Recycle bin ({})", deleted.len()))
+                        .id_source("recycle_bin")
+                        .show(ui, |ui| {
+                            if deleted.is_empty() {
+                                ui.label(tr("Nothing deleted."));
+                            }
+                            for user in &deleted {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!(
+                                        "ID {} | {} {} | {} | {}",
+                                        user.id, user.first_name, user.surname, user.email, user.number
+                                    ));
+                                    if ui.small_button(tr("Restore")).clicked() {
+                                        match self.with_db(|db| db.restore_user(user.id)) {
+                                            Ok(true) => {
+                                                // restored by hand, an undo would reset their winner flag
+                                                self.recent_deletes.retain(|u| u.id != user.id);
+                                                self.toasts.success(tr!("{} {} restored", user.first_name, user.surname));
+                                            }
+                                            Ok(false) => self.toasts.error(tr!("{} is already used by a live registration", user.email)),
+                                            Err(e) => self.toasts.error(tr!("Error: {}", e)),
+                                        }
+                                    }
+                                    if self.table_window.pending_purge == Some(user.id) {
+                                        if ui.small_button(egui::RichText::new(tr("Really delete")).color(egui::Color32::RED)).clicked() {
+                                            self.table_window.pending_purge = None;
+                                            match self.with_db(|db| db.purge_user(user.id)) {
+                                                Ok(_) => {}
+                                                // draw_winners still points at them
+                                                Err(e) if e.is_constraint_violation() => {
+                                                    self.toasts.error(tr("Past winners stay in the draw history and cannot be purged"));
+                                                }
+                                                Err(e) => self.toasts.error(tr!("Error: {}", e)),
+                                            }
+                                        }
+                                    } else if may_delete && ui.small_button(tr("Delete permanently")).clicked() {
+                                        self.table_window.pending_purge = Some(user.id);
+                                    }
+                                });
+                            }
+                        });
+                    ui.separator();
+
+                    match sorted {
+                        Ok(users) => {
+                            if users.is_empty() {
+                                ui.label(tr("No registrations yet."));
+                            } else {
+                                ui.label(tr!("Total registrations: {} | Target number: {}", users.len(), max_num));
+                                let mut compact = self.table_window.compact.unwrap_or(ui.available_width() < COMPACT_TABLE_WIDTH);
+                                ui.horizontal(|ui| {
+                                    if ui.checkbox(&mut compact, tr("Compact")).on_hover_text(tr("Two lines per row, for small screens")).changed() {
+                                        self.table_window.compact = Some(compact);
+                                    }
+                                    ui.label(tr("Colors:"));
+                                    egui::ComboBox::from_id_source("palette")
+                                        .selected_text(self.table_window.palette.as_str())
+                                        .show_ui(ui, |ui| {
+                                            for p in Palette::ALL {
+                                                ui.selectable_value(&mut self.table_window.palette, p, p.as_str());
+                                            }
+                                        });
+                                });
+                                if may_draw {
+                                    ui.horizontal(|ui| {
+                                        ui.label(tr("Re-roll reason:"));
+                                        ui.text_edit_singleline(&mut self.table_window.reroll_reason);
+                                    });
+                                }
+                                if may_export && ui.button(tr("Copy all winner emails")).clicked() {
+                                    let emails = winner_emails(&users);
+                                    if emails.is_empty() {
+                                        self.toasts.error(tr("No winners to copy"));
+                                    } else {
+                                        self.toasts.info(tr!("Copied {} winner emails", emails.split(", ").count()));
+                                        ctx.copy_text(emails);
+                                    }
+                                }
+                                if self.table_window.reveal_started.is_some() {
+                                    if ui.button(tr("Reveal all now")).clicked() {
+                                        self.table_window.reveal_started = None;
+                                    }
+                                } else if ui.button(tr("Reveal winners")).clicked() {
+                                    self.table_window.reveal_started = Some(Instant::now());
+                                }
+                                let hidden = match self.table_window.reveal_started {
+                                    Some(start) => unrevealed_winners(&users, start.elapsed()),
+                                    None => Vec::new(),
+                                };
+                                if hidden.is_empty() {
+                                    self.table_window.reveal_started = None;
+                                } else {
+                                    ctx.request_repaint_after(TABLE_REVEAL_STEP / 4);
+                                }
+                                let revealing = self.table_window.reveal_started.is_some();
+                                let users: Vec<User> = if self.table_window.fuzzy {
+                                    fuzzy_search(&users, &self.table_window.filter).into_iter().map(|(u, _)| u.clone()).collect()
+                                } else {
+                                    users.into_iter().filter(|u| matches_filter(u, &self.table_window.filter)).collect()
+                                };
+                                ui.horizontal(|ui| {
+                                    ui.label(tr("Filter:"));
+                                    ui.text_edit_singleline(&mut self.table_window.filter);
+                                    ui.checkbox(&mut self.table_window.fuzzy, tr("Fuzzy"))
+                                        .on_hover_text(tr("Tolerates typos and missing accents, best matches first"));
+                                    if !self.table_window.filter.is_empty() {
+                                        ui.label(tr!("{} shown", users.len()));
+                                    }
+                                    if may_export && ui.add_enabled(!self.exporting(), egui::Button::new(tr("Export current view"))).clicked() {
+                                        self.export_users(users.clone(), prizes.clone(), "registrations_view");
+                                    }
+                                });
+                                ui.add_space(5.0);
+
+                                // winners are measured against their own prize's target
+                                let distance_of = |user: &User| {
+                                    let target = user.prize_index
+                                        .and_then(|p| targets.get(p as usize))
+                                        .copied()
+                                        .unwrap_or(max_num);
+                                    (user.number as i64 - target as i64).abs()
+                                };
+                                let max_distance = users.iter().map(distance_of).max().unwrap_or(0);
+                                let palette = self.table_window.palette;
+                                let dark = ui.visuals().dark_mode;
+
+                                egui::ScrollArea::vertical().show(ui, |ui| {
+                                    ui.heading(tr!("Winners (Top {} closest)", self.dev_window.winner_count()));
+                                    ui.separator();
+
+                                    for (idx, user) in users.iter().enumerate() {
+                                        let distance = distance_of(user);
+                                        // the config's colors stand in for the standard palette only
+                                        let distance_color = self.colors.distance_color(distance_fraction(distance, max_distance))
+                                            .filter(|_| palette == Palette::Standard)
+                                            .unwrap_or_else(|| palette.distance_color(distance, max_distance));
+                                        let shown_winner = user.winner && !hidden.contains(&user.id);
+                                        let bg_color = if shown_winner {
+                                            self.colors.winner_highlight
+                                                .filter(|_| palette == Palette::Standard)
+                                                .unwrap_or_else(|| palette.winner_fill(dark))
+                                        } else {
+                                            stripe_fill(idx, dark)
+                                        };
+
+                                        let controls = |app: &mut Self, ui: &mut egui::Ui| {
+                                            if shown_winner {
+                                                ui.label(egui::RichText::new(tr("★ WINNER")).color(badge_gold(dark)).size(14.0));
+                                                if let Some(prize) = user.prize_index {
+                                                    ui.label(
+                                                        egui::RichText::new(tr!("Prize {}: {}", prize + 1, prize_name(&prizes, Some(prize))))
+                                                            .color(badge_gold(dark))
+                                                    );
+                                                }
+                                                // hand-set winners have no prize and nothing to re-roll
+                                                if may_draw && user.prize_index.is_some() && ui.small_button(tr("Re-roll")).clicked() {
+                                                    let reason = app.table_window.reroll_reason.trim();
+                                                    if locked {
+                                                        app.toasts.error(tr("Winners are locked - unlock them first"));
+                                                    } else if reason.is_empty() {
+                                                        app.toasts.error(tr("Please enter a reason for the re-roll"));
+                                                    } else {
+                                                        match app.with_db(|db| db.reroll_winner(user.id, reason)) {
+                                                            Ok(Some(next)) => app.toasts.success(tr!(
+                                                                "{} {} replaced by {} {}",
+                                                                user.first_name, user.surname, next.first_name, next.surname
+                                                            )),
+                                                            Ok(None) => app.toasts.error(tr("No further candidates to re-roll to")),
+                                                            Err(e) => app.report_error(&e),
+                                                        }
+                                                    }
+                                                }
+                                            }
+
+                                            if let Some(rounds) = previous.get(&user.id) {
+                                                let rounds: Vec<String> = rounds.iter().map(|r| r.to_string()).collect();
+                                                let text = tr!("won round {}", rounds.join(", "));
+                                                // with the exclusion on, they sit out the next draw
+                                                if app.dev_window.exclude_previous && !user.winner {
+                                                    ui.small(egui::RichText::new(tr!("excluded, {}", text)).color(egui::Color32::GRAY).italics());
+                                                } else {
+                                                    ui.small(text);
+                                                }
+                                            }
+
+                                            if shown_winner && may_draw {
+                                                let mut status = user.notified;
+                                                egui::ComboBox::from_id_source(("notified", user.id))
+                                                    .selected_text(tr(status.as_str()))
+                                                    .width(90.0)
+                                                    .show_ui(ui, |ui| {
+                                                        for s in NotifyStatus::ALL {
+                                                            ui.selectable_value(&mut status, s, tr(s.as_str()));
+                                                        }
+                                                    });
+                                                if status != user.notified
+                                                    && let Err(e) = app.with_db(|db| db.set_notified(user.id, status))
+                                                {
+                                                    app.toasts.error(tr!("Error: {}", e));
+                                                }
+                                            }
+
+                                            let toggle_text = if user.winner { tr("Unmark winner") } else { tr("Mark winner") };
+                                            // the button's text would give hidden winners away
+                                            if may_draw && !revealing && ui.small_button(toggle_text).clicked() {
+                                                if locked {
+                                                    app.toasts.error(tr("Winners are locked - unlock them first"));
+                                                } else {
+                                                    app.table_window.pending_toggle = Some((
+                                                        user.id,
+                                                        !user.winner,
+                                                        format!("{} {}", user.first_name, user.surname),
+                                                    ));
+                                                }
+                                            }
+
+                                            if ui.small_button("✏").on_hover_text(tr("Edit")).clicked() {
+                                                app.table_window.pending_edit = Some((user.id, [
+                                                    user.first_name.clone(),
+                                                    user.surname.clone(),
+                                                    user.email.clone(),
+                                                    user.number.to_string(),
+                                                ]));
+                                            }
+                                            if ui.small_button("🗑").on_hover_text(tr("Move to recycle bin")).clicked() {
+                                                app.delete_registration(user);
+                                            }
+                                        };
+
+                                        let frame = egui::Frame::none().fill(bg_color).inner_margin(5.0);
+                                        if compact {
+                                            // name and number, then email and distance; no ticket column
+                                            frame.show(ui, |ui| {
+                                                ui.set_min_width(ui.available_width());
+                                                ui.horizontal_wrapped(|ui| {
+                                                    ui.label(egui::RichText::new(format!("{} {}", user.first_name, user.surname)).strong());
+                                                    ui.label(tr!("Number: {}", user.number));
+                                                    controls(self, ui);
+                                                });
+                                                ui.horizontal_wrapped(|ui| {
+                                                    ui.label(&user.email);
+                                                    if ui.small_button("📋").on_hover_text(tr("Copy email")).clicked() {
+                                                        ctx.copy_text(user.email.clone());
+                                                        self.toasts.info(tr!("Copied {}", user.email));
+                                                    }
+                                                    ui.colored_label(
+                                                        distance_color,
+                                                        tr!("Distance: {} ({})", format_distance(distance), distance_label(distance, max_distance))
+                                                    ).on_hover_text(distance.to_string());
+                                                });
+                                            });
+                                            ui.add_space(2.0);
+                                            continue;
+                                        }
+
+                                        ui.horizontal(|ui| {
+                                            frame.show(ui, |ui| {
+                                                ui.set_min_width(650.0);
+                                                controls(self, ui);
+
+                                                ui.label(egui::RichText::new(format!("#{}", user.ticket_number)).strong().size(15.0))
+                                                    .on_hover_text(tr!("Database ID {}", user.id));
+                                                ui.separator();
+                                                ui.label(&user.first_name);
+                                                ui.label(&user.surname);
+                                                ui.separator();
+                                                ui.label(&user.email);
+                                                if ui.small_button("📋").on_hover_text(tr("Copy email")).clicked() {
+                                                    ctx.copy_text(user.email.clone());
+                                                    self.toasts.info(tr!("Copied {}", user.email));
+                                                }
+                                                ui.separator();
+                                                ui.label(tr!("Number: {}", user.number));
+                                                ui.separator();
+                                                ui.colored_label(
+                                                    distance_color,
+                                                    tr!("Distance: {} ({})", format_distance(distance), distance_label(distance, max_distance))
+                                                ).on_hover_text(distance.to_string());
+                                            });
+                                        });
+                                        ui.add_space(2.0);
+                                    }
+                                });
+                            }
+                        }
+                        Err(e) => {
+                            ui.colored_label(egui::Color32::RED, tr!("Error: {}", e));
+                        }
+                    }
+                });
+            self.table_window.open = table_open;
+            if let Some(shown) = shown {
+                self.table_window.rect = Some(shown.response.rect);
+            }
+        }
+        self.track_admin_input(ctx);
+        if self.role.is_some() && !self.keep_unlocked && !self.dev_window.open && !self.table_window.open {
+            self.set_role(None);
+        }
+        let mut title = APP_TITLE.to_string();
+        if !self.event_name.is_empty() {
+            title = format!("{} - {}", self.event_name, title);
+        }
+        if let Some(role) = self.role {
+            title = format!("{} [{}]", title, role.label());
+        }
+        if title != self.title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.title = title;
+        }
+
+        // Statistics window
+        if self.stats_window.open && self.reveal.is_none() {
+            let mut stats_open = self.stats_window.open;
+            let target = self.dev_window.targets().map(|t| t[0]).unwrap_or(300);
+            egui::Window::new(tr("Statistics"))
+                .id(egui::Id::new("stats_window"))
+                .open(&mut stats_open)
+                .default_width(500.0)
+                .default_height(400.0)
+                .show(ctx, |ui| {
+                    if let Err(e) = self.refresh_stats() {
+                        ui.colored_label(egui::Color32::RED, tr!("Error: {}", e));
+                        return;
+                    }
+
+                    let stats = &self.stats_window.stats;
+                    let fmt = |v: Option<f64>| v.map(|v| format!("{:.1}", v)).unwrap_or_else(|| "-".to_string());
+                    ui.label(tr!(
+                        "Count: {} | Min: {} | Max: {} | Mean: {} | Median: {}",
+                        stats.count,
+                        stats.min.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string()),
+                        stats.max.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string()),
+                        fmt(stats.mean),
+                        fmt(stats.median),
+                    ));
+
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Bin width:"));
+                        ui.add(egui::TextEdit::singleline(&mut self.stats_window.bin_width).desired_width(60.0));
+                    });
+
+                    let width = self.stats_window.bin_width() as f64;
+                    let bars: Vec<egui_plot::Bar> = self.stats_window.bins.iter()
+                        .map(|&(start, count)| egui_plot::Bar::new(start as f64 + width / 2.0, count as f64).width(width))
+                        .collect();
+
+                    egui_plot::Plot::new("number_histogram")
+                        .allow_scroll(false)
+                        .show(ui, |plot_ui| {
+                            plot_ui.bar_chart(egui_plot::BarChart::new(bars).color(egui::Color32::LIGHT_BLUE));
+                            plot_ui.vline(egui_plot::VLine::new(target as f64).color(egui::Color32::GOLD).name(tr("Target")));
+                        });
+                });
+            self.stats_window.open = stats_open;
+        }
+
+        // Audit log viewer
+        if self.audit_window.open && self.reveal.is_none() {
+            let mut audit_open = self.audit_window.open;
+            egui::Window::new(tr("Audit log"))
+                .id(egui::Id::new("audit_window"))
+                .open(&mut audit_open)
+                .default_width(650.0)
+                .default_height(400.0)
+                .show(ctx, |ui| {
+                    let actions = self.with_db(|db| db.audit_actions()).unwrap_or_default();
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Action:"));
+                        let selected = self.audit_window.action_filter.clone().unwrap_or_else(|| tr("All").to_string());
+                        egui::ComboBox::from_id_source("audit_action")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.audit_window.action_filter, None, tr("All"));
+                                for action in actions {
+                                    ui.selectable_value(&mut self.audit_window.action_filter, Some(action.clone()), action);
+                                }
+                            });
+                        ui.label(tr("User ID:"));
+                        ui.add(egui::TextEdit::singleline(&mut self.audit_window.user_filter).desired_width(60.0));
+                    });
+
+                    let user_id = self.audit_window.user_filter.trim().parse::<i32>().ok();
+                    let action = self.audit_window.action_filter.clone();
+                    let entries = match self.with_db(|db| db.get_audit_log(action.as_deref(), user_id, 500)) {
+                        Ok(entries) => entries,
+                        Err(e) => {
+                            ui.colored_label(egui::Color32::RED, tr!("Error: {}", e));
+                            return;
+                        }
+                    };
+                    ui.small(tr!("{} entries (newest first, at most 500)", entries.len()));
+                    ui.separator();
+
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for entry in &entries {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.weak(format!("#{}", entry.id));
+                                ui.label(format_timestamp(entry.created_at));
+                                ui.strong(&entry.action);
+                                if let Some(id) = entry.user_id {
+                                    ui.label(tr!("user {}", id));
+                                }
+                                if let Some(actor) = &entry.actor {
+                                    ui.label(tr!("by {}", actor));
+                                }
+                                ui.monospace(&entry.details);
+                            });
+                        }
+                    });
+                });
+            self.audit_window.open = audit_open;
+        }
+
+        // Registrierung bearbeiten
+        if self.table_window.pending_edit.is_some() {
+            let mut close = false;
+            egui::Window::new(tr("Edit registration"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    let Some((id, fields)) = &mut self.table_window.pending_edit else {
+                        return;
+                    };
+                    let id = *id;
+                    for (label, field) in ["First name:", "Surname:", "Email:", "Number:"].map(tr).iter().zip(fields.iter_mut()) {
+                        ui.label(*label);
+                        ui.text_edit_singleline(field);
+                    }
+                    let fields = fields.clone();
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("Save")).clicked() {
+                            let fields = fields.each_ref().map(String::as_str);
+                            match trim_form_fields(fields) {
+                                None => self.toasts.error(tr("Please fill all fields!")),
+                                Some([first_name, surname, email, number]) => match number.parse::<i32>() {
+                                    Ok(num) if num >= 1 => {
+                                        let [first_name, surname, email] =
+                                            normalize_registration(first_name, surname, email, self.config.title_case_names);
+                                        match self.with_db(|db| db.update_user(id, &first_name, &surname, &email, num)) {
+                                            Ok(_) => {
+                                                self.toasts.success(tr("Registration updated"));
+                                                // an edit can move a guess without changing the revision
+                                                self.number_counts = None;
+                                                close = true;
+                                            }
+                                            Err(e) if e.is_constraint_violation() => {
+                                                self.toasts.error(tr("This email is already registered"));
+                                            }
+                                            Err(e) => self.report_error(&e),
+                                        }
+                                    }
+                                    Ok(_) => self.toasts.error(tr("Number must be >= 1")),
+                                    Err(_) => self.toasts.error(tr("Invalid number format!")),
+                                },
+                            }
+                        }
+                        if ui.button(tr("Cancel")).clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            if close {
+                self.table_window.pending_edit = None;
+            }
+        }
+
+        // Bestätigung für manuelles Gewinner setzen
+        if let Some((id, winner, name)) = self.table_window.pending_toggle.clone() {
+            egui::Window::new(tr("Confirm winner override"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    if winner {
+                        ui.label(tr!("Mark {} as a winner by hand?", name));
+                    } else {
+                        ui.label(tr!("Remove the winner flag from {}?", name));
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("Yes")).clicked() {
+                            match self.with_db(|db| db.set_winner(id, winner)) {
+                                Ok(_) => self.toasts.info(tr!("Manual override saved for {}", name)),
+                                Err(e) => self.toasts.error(tr!("Error: {}", e)),
+                            }
+                            self.table_window.pending_toggle = None;
+                        }
+                        if ui.button(tr("Cancel")).clicked() {
+                            self.table_window.pending_toggle = None;
+                        }
+                    });
+                });
+        }
+
+        // Main panel - OHNE RAHMEN UND PADDING
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none()) // Entfernt alle Rahmen und Padding
+            .show(ctx, |ui| {
+                let painter = ui.painter();
+                let rect = ui.max_rect();
+
+                // Hintergrundbild über den gesamten Bildschirm
+                if let Some(texture) = &self.background_texture
+                    && !self.dev_window.backdrop.flat
+                {
+                    painter.image(
+                        texture.id(),
+                        rect,
+                        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                        egui::Color32::WHITE,
+                    );
+                } else {
+                    // Fallback, falls das Bild nicht geladen werden kann
+                    painter.rect_filled(
+                        rect,
+                        0.0,
+                        self.dev_window.backdrop.background,
+                    );
+                }
+
+                // Schneeflocken über dem Hintergrund
+                for flake in &self.snowflakes {
+                    let center = egui::pos2(
+                        rect.left() + flake.x * rect.width(),
+                        rect.top() + flake.y * rect.height(),
+                    );
+                    let color = egui::Color32::from_rgba_unmultiplied(255, 255, 255, flake.alpha());
+                    if self.dev_window.simple_snow {
+                        painter.circle_filled(center, flake.size, color);
+                    } else {
+                        flake.paint_star(painter, center, color);
+                    }
+                }
+
+                // Schneehaufen als Dreiecksstreifen, ein Punkt pro Bucket
+                let mut drift = egui::Mesh::default();
+                let drift_color = egui::Color32::from_rgba_unmultiplied(245, 248, 255, 235);
+                let step = rect.width() / (SNOW_BUCKETS - 1) as f32;
+                for (i, height) in self.snow_pile.iter().enumerate() {
+                    let x = rect.left() + i as f32 * step;
+                    drift.colored_vertex(egui::pos2(x, rect.bottom()), drift_color);
+                    drift.colored_vertex(egui::pos2(x, rect.bottom() - height * rect.height()), drift_color);
+                }
+                for i in 0..(SNOW_BUCKETS as u32 - 1) {
+                    let base = i * 2;
+                    drift.add_triangle(base, base + 1, base + 2);
+                    drift.add_triangle(base + 1, base + 3, base + 2);
+                }
+                painter.add(egui::Shape::mesh(drift));
+
+                // same parsed value the draw uses, nothing while the input is invalid
+                let targets = self.dev_window.targets().filter(|_| self.dev_window.show_target);
+                if let Some(reveal) = &self.reveal {
+                    reveal.draw(painter, rect);
+                    if let Some(targets) = &targets {
+                        paint_targets(painter, rect, targets);
+                    }
+                    return;
+                }
+
+                if let Some(countdown) = &self.countdown {
+                    countdown.draw(painter, rect);
+                }
+                if let Some(targets) = &targets {
+                    paint_targets(painter, rect, targets);
+                }
+
+                let touch = self.dev_window.touch_mode;
+                let (form_width, form_height) = if touch {
+                    ((rect.width() * 0.6).clamp(420.0, 760.0), (rect.height() * 0.92).clamp(480.0, 1000.0))
+                } else {
+                    ((rect.width() * 0.35).clamp(280.0, 400.0), (rect.height() * 0.5).clamp(280.0, 350.0))
+                };
+
+                // Registrierungsformular muss Transparenz komisches Vireck invordergrung
+                egui::Window::new(tr("Winter Registration"))
+                    .id(egui::Id::new("registration_form"))
+                    .fixed_pos(egui::pos2(
+                        rect.center().x - form_width / 2.0,
+                        rect.center().y - form_height / 2.0,
+                    ))
+                    .fixed_size(egui::vec2(form_width, form_height))
+                    .collapsible(false)
+                    .frame(egui::Frame {
+                        fill: self.colors.form_background.unwrap_or_else(|| form_fill(ctx.style().visuals.dark_mode)),
+                        rounding: egui::Rounding::same(10.0),
+                       // stroke: egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(255, 255, 0, 255)),// eine Umrandung wenn die gewolt wird
+                        inner_margin: egui::Margin::same(15.0),
+                        ..Default::default()
+                    })
+                    .show(ctx, |ui| {
+                        if touch {
+                            touch_style(ui.style_mut());
+                        }
+                        ui.vertical_centered(|ui| {
+                            ui.heading(tr("Register"));
+                            ui.add_space(10.0);
+                        });
+
+                        if let Some(draft) = self.offered_draft.clone() {
+                            ui.horizontal(|ui| {
+                                ui.label(tr("Restore unsubmitted registration?"));
+                                if ui.small_button(tr("Restore")).clicked() {
+                                    [self.first_name, self.surname, self.email, self.number] = draft;
+                                    self.offered_draft = None;
+                                }
+                                if ui.small_button(tr("Discard")).clicked() {
+                                    self.offered_draft = None;
+                                    self.save_draft(true);
+                                }
+                            });
+                        }
+
+                        self.update_form_errors(Instant::now());
+                        let fields = [&self.first_name, &self.surname, &self.email, &self.number].map(String::as_str);
+                        let problems = validate_form(fields, &self.guess_range);
+                        let can_submit = self.registration_open
+                            && self.pending_registration.is_none()
+                            && problems.is_empty()
+                            && self.save_errors.is_empty();
+
+                        // egui's own Tab order follows the layout, so the form
+                        // widgets lock Tab and it is handled here
+                        let focused = self.focused_stop.take();
+                        if let Some(stop) = focused {
+                            let back = ui.input_mut(|i| i.consume_key(egui::Modifiers::SHIFT, egui::Key::Tab));
+                            if back || ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)) {
+                                // a disabled Submit can't hold the focus
+                                let next = tab_step(stop, back);
+                                self.focus_request = Some(if next == FormStop::Submit && !can_submit { tab_step(next, back) } else { next });
+                            }
+                        }
+                        // Esc also takes the focus away, so it is handed back for the second press
+                        let staff_open = self.dev_window.open
+                            || self.table_window.open
+                            || self.stats_window.open
+                            || self.audit_window.open
+                            || self.password_prompt.is_some()
+                            || self.pending_registration.is_some();
+                        if let Some(stop) = focused
+                            && !staff_open
+                            && ui.input(|i| i.key_pressed(egui::Key::Escape))
+                        {
+                            self.focus_request = Some(stop);
+                            self.press_clear(Instant::now());
+                        }
+                        let enter = |response: &egui::Response| {
+                            response.lost_focus() && response.ctx.input(|i| i.key_pressed(egui::Key::Enter))
+                        };
+                        let mut submit_key = false;
+                        let mut edited = Vec::new();
+
+                        form_label(ui, tr("First Name:"), &self.form_errors, FormField::FirstName);
+                        let response = ui.add(egui::TextEdit::singleline(&mut self.first_name).lock_focus(true));
+                        if response.changed() {
+                            truncate_chars(&mut self.first_name, MAX_NAME_LEN);
+                            edited.push(FormField::FirstName);
+                        }
+                        self.track_focus(&response, FormStop::Field(FormField::FirstName));
+                        self.outline_field(ui, &response, FormField::FirstName);
+                        submit_key |= enter(&response);
+                        form_error(ui, &self.form_errors, FormField::FirstName);
+
+                        form_label(ui, tr("Surname:"), &self.form_errors, FormField::Surname);
+                        let response = ui.add(egui::TextEdit::singleline(&mut self.surname).lock_focus(true));
+                        if response.changed() {
+                            truncate_chars(&mut self.surname, MAX_NAME_LEN);
+                            edited.push(FormField::Surname);
+                        }
+                        self.track_focus(&response, FormStop::Field(FormField::Surname));
+                        self.outline_field(ui, &response, FormField::Surname);
+                        submit_key |= enter(&response);
+                        form_error(ui, &self.form_errors, FormField::Surname);
+
+                        form_label(ui, tr("Email:"), &self.form_errors, FormField::Email);
+                        let response = ui.add(egui::TextEdit::singleline(&mut self.email).lock_focus(true));
+                        if response.changed() {
+                            truncate_chars(&mut self.email, MAX_EMAIL_LEN);
+                            edited.push(FormField::Email);
+                        }
+                        self.track_focus(&response, FormStop::Field(FormField::Email));
+                        self.outline_field(ui, &response, FormField::Email);
+                        submit_key |= enter(&response);
+                        form_error(ui, &self.form_errors, FormField::Email);
+
+                        let label = tr!("Number ({}):", guess_range_label(&self.guess_range));
+                        form_label(ui, &label, &self.form_errors, FormField::Number);
+                        let response = ui.add(egui::TextEdit::singleline(&mut self.number).lock_focus(true));
+                        if response.changed() {
+                            edited.push(FormField::Number);
+                        }
+                        self.track_focus(&response, FormStop::Field(FormField::Number));
+                        self.outline_field(ui, &response, FormField::Number);
+                        submit_key |= enter(&response);
+                        form_error(ui, &self.form_errors, FormField::Number);
+                        if self.dev_window.number_hint
+                            && let Ok(number) = self.number.trim().parse::<i32>()
+                            && number >= 1
+                        {
+                            let hint = match self.number_count(number) {
+                                0 => tr!("Nobody chose {} yet", number),
+                                1 => tr!("1 other chose {}", number),
+                                n => tr!("{} others chose {}", n, number),
+                            };
+                            ui.small(hint);
+                        }
+
+                        for field in edited {
+                            self.field_edited(field, Instant::now());
+                        }
+
+                        if touch {
+                            // typed into the field touched last, the number by default
+                            let key = if self.dev_window.touch_keyboard && self.key_target != FormField::Number {
+                                qwertz_keyboard(ui, &mut self.keyboard_shift)
+                            } else {
+                                numeric_keypad(ui)
+                            };
+                            if let Some(key) = key {
+                                self.type_key(key);
+                            }
+                        }
+
+                        ui.add_space(10.0);
+
+                        if !self.registration_open {
+                            ui.colored_label(egui::Color32::RED, tr("Registration is closed"));
+                        }
+
+                        let clear_pending = self.clear_pending(Instant::now());
+                        let clear_label = if clear_pending { tr("Really clear?") } else { tr("Clear") };
+                        let (submit, clear) = if touch {
+                            let clear_width = 150.0;
+                            let submit_width = ui.available_width() - clear_width - ui.spacing().item_spacing.x;
+                            (
+                                egui::Button::new(egui::RichText::new(tr("Submit")).size(28.0)).min_size(egui::vec2(submit_width, 56.0)),
+                                egui::Button::new(egui::RichText::new(clear_label).size(20.0)).min_size(egui::vec2(clear_width, 56.0)),
+                            )
+                        } else {
+                            (egui::Button::new(tr("Submit")), egui::Button::new(clear_label).small())
+                        };
+                        let (response, clear_clicked) =
+                            ui.horizontal(|ui| (ui.add_enabled(can_submit, submit), ui.add(clear).clicked())).inner;
+                        let why_not = if !self.registration_open {
+                            tr("Registration is closed").to_string()
+                        } else if let Some(error) = problems.first().or(self.save_errors.first()) {
+                            error.message.clone()
+                        } else {
+                            tr("Waiting for the confirmation").to_string()
+                        };
+                        let response = response.on_disabled_hover_text(why_not);
+                        self.track_focus(&response, FormStop::Submit);
+                        if clear_clicked {
+                            self.press_clear(Instant::now());
+                        }
+                        if clear_pending {
+                            ui.colored_label(egui::Color32::YELLOW, tr("Clear form? Esc or Clear again to confirm"));
+                        }
+                        let modal_open = self.pending_registration.is_some() || self.password_prompt.is_some();
+                        if submit_key && !modal_open && !can_submit {
+                            // Enter on an unfinished form points out what is missing
+                            for field in [FormField::FirstName, FormField::Surname, FormField::Email, FormField::Number] {
+                                self.check_field(field);
+                            }
+                        }
+                        if response.clicked() || (submit_key && can_submit && !modal_open) {
+                            let fields = [&self.first_name, &self.surname, &self.email, &self.number]
+                                .map(String::as_str);
+                            if !self.submit_limiter.allow((), Instant::now()) {
+                                self.toasts.info(tr("Please wait a moment"));
+                            } else {
+                                let [first_name, surname, email, number] = fields;
+                                // validate_form already checked the number
+                                let num = number.trim().parse::<i32>().unwrap_or(1);
+                                // the confirm dialog already shows the stored spelling
+                                let [first_name, surname, email] =
+                                    normalize_registration(first_name, surname, email, self.config.title_case_names);
+                                self.pending_registration = Some((first_name, surname, email, num));
+                            }
+                        }
+
+                        let can_undo = self.undo_candidate().is_some();
+                        if ui.add_enabled(can_undo, egui::Button::new(tr("Undo last registration")).small()).clicked() {
+                            self.undo_last_registration();
+                        }
+
+                        ui.add_space(5.0);
+                        ui.separator();
+                        ui.vertical_centered(|ui| {
+                            ui.small(tr("Developed by Pierre Maurice Hesse"));
+                        });
+                    });
+            });
+
+        // Angaben vor dem Speichern bestätigen lassen
+        if let Some(staged) = self.pending_registration.clone() {
+            let (first_name, surname, email, number) = &staged;
+            egui::Window::new(tr("Please confirm your details"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    egui::Grid::new("confirm_registration").num_columns(2).show(ui, |ui| {
+                        ui.label(tr("Name:"));
+                        ui.strong(format!("{} {}", first_name, surname));
+                        ui.end_row();
+                        ui.label(tr("Email:"));
+                        ui.strong(email);
+                        ui.end_row();
+                        ui.label(tr("Number:"));
+                        ui.strong(egui::RichText::new(number.to_string()).size(20.0));
+                        ui.end_row();
+                    });
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("Confirm")).clicked() {
+                            self.pending_registration = None;
+                            self.submit_registration(staged.clone());
+                        }
+                        if ui.button(tr("Edit")).clicked() {
+                            self.pending_registration = None;
+                        }
+                    });
+                });
+        }
+
+        if let Some(celebration) = &self.celebration {
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("celebration")));
+            celebration.draw(&painter, ctx.screen_rect(), self.dev_window.simple_snow);
+        }
+
+        self.toasts.show(ctx, &self.colors);
+    }
+}
+
+/// Shown instead of the booth while the database file can't be opened.
+pub struct StartupError {
+    path: PathBuf,
+    error: String,
+    /// handed on to the booth once it starts
+    config: (AppConfig, PathBuf),
+    events: Vec<PathBuf>,
+}
+
+impl StartupError {
+    /// Returns the database once one of the buttons managed to open it.
+    fn show(&mut self, ctx: &egui::Context) -> Option<NamedDatabase> {
+        let mut attempt = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(40.0);
+                ui.heading(tr("The registration database could not be opened"));
+                ui.add_space(10.0);
+                ui.label(tr!("File: {}", self.path.display()));
+                ui.colored_label(egui::Color32::RED, &self.error);
+                ui.add_space(5.0);
+                ui.label(tr("Another program may still have it open, or the file is damaged."));
+                ui.add_space(15.0);
+
+                if ui.button(tr("Retry")).clicked() {
+                    attempt = Some(self.path.clone());
+                }
+                if ui.button(tr("Open another file...")).clicked() {
+                    attempt = rfd::FileDialog::new().add_filter("SQLite database", &["db"]).pick_file();
+                }
+                if ui.button(tr("Start with a fresh database")).clicked() {
+                    // next to the old file, which stays untouched for recovery
+                    attempt = Some(self.path.with_file_name(format!("registrations_{}.db", unix_now())));
+                }
+            });
+        });
+
+        let path = attempt?;
+        match Database::open(&path) {
+            Ok(db) => Some(NamedDatabase::new(&path, db)),
+            Err(e) => {
+                self.error = e.to_string();
+                self.path = path;
+                None
+            }
+        }
+    }
+}
+
+/// What the window runs: the booth, or the screen explaining why the
+/// database didn't open. Succeeding from there switches to the booth.
+pub enum Booth {
+    Failed(Box<StartupError>),
+    Running(Box<MyApp>),
+}
+
+impl Booth {
+    /// `events` are opened next to `path` as further tabs; `serve` starts
+    /// the info-screen API right away, as `--serve` does.
+    pub fn new(
+        ctx: &egui::Context,
+        path: PathBuf,
+        database: SqlResult<Database>,
+        events: Vec<PathBuf>,
+        config: (AppConfig, PathBuf),
+        serve: Option<String>,
+    ) -> Self {
+        // the error screen comes before any saved settings
+        set_lang(Lang::parse(&config.0.language).unwrap_or(Lang::En));
+        match database {
+            Ok(db) => {
+                let mut app = MyApp::new(ctx, NamedDatabase::new(&path, db), config.0, config.1);
+                for event in &events {
+                    app.add_event(event);
+                }
+                if let Some(addr) = serve {
+                    app.dev_window.serve_addr = addr.clone();
+                    app.start_server(&addr);
+                }
+                Booth::Running(Box::new(app))
+            }
+            Err(e) => Booth::Failed(Box::new(StartupError { path, error: e.to_string(), config, events })),
+        }
+    }
+}
+
+impl eframe::App for Booth {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        match self {
+            Booth::Running(app) => app.update(ctx, frame),
+            Booth::Failed(failed) => {
+                if let Some(db) = failed.show(ctx) {
+                    let (config, config_path) = failed.config.clone();
+                    let mut app = MyApp::new(ctx, db, config, config_path);
+                    for event in &failed.events {
+                        app.add_event(event);
+                    }
+                    *self = Booth::Running(Box::new(app));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ThemeConfig;
+    use crate::fixtures::{MemoryStore, named, user};
+
+    /// An app on the list-backed `MemoryStore`, no SQLite involved.
+    fn test_app() -> MyApp {
+        test_app_with(AppConfig::default())
+    }
+
+    fn test_app_with(config: AppConfig) -> MyApp {
+        let event = NamedDatabase::new(Path::new("memory"), MemoryStore::default());
+        MyApp::new(&egui::Context::default(), event, config, PathBuf::from("config.toml"))
+    }
+
+    #[test]
+    fn distance_color_endpoints() {
+        let distance_color = |d, max| Palette::Standard.distance_color(d, max);
+        assert_eq!(distance_color(0, 100), egui::Color32::GREEN);
+        assert_eq!(distance_color(100, 100), egui::Color32::RED);
+
+        // beyond the spread and with no spread at all
+        assert_eq!(distance_color(5000, 100), egui::Color32::RED);
+        assert_eq!(distance_color(0, 0), egui::Color32::GREEN);
+
+        let mid = distance_color(50, 100);
+        assert!(mid.r() > 200 && mid.g() > 200, "{:?}", mid);
+    }
+
+    #[test]
+    fn errors_outlive_other_toasts_and_only_the_newest_show() {
+        let start = Instant::now();
+        let mut toasts = Toasts::default();
+        toasts.items.push(("saved".to_string(), start, Level::Success));
+        toasts.items.push(("failed".to_string(), start, Level::Error));
+        let texts = |toasts: &mut Toasts, at| toasts.visible(at).iter().map(|t| t.0.clone()).collect::<Vec<_>>();
+        assert_eq!(texts(&mut toasts, start + Duration::from_secs(6)), vec!["failed"]);
+        assert!(texts(&mut toasts, start + Duration::from_secs(11)).is_empty());
+
+        for i in 0..6 {
+            toasts.items.push((i.to_string(), start, Level::Info));
+        }
+        assert_eq!(texts(&mut toasts, start), vec!["2", "3", "4", "5"]);
+    }
+
+    #[test]
+    fn celebration_burst_is_over_after_two_seconds() {
+        let mut celebration = Celebration::new(&mut rand::thread_rng());
+        assert_eq!(celebration.flakes.len(), BURST_FLAKES);
+        for _ in 0..60 {
+            celebration.tick(1.0 / 60.0);
+        }
+        assert!(!celebration.done());
+        // the first flakes have left the center
+        assert!(celebration.flakes.iter().all(|f| (f.x, f.y) != (0.5, 0.5)));
+        for _ in 0..61 {
+            celebration.tick(1.0 / 60.0);
+        }
+        assert!(celebration.done());
+        assert!(celebration.flakes.is_empty());
+    }
+
+    #[test]
+    fn reveal_names_the_place_of_the_prize_won() {
+        // the 2nd prize went to someone deleted, plus one winner set by hand
+        let winners = vec![
+            User { winner: true, prize_index: Some(0), ..user(1, 42) },
+            User { winner: true, prize_index: Some(2), ..user(2, 40) },
+            User { winner: true, ..user(3, 10) },
+        ];
+        let mut reveal = Reveal { winners, prizes: Vec::new(), revealed: 0, timer: 0.0 };
+        assert_eq!((reveal.next_index(), reveal.place(reveal.next_index())), (2, None));
+        reveal.advance();
+        assert_eq!(reveal.place(reveal.next_index()), Some(3));
+        reveal.advance();
+        assert_eq!(reveal.place(reveal.next_index()), Some(1));
+    }
+
+    #[test]
+    fn table_reveal_goes_from_the_last_prize_to_the_first() {
+        let user = |id, winner, prize_index| User { winner, prize_index, ..user(id, 42) };
+        let users = [user(1, true, Some(1)), user(2, false, None), user(3, true, Some(0)), user(4, true, Some(2))];
+        assert_eq!(unrevealed_winners(&users, Duration::ZERO), vec![3, 1, 4]);
+        assert_eq!(unrevealed_winners(&users, TABLE_REVEAL_STEP), vec![3, 1]);
+        assert_eq!(unrevealed_winners(&users, TABLE_REVEAL_STEP * 2 + TABLE_REVEAL_STEP / 2), vec![3]);
+        assert!(unrevealed_winners(&users, TABLE_REVEAL_STEP * 3).is_empty());
+    }
+
+    #[test]
+    fn distances_get_separators_and_a_cap() {
+        assert_eq!(format_distance(0), "0");
+        assert_eq!(format_distance(999), "999");
+        assert_eq!(format_distance(1000), "1,000");
+        assert_eq!(format_distance(123_456), "123,456");
+        assert_eq!(format_distance(-1234), "-1,234");
+        assert_eq!(format_distance(DISTANCE_DISPLAY_CAP), "1,000,000");
+        assert_eq!(format_distance(DISTANCE_DISPLAY_CAP + 1), ">1,000,000");
+        assert_eq!(format_distance(i32::MAX as i64 * 2), ">1,000,000");
+    }
+
+    #[test]
+    fn on_screen_keys_edit_the_text() {
+        let mut number = "12".to_string();
+        apply_key(&mut number, KeyInput::Char('3'));
+        assert_eq!(number, "123");
+        apply_key(&mut number, KeyInput::Backspace);
+        assert_eq!(number, "12");
+        apply_key(&mut number, KeyInput::Clear);
+        apply_key(&mut number, KeyInput::Backspace);
+        assert_eq!(number, "");
+    }
+
+    #[test]
+    fn events_side_by_side_keep_their_own_data() {
+        let dir = std::env::temp_dir().join(format!("winter_raten_events_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (adults, kids) = (dir.join("adults.db"), dir.join("kids.db"));
+        let database = Database::open(&adults).unwrap();
+        database.set_event_name("Wintermarkt").unwrap();
+
+        let ctx = egui::Context::default();
+        let mut app = MyApp::new(&ctx, NamedDatabase::new(&adults, database), AppConfig::default(), dir.join("config.toml"));
+        app.with_db(|db| db.insert_user("Anna", "Schmidt", "anna@example.com", 42)).unwrap();
+        let kids_index = app.add_event(&kids).unwrap();
+        assert_eq!(app.add_event(&kids), Some(kids_index));
+        app.switch_event(kids_index);
+        assert_eq!(app.databases[kids_index].name, "kids");
+        assert_eq!(app.event_name, "");
+        assert!(app.with_db(|db| db.get_all_users()).unwrap().is_empty());
+
+        app.switch_event(0);
+        assert_eq!(app.event_name, "Wintermarkt");
+        assert_eq!(app.with_db(|db| db.get_all_users()).unwrap().len(), 1);
+        drop(app);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn presets_rebuild_the_snow_and_stay_overridable() {
+        let config = AppConfig {
+            theme: ThemeConfig { primary: Some("#102030".to_string()), ..ThemeConfig::default() },
+            ..AppConfig::default()
+        };
+        let mut app = test_app_with(config);
+        assert_eq!(app.snowflakes.len(), Backdrop::default().count);
+
+        app.apply_preset(Some(ThemePreset::Christmas));
+        assert_eq!(app.snowflakes.len(), ThemePreset::Christmas.backdrop().count);
+        // the config's color beats the preset's, the rest comes from the preset
+        assert_eq!(app.colors.primary, Some(egui::Color32::from_rgb(16, 32, 48)));
+        assert_eq!(app.colors.winner_highlight, ThemePreset::Christmas.colors().winner_highlight);
+
+        app.dev_window.backdrop.count = 42;
+        app.dev_window.backdrop.flat = true;
+        let saved = app.current_settings();
+        app.apply_preset(Some(ThemePreset::Neutral));
+        assert!(app.snowflakes.is_empty());
+        app.apply_settings(saved);
+        assert_eq!(app.dev_window.preset, Some(ThemePreset::Christmas));
+        assert_eq!(app.snowflakes.len(), 42);
+        assert!(app.dev_window.backdrop.flat);
+
+        let new_year = ThemePreset::NewYear.backdrop();
+        let flake = Snowflake::spawn(&mut rand::thread_rng(), &new_year, 0.0);
+        assert!((1.5..=7.0).contains(&flake.size), "{}", flake.size);
+        for preset in ThemePreset::ALL {
+            assert_eq!(ThemePreset::parse(preset.code()), Some(preset));
+        }
+    }
+
+    #[test]
+    fn password_is_checked_off_the_ui_thread() {
+        let config = AppConfig { admin_password_hash: Some(crate::password::hash_password("geheim")), ..AppConfig::default() };
+        let mut app = test_app_with(config);
+        let unlock = |app: &mut MyApp, password: &str| {
+            app.toggle_guarded(Guarded::Dev);
+            app.password_prompt.as_mut().unwrap().input = password.to_string();
+            app.try_unlock();
+            assert!(app.password_prompt.as_ref().unwrap().checking.is_some());
+            let start = Instant::now();
+            while app.password_prompt.as_ref().is_some_and(|p| p.checking.is_some()) {
+                assert!(start.elapsed() < Duration::from_secs(30), "the check never finished");
+                std::thread::sleep(Duration::from_millis(10));
+                app.finish_unlock();
+            }
+        };
+
+        unlock(&mut app, "falsch");
+        assert_eq!(app.role, None);
+        assert!(app.password_prompt.take().is_some(), "a wrong password keeps the prompt");
+        unlock(&mut app, "geheim");
+        assert_eq!(app.role, Some(Role::Admin));
+        assert!(app.dev_window.open && app.password_prompt.is_none());
+    }
+
+    #[test]
+    fn submit_stores_the_entry_and_flags_a_taken_email() {
+        let mut app = test_app();
+        app.first_name = "Anna".to_string();
+        app.submit_registration(("Anna".to_string(), "Schmidt".to_string(), "anna@example.com".to_string(), 42));
+        let users = app.with_db(|db| db.get_all_users()).unwrap();
+        assert_eq!((users.len(), users[0].number), (1, 42));
+        assert_eq!(app.recent_inserts, vec![users[0].id]);
+        assert!(app.form_fields().iter().all(String::is_empty));
+
+        app.submit_registration(("Anne".to_string(), "S".to_string(), "ANNA@example.com".to_string(), 7));
+        assert_eq!(app.save_errors.iter().map(|e| e.field).collect::<Vec<_>>(), vec![FormField::Email]);
+        assert_eq!(app.with_db(|db| db.get_all_users()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn draw_picks_the_closest_until_the_winners_are_locked() {
+        let mut app = test_app();
+        for (i, number) in [100, 290, 305, 500].into_iter().enumerate() {
+            app.with_db(|db| db.insert_user("User", &i.to_string(), &format!("u{}@example.com", i), number)).unwrap();
+        }
+        app.dev_window.max_number = "300".to_string();
+        app.dev_window.winner_count = "2".to_string();
+        app.dev_window.mode = WinnerMode::Closest;
+        let winners = |app: &MyApp| -> Vec<i32> {
+            app.with_db(|db| db.get_all_users()).unwrap().into_iter().filter(|u| u.winner).map(|u| u.number).collect()
+        };
+
+        app.run_calculation();
+        assert_eq!(winners(&app), vec![290, 305]);
+
+        app.with_db(|db| db.set_winners_locked(true)).unwrap();
+        app.dev_window.max_number = "500".to_string();
+        app.run_calculation();
+        assert_eq!(winners(&app), vec![290, 305], "locked winners stay");
+        assert!(matches!(app.toasts.items.last(), Some((_, _, Level::Error))));
+    }
+
+    #[test]
+    fn clearing_the_form_needs_a_second_press() {
+        let mut app = test_app();
+        let start = Instant::now();
+        assert!(!app.press_clear(start));
+        assert!(!app.clear_pending(start), "an empty form is not armed");
+
+        app.first_name = "Anna".to_string();
+        assert!(!app.press_clear(start));
+        assert!(!app.press_clear(start + CLEAR_CONFIRM_WINDOW), "timed out, asks again");
+        assert_eq!(app.first_name, "Anna");
+        assert!(app.press_clear(start + CLEAR_CONFIRM_WINDOW + Duration::from_secs(1)));
+        assert!(app.form_fields().iter().all(String::is_empty));
+    }
+
+    #[test]
+    fn fields_are_checked_once_typing_pauses() {
+        let mut app = test_app();
+        let start = Instant::now();
+        app.update_form_errors(start);
+        assert!(app.form_errors.is_empty(), "nothing is flagged before it was touched");
+
+        app.email = "anna(at)example.com".to_string();
+        app.field_edited(FormField::Email, start);
+        app.update_form_errors(start + VALIDATE_DELAY / 2);
+        assert!(app.form_errors.is_empty());
+        app.update_form_errors(start + VALIDATE_DELAY);
+        assert_eq!(app.form_errors.iter().map(|e| e.field).collect::<Vec<_>>(), vec![FormField::Email]);
+
+        app.save_errors.push(FieldError { field: FormField::Email, message: "This email is already registered".to_string() });
+        app.email = "anna@example.com".to_string();
+        app.field_edited(FormField::Email, start);
+        app.update_form_errors(start + VALIDATE_DELAY);
+        assert!(app.form_errors.is_empty(), "{:?}", app.form_errors);
+    }
+
+    #[test]
+    fn tab_cycles_through_the_form() {
+        let mut stop = FormStop::Field(FormField::FirstName);
+        for _ in 0..TAB_ORDER.len() {
+            stop = tab_step(stop, false);
+        }
+        assert_eq!(stop, FormStop::Field(FormField::FirstName));
+        assert_eq!(tab_step(FormStop::Field(FormField::Number), false), FormStop::Submit);
+        assert_eq!(tab_step(FormStop::Field(FormField::FirstName), true), FormStop::Submit);
+        assert_eq!(tab_step(FormStop::Submit, true), FormStop::Field(FormField::Number));
+    }
+
+    #[test]
+    fn filter_matches_name_email_ticket_and_guess() {
+        let user = User { ticket_number: 12, ..named(9, "Anna", "Schmidt", 250) };
+        for filter in ["", "  ", "anna schmidt", "SCHMI", "@example", "#12", "12", "250"] {
+            assert!(matches_filter(&user, filter), "{:?}", filter);
+        }
+        for filter in ["bob", "25", "#9"] {
+            assert!(!matches_filter(&user, filter), "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn color_blind_palette_avoids_red_and_green() {
+        let near = Palette::ColorBlind.distance_color(0, 100);
+        let far = Palette::ColorBlind.distance_color(100, 100);
+        assert!(near.b() > near.r() && near.b() > near.g(), "{:?}", near);
+        assert!(far.r() > far.b(), "{:?}", far);
+
+        assert_eq!(distance_label(0, 100), "close");
+        assert_eq!(distance_label(50, 100), "medium");
+        assert_eq!(distance_label(100, 100), "far");
+        assert_eq!(distance_label(7, 0), "close");
+    }
+
+    #[test]
+    fn theme_survives_the_settings_and_follows_the_system() {
+        for theme in Theme::ALL {
+            assert_eq!(Theme::parse(theme.code()), Some(theme));
+        }
+        assert_eq!(Theme::parse("purple"), None);
+
+        assert!(Theme::System.is_dark(None));
+        assert!(!Theme::System.is_dark(Some(eframe::Theme::Light)));
+        assert!(Theme::Dark.is_dark(Some(eframe::Theme::Light)));
+        assert!(!Theme::Light.is_dark(Some(eframe::Theme::Dark)));
+    }
+
+    #[test]
+    fn restored_windows_stay_on_screen() {
+        let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0));
+        let inside = egui::Rect::from_min_size(egui::pos2(10.0, 20.0), egui::vec2(300.0, 200.0));
+        assert_eq!(clamp_to_screen(inside, screen), inside);
+
+        // saved on a second monitor that is gone now
+        let off = egui::Rect::from_min_size(egui::pos2(2500.0, -50.0), egui::vec2(300.0, 200.0));
+        assert_eq!(clamp_to_screen(off, screen), egui::Rect::from_min_size(egui::pos2(500.0, 0.0), egui::vec2(300.0, 200.0)));
+
+        let huge = egui::Rect::from_min_size(egui::pos2(100.0, 100.0), egui::vec2(1200.0, 900.0));
+        assert_eq!(clamp_to_screen(huge, screen), screen);
+    }
+
+    #[test]
+    fn main_window_is_moved_back_onto_the_monitor() {
+        let ctx = egui::Context::default();
+        let mut app = test_app();
+
+        let mut input = egui::RawInput::default();
+        let viewport = input.viewports.entry(egui::ViewportId::ROOT).or_default();
+        viewport.monitor_size = Some(egui::vec2(1920.0, 1080.0));
+        viewport.outer_rect = Some(egui::Rect::from_min_size(egui::pos2(2500.0, 100.0), egui::vec2(800.0, 600.0)));
+        viewport.inner_rect = Some(egui::Rect::from_min_size(egui::pos2(2500.0, 130.0), egui::vec2(800.0, 570.0)));
+        let output = ctx.run(input, |ctx| app.keep_window_on_screen(ctx));
+
+        assert!(app.window_on_screen);
+        let commands = &output.viewport_output[&egui::ViewportId::ROOT].commands;
+        assert!(commands.contains(&egui::ViewportCommand::OuterPosition(egui::pos2(1120.0, 100.0))), "{:?}", commands);
+        assert!(!commands.iter().any(|c| matches!(c, egui::ViewportCommand::InnerSize(_))), "{:?}", commands);
+    }
+}
@@ -0,0 +1,187 @@
+//! Posts a small JSON message for every new registration, e.g. to a Slack
+//! or Discord webhook or a chat relay.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, mpsc};
+use std::time::Duration;
+use serde_json::Value;
+
+/// Tries per message before it counts as failed.
+const ATTEMPTS: u32 = 4;
+/// Wait before the first retry, doubled after every further failure.
+const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+/// For connecting and for the whole answer each, so an unreachable host
+/// can't hold up the messages behind it.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Background sender; `send` never blocks on the network.
+pub struct Webhook {
+    url: String,
+    queue: mpsc::Sender<Value>,
+    failures: Arc<AtomicUsize>,
+}
+
+impl Webhook {
+    /// Checks `url` and starts the worker thread. It ends once the
+    /// webhook is dropped and the queue is empty.
+    pub fn start(url: &str) -> Result<Self, String> {
+        Self::start_with_backoff(url, FIRST_BACKOFF)
+    }
+
+    fn start_with_backoff(url: &str, first_backoff: Duration) -> Result<Self, String> {
+        let target = check_url(url)?;
+        let (queue, rx) = mpsc::channel::<Value>();
+        let failures = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&failures);
+        std::thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout_connect(TIMEOUT).timeout(TIMEOUT).build();
+            for payload in rx {
+                let mut backoff = first_backoff;
+                for attempt in 1..=ATTEMPTS {
+                    match post(&agent, &target, &payload) {
+                        Ok(()) => break,
+                        Err(e) if attempt == ATTEMPTS => {
+                            log::error!("Webhook gave up after {} attempts: {}", ATTEMPTS, e);
+                            counter.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(e) => {
+                            log::debug!("Webhook attempt {} failed: {}", attempt, e);
+                            std::thread::sleep(backoff);
+                            backoff *= 2;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Webhook { url: url.to_string(), queue, failures })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Queues `payload` for the worker.
+    pub fn send(&self, payload: Value) {
+        // the worker only stops when we do
+        let _ = self.queue.send(payload);
+    }
+
+    /// Messages dropped after the last retry, for the dev window.
+    pub fn failures(&self) -> usize {
+        self.failures.load(Ordering::Relaxed)
+    }
+}
+
+/// `url` trimmed, if it is an http:// or https:// URL with a host.
+fn check_url(url: &str) -> Result<String, String> {
+    let url = url.trim();
+    let parsed = url::Url::parse(url).map_err(|e| format!("Not a URL: {} ({})", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Not an http:// or https:// URL: {}", url));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(format!("No host in {}", url));
+    }
+    Ok(url.to_string())
+}
+
+/// One POST; anything but a 2xx answer, after redirects, is an error.
+fn post(agent: &ureq::Agent, url: &str, payload: &Value) -> Result<(), String> {
+    let response = agent
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&payload.to_string())
+        .map_err(|e| e.to_string())?;
+    match response.status() {
+        200..=299 => Ok(()),
+        status => Err(format!("webhook answered {}", status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use serde_json::json;
+
+    #[test]
+    fn accepts_http_and_https_urls_only() {
+        assert_eq!(check_url(" http://relay.local:8080/hooks/raffle ").unwrap(), "http://relay.local:8080/hooks/raffle");
+        assert!(check_url("https://hooks.slack.com/services/x").is_ok());
+        assert!(check_url("ftp://relay.local/x").is_err());
+        assert!(check_url("relay.local/x").is_err());
+        assert!(check_url("http://:80/").is_err());
+    }
+
+    /// Answers one request per entry of `statuses`, then stops; the
+    /// thread returns how many requests it saw.
+    fn serve(statuses: Vec<u16>) -> (String, std::thread::JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            for status in &statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 256];
+                while !request.ends_with(b"}") {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let answer = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                stream.write_all(answer.as_bytes()).unwrap();
+            }
+            statuses.len()
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn retries_until_the_webhook_answers() {
+        let (url, server) = serve(vec![500, 503, 204]);
+        let webhook = Webhook::start_with_backoff(&url, Duration::from_millis(10)).unwrap();
+        webhook.send(json!({ "first_name": "Anna" }));
+        assert_eq!(server.join().unwrap(), 3);
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(webhook.failures(), 0);
+    }
+
+    #[test]
+    fn counts_a_message_as_failed_after_the_last_attempt() {
+        let (url, server) = serve(vec![500; ATTEMPTS as usize]);
+        let webhook = Webhook::start_with_backoff(&url, Duration::from_millis(10)).unwrap();
+        webhook.send(json!({ "first_name": "Anna" }));
+        assert_eq!(server.join().unwrap(), ATTEMPTS as usize);
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while webhook.failures() == 0 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(webhook.failures(), 1);
+    }
+
+    #[test]
+    fn post_sends_the_json_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 256];
+            while !request.ends_with(b"}") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        post(&agent, &format!("http://127.0.0.1:{}/hook", port), &json!({ "first_name": "Anna" })).unwrap();
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1"), "{}", request);
+        assert!(request.to_ascii_lowercase().contains("content-type: application/json"), "{}", request);
+        assert!(request.ends_with(r#"{"first_name":"Anna"}"#), "{}", request);
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use try_2::db::{Database, NotifyStatus, UserStore};
+use try_2::draw::{WinnerMode, parse_targets};
+use try_2::export::{ExportColumn, winner_emails, write_excel};
+use try_2::form::find_duplicates;
+use try_2::testdata::{TestDistribution, generate_test_users};
+
+/// Path in the temp dir, removed again when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("winter_raten_smoke_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        TempFile(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn register_draw_and_export() {
+    let db = Database::open(Path::new(":memory:")).unwrap();
+    for (i, number) in [150, 290, 310, 300, 1000].into_iter().enumerate() {
+        let (_, ticket) = db.insert_user("Guest", &i.to_string(), &format!("guest{}@example.com", i), number).unwrap();
+        assert_eq!(ticket, i as i32 + 1);
+    }
+
+    let targets = parse_targets("300", 3).unwrap();
+    db.calculate_winners(&targets, false, WinnerMode::Closest, 0).unwrap();
+    let users = db.get_sorted_users(300).unwrap();
+    let winners: Vec<i32> = users.iter().filter(|u| u.winner).map(|u| u.number).collect();
+    assert_eq!(winners, vec![300, 290, 310]);
+    assert_eq!(winner_emails(&users), "guest3@example.com, guest1@example.com, guest2@example.com");
+
+    let file = TempFile::new("export.xlsx");
+    let message = write_excel(&users, &db.get_prizes().unwrap(), &ExportColumn::ALL, file.0.to_str().unwrap()).unwrap();
+    assert!(message.contains("Exported 5 users"), "{}", message);
+    assert!(std::fs::metadata(&file.0).unwrap().len() > 0);
+}
+
+#[test]
+fn weighted_draw_repeats_with_the_same_seed() {
+    let db = Database::open(Path::new(":memory:")).unwrap();
+    let users = generate_test_users(&mut StdRng::seed_from_u64(3), 200, 1, TestDistribution::Normal, 1..=500);
+    assert_eq!(db.insert_users_batch(&users).unwrap().inserted(), 200);
+
+    let pick = || {
+        db.calculate_winners(&[250; 5], false, WinnerMode::WeightedRandom, 42).unwrap();
+        db.get_sorted_users(250).unwrap().into_iter().filter(|u| u.winner).map(|u| u.id).collect::<Vec<_>>()
+    };
+    let first = pick();
+    assert_eq!(first.len(), 5);
+    assert_eq!(pick(), first);
+}
+
+#[test]
+fn winners_survive_reopening_the_file() {
+    let file = TempFile::new("reopen.db");
+    {
+        let db = Database::open(&file.0).unwrap();
+        let (id, _) = db.insert_user("Anna", "Schmidt", "anna@example.com", 42).unwrap();
+        db.insert_user("Anna", "Schmit", "anna+raffle@example.com", 40).unwrap();
+        db.calculate_winners(&[42], false, WinnerMode::Closest, 0).unwrap();
+        db.set_notified(id, NotifyStatus::Confirmed).unwrap();
+    }
+
+    let db = Database::open(&file.0).unwrap();
+    assert_eq!(db.notification_summary().unwrap(), (1, 1));
+    assert_eq!(find_duplicates(&db.get_all_users().unwrap(), 2).len(), 1);
+}